@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP INDEX tags_for_job_weight_idx;
+
+ALTER TABLE tags_for_job
+    DROP COLUMN hits,
+    DROP COLUMN weight,
+    DROP COLUMN in_title,
+    DROP COLUMN match_count;
\ No newline at end of file
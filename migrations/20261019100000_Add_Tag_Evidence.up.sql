@@ -0,0 +1,8 @@
+-- Add up migration script here
+ALTER TABLE tags_for_job
+    ADD COLUMN match_count integer NOT NULL DEFAULT 0,
+    ADD COLUMN in_title boolean NOT NULL DEFAULT false,
+    ADD COLUMN weight double precision NOT NULL DEFAULT 0,
+    ADD COLUMN hits jsonb NOT NULL DEFAULT '[]';
+
+CREATE INDEX tags_for_job_weight_idx ON tags_for_job (job_tag_id, weight DESC);
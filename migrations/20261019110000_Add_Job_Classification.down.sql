@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP INDEX job_language_idx;
+DROP INDEX job_employment_type_idx;
+DROP INDEX job_seniority_idx;
+
+ALTER TABLE job
+    DROP COLUMN language,
+    DROP COLUMN employment_type,
+    DROP COLUMN seniority;
\ No newline at end of file
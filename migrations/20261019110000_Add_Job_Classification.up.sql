@@ -0,0 +1,9 @@
+-- Add up migration script here
+ALTER TABLE job
+    ADD COLUMN seniority varchar(32),
+    ADD COLUMN employment_type varchar(32),
+    ADD COLUMN language varchar(8);
+
+CREATE INDEX job_seniority_idx ON job (seniority);
+CREATE INDEX job_employment_type_idx ON job (employment_type);
+CREATE INDEX job_language_idx ON job (language);
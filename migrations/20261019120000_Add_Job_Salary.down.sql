@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP INDEX job_salary_monthly_idx;
+
+ALTER TABLE job
+    DROP COLUMN salary_monthly_max,
+    DROP COLUMN salary_monthly_min,
+    DROP COLUMN salary_negotiable,
+    DROP COLUMN salary_period,
+    DROP COLUMN salary_currency,
+    DROP COLUMN salary_max,
+    DROP COLUMN salary_min;
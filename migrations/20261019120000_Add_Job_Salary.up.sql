@@ -0,0 +1,12 @@
+-- Add up migration script here
+ALTER TABLE job
+    ADD COLUMN salary_min integer,
+    ADD COLUMN salary_max integer,
+    ADD COLUMN salary_currency varchar(3),
+    ADD COLUMN salary_period varchar(8),
+    ADD COLUMN salary_negotiable boolean NOT NULL DEFAULT false,
+    -- the range normalised to DKK per month, for filtering across ads
+    ADD COLUMN salary_monthly_min integer,
+    ADD COLUMN salary_monthly_max integer;
+
+CREATE INDEX job_salary_monthly_idx ON job (salary_monthly_min, salary_monthly_max);
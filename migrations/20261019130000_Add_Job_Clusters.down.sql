@@ -0,0 +1,12 @@
+-- Add down migration script here
+DROP INDEX job_company_id_idx;
+DROP INDEX job_cluster_id_idx;
+
+ALTER TABLE job
+    DROP CONSTRAINT job_cluster_fk,
+    DROP COLUMN simhash,
+    DROP COLUMN dedup_locations,
+    DROP COLUMN dedup_title,
+    DROP COLUMN cluster_id;
+
+DROP TABLE job_cluster;
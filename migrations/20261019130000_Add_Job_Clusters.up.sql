@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE job_cluster (
+    id bigserial PRIMARY KEY,
+    canonical_job_id bigint,
+    CONSTRAINT canonical_job_fk FOREIGN KEY (canonical_job_id)
+    REFERENCES job(id)
+);
+
+ALTER TABLE job
+    ADD COLUMN cluster_id bigint,
+    -- what reposts are recognised by, see JobFingerprint
+    ADD COLUMN dedup_title text,
+    ADD COLUMN dedup_locations text[],
+    ADD COLUMN simhash bigint,
+    ADD CONSTRAINT job_cluster_fk FOREIGN KEY (cluster_id)
+    REFERENCES job_cluster(id);
+
+CREATE INDEX job_cluster_id_idx ON job (cluster_id);
+CREATE INDEX job_company_id_idx ON job (company_id);
@@ -0,0 +1,19 @@
+-- Add down migration script here
+DROP INDEX job_last_date_idx;
+DROP INDEX job_created_at_idx;
+
+ALTER TABLE job
+    DROP COLUMN last_date,
+    DROP COLUMN created_at;
+
+DROP TABLE company_alias;
+
+ALTER TABLE company
+    DROP CONSTRAINT company_normalised_name_key,
+    DROP COLUMN rating_count,
+    DROP COLUMN rating_score,
+    DROP COLUMN profile_url,
+    DROP COLUMN normalised_name,
+    ADD CONSTRAINT company_name_key UNIQUE (name),
+    ALTER COLUMN id DROP DEFAULT;
+DROP SEQUENCE company_id_seq;
@@ -0,0 +1,28 @@
+-- Add up migration script here
+-- company.id had no default, so no company could be inserted and there are
+-- no rows to give a normalised name.
+CREATE SEQUENCE company_id_seq OWNED BY company.id;
+ALTER TABLE company
+    ALTER COLUMN id SET DEFAULT nextval('company_id_seq'),
+    DROP CONSTRAINT company_name_key,
+    -- see company_key, this is what companies are told apart by
+    ADD COLUMN normalised_name varchar(255) NOT NULL,
+    ADD COLUMN profile_url varchar(1000),
+    ADD COLUMN rating_score double precision,
+    ADD COLUMN rating_count integer,
+    ADD CONSTRAINT company_normalised_name_key UNIQUE (normalised_name);
+
+-- every name a company has been posted under
+CREATE TABLE company_alias (
+    name varchar(255) PRIMARY KEY,
+    company_id bigint NOT NULL,
+    CONSTRAINT company_fk FOREIGN KEY (company_id)
+    REFERENCES company(id)
+);
+
+ALTER TABLE job
+    ADD COLUMN created_at timestamptz,
+    ADD COLUMN last_date timestamptz;
+
+CREATE INDEX job_created_at_idx ON job (created_at);
+CREATE INDEX job_last_date_idx ON job (last_date);
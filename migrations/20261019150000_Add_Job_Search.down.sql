@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX job_search_vector_idx;
+
+ALTER TABLE job DROP COLUMN search_vector;
+
+DROP FUNCTION job_search_vector(varchar, text, text, text);
+DROP FUNCTION job_search_config(varchar);
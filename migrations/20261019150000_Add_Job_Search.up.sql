@@ -0,0 +1,31 @@
+-- Add up migration script here
+-- the text search configuration of a job's detected language
+CREATE FUNCTION job_search_config(language varchar) RETURNS regconfig
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    RETURN CASE language
+        WHEN 'da' THEN 'danish'::regconfig
+        WHEN 'en' THEN 'english'::regconfig
+        ELSE 'simple'::regconfig
+    END;
+
+CREATE FUNCTION job_search_vector(
+    language varchar,
+    title text,
+    description text,
+    company text
+) RETURNS tsvector
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    RETURN setweight(to_tsvector(job_search_config(language), coalesce(title, '')), 'A')
+        || setweight(to_tsvector(job_search_config(language), coalesce(company, '')), 'B')
+        || setweight(to_tsvector(job_search_config(language), coalesce(description, '')), 'C');
+
+ALTER TABLE job ADD COLUMN search_vector tsvector;
+
+UPDATE job SET search_vector = job_search_vector(
+    job.language,
+    job.title,
+    job.description,
+    (SELECT company.name FROM company WHERE company.id = job.company_id)
+);
+
+CREATE INDEX job_search_vector_idx ON job USING GIN (search_vector);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX job_remote_idx;
+
+ALTER TABLE job
+    DROP COLUMN remote;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE job
+    ADD COLUMN remote boolean NOT NULL DEFAULT false;
+
+CREATE INDEX job_remote_idx ON job (remote);
@@ -0,0 +1,25 @@
+-- Add down migration script here
+DROP INDEX job_location_x_y_idx;
+DROP INDEX job_location_earth_idx;
+
+-- the addresses that shared a point with another are lost
+DELETE FROM location_for_job
+WHERE location_id IN (
+    SELECT id FROM job_location AS later
+    WHERE EXISTS (
+        SELECT 1 FROM job_location AS earlier
+        WHERE earlier.x = later.x AND earlier.y = later.y AND earlier.id < later.id
+    )
+);
+DELETE FROM job_location AS later
+WHERE EXISTS (
+    SELECT 1 FROM job_location AS earlier
+    WHERE earlier.x = later.x AND earlier.y = later.y AND earlier.id < later.id
+);
+
+ALTER TABLE job_location
+    DROP CONSTRAINT job_location_address_x_y_key,
+    ADD CONSTRAINT job_location_x_y_key UNIQUE (x, y);
+
+DROP EXTENSION earthdistance;
+DROP EXTENSION cube;
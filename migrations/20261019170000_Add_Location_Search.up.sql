@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+
+-- x is the latitude and y the longitude. Different addresses can share a
+-- point, like the offices of a building.
+ALTER TABLE job_location
+    DROP CONSTRAINT job_location_x_y_key,
+    ADD CONSTRAINT job_location_address_x_y_key UNIQUE NULLS NOT DISTINCT (address, x, y);
+
+CREATE INDEX job_location_earth_idx ON job_location USING gist (ll_to_earth(x, y));
+CREATE INDEX job_location_x_y_idx ON job_location (x, y);
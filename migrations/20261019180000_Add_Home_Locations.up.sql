@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE home_location (
+    id bigserial PRIMARY KEY,
+    name varchar(255) NOT NULL UNIQUE,
+    address text,
+    -- like job_location, x is the latitude and y the longitude
+    x double precision NOT NULL,
+    y double precision NOT NULL,
+    commute_mode varchar(16) NOT NULL DEFAULT 'car'
+);
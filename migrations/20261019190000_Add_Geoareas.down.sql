@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE geoarea_for_job;
+DROP TABLE geoarea;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+-- the areas of JobIndex, by their geoareaid, like "Region Midtjylland" or
+-- "8200 Aarhus N"
+CREATE TABLE geoarea (
+    id bigint PRIMARY KEY,
+    name varchar(255) NOT NULL,
+    code varchar(255),
+    kind varchar(16) NOT NULL,
+    -- the smallest larger area it lies in, as seen from the jobs in both
+    parent_id bigint REFERENCES geoarea(id) ON DELETE SET NULL
+);
+CREATE INDEX geoarea_name_idx ON geoarea (lower(name));
+CREATE INDEX geoarea_parent_id_idx ON geoarea (parent_id);
+
+-- every area a job lies in, also the ones not in the catalogue yet
+CREATE TABLE geoarea_for_job (
+    job_id bigint REFERENCES job(id) ON DELETE CASCADE,
+    geoarea_id bigint,
+
+    CONSTRAINT geoarea_for_job_pk PRIMARY KEY (job_id, geoarea_id)
+);
+CREATE INDEX geoarea_for_job_geoarea_id_idx ON geoarea_for_job (geoarea_id);
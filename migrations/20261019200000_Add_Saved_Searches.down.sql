@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE notification;
+DROP TABLE saved_search_match;
+DROP TABLE saved_search;
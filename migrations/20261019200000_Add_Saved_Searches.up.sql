@@ -0,0 +1,34 @@
+-- Add up migration script here
+CREATE TABLE saved_search (
+    id bigserial PRIMARY KEY,
+    owner varchar(255) NOT NULL,
+    name varchar(255) NOT NULL,
+    -- the QueryOptions and FilterOptions, as serialised by serde
+    query_options jsonb NOT NULL,
+    filter_options jsonb NOT NULL,
+    -- where to send new matches, see NotifyTarget
+    notify jsonb NOT NULL DEFAULT '[]',
+    created_at timestamptz NOT NULL DEFAULT now(),
+
+    CONSTRAINT saved_search_owner_name_key UNIQUE (owner, name)
+);
+
+-- the jobs a saved search has matched, so each is only notified once
+CREATE TABLE saved_search_match (
+    saved_search_id bigint REFERENCES saved_search(id) ON DELETE CASCADE,
+    job_id bigint REFERENCES job(id) ON DELETE CASCADE,
+    matched_at timestamptz NOT NULL DEFAULT now(),
+
+    CONSTRAINT saved_search_match_pk PRIMARY KEY (saved_search_id, job_id)
+);
+
+-- the in-app feed
+CREATE TABLE notification (
+    id bigserial PRIMARY KEY,
+    owner varchar(255) NOT NULL,
+    saved_search_id bigint NOT NULL REFERENCES saved_search(id) ON DELETE CASCADE,
+    job_id bigint NOT NULL REFERENCES job(id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    read_at timestamptz
+);
+CREATE INDEX notification_owner_idx ON notification (owner, created_at DESC);
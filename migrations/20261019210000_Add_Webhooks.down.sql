@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE job DROP COLUMN expired_at;
+DROP TABLE webhook_delivery;
+DROP TABLE webhook;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+CREATE TABLE webhook (
+    id bigserial PRIMARY KEY,
+    url text NOT NULL,
+    -- the HMAC key the payloads are signed with
+    secret varchar(255) NOT NULL,
+    -- the JobEvent names it is sent, every event when empty
+    events varchar(16)[] NOT NULL DEFAULT '{}',
+    active boolean NOT NULL DEFAULT true,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- the outbox of webhooks, written in the same transaction as the change the
+-- event is about. Deliveries that ran out of attempts are the dead letters.
+CREATE TABLE webhook_delivery (
+    id bigserial PRIMARY KEY,
+    webhook_id bigint NOT NULL REFERENCES webhook(id) ON DELETE CASCADE,
+    event varchar(16) NOT NULL,
+    -- not a foreign key, the event of a deleted job outlives it
+    job_id bigint NOT NULL,
+    -- exactly the bytes that are signed and sent
+    payload text NOT NULL,
+    status varchar(16) NOT NULL DEFAULT 'pending',
+    attempts integer NOT NULL DEFAULT 0,
+    next_attempt_at timestamptz NOT NULL DEFAULT now(),
+    last_status integer,
+    last_error text,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    delivered_at timestamptz
+);
+CREATE INDEX webhook_delivery_due_idx ON webhook_delivery (next_attempt_at)
+    WHERE status = 'pending';
+CREATE INDEX webhook_delivery_webhook_id_idx ON webhook_delivery (webhook_id, created_at DESC);
+CREATE INDEX webhook_delivery_dead_idx ON webhook_delivery (created_at DESC)
+    WHERE status = 'dead';
+
+-- when the expired event was sent, so it is only sent once
+ALTER TABLE job ADD COLUMN expired_at timestamptz;
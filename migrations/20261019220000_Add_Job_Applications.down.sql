@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE job_application_reminder;
+DROP TABLE job_application_document;
+DROP TABLE job_application_status_change;
+DROP TABLE job_application;
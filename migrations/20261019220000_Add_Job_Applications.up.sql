@@ -0,0 +1,53 @@
+-- Add up migration script here
+CREATE TABLE job_application (
+    id bigserial PRIMARY KEY,
+    owner varchar(255) NOT NULL,
+    job_id bigint NOT NULL REFERENCES job(id) ON DELETE CASCADE,
+    -- the ApplicationStatus name
+    status varchar(16) NOT NULL DEFAULT 'interested',
+    notes text NOT NULL DEFAULT '',
+    created_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    -- when it was sent, which is kept when it is rejected later
+    applied_at timestamptz,
+
+    CONSTRAINT job_application_owner_job_key UNIQUE (owner, job_id)
+);
+CREATE INDEX job_application_owner_status_idx ON job_application (owner, status);
+
+-- every status an application has been in, the first without from_status
+CREATE TABLE job_application_status_change (
+    id bigserial PRIMARY KEY,
+    application_id bigint NOT NULL REFERENCES job_application(id) ON DELETE CASCADE,
+    from_status varchar(16),
+    to_status varchar(16) NOT NULL,
+    changed_at timestamptz NOT NULL DEFAULT now()
+);
+CREATE INDEX job_application_status_change_application_id_idx
+    ON job_application_status_change (application_id, changed_at);
+
+CREATE TABLE job_application_document (
+    id bigserial PRIMARY KEY,
+    application_id bigint NOT NULL REFERENCES job_application(id) ON DELETE CASCADE,
+    -- the DocumentKind name
+    kind varchar(16) NOT NULL,
+    name varchar(255) NOT NULL,
+    content_type varchar(255) NOT NULL,
+    content bytea NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+CREATE INDEX job_application_document_application_id_idx
+    ON job_application_document (application_id);
+
+CREATE TABLE job_application_reminder (
+    id bigserial PRIMARY KEY,
+    application_id bigint NOT NULL REFERENCES job_application(id) ON DELETE CASCADE,
+    remind_at timestamptz NOT NULL,
+    note text NOT NULL DEFAULT '',
+    done_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+CREATE INDEX job_application_reminder_application_id_idx
+    ON job_application_reminder (application_id);
+CREATE INDEX job_application_reminder_due_idx
+    ON job_application_reminder (remind_at) WHERE done_at IS NULL;
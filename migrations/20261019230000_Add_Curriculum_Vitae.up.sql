@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE cv (
+    owner varchar(255) PRIMARY KEY,
+    content jsonb NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
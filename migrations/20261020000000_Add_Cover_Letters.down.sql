@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE cover_letter_template;
+
+ALTER TABLE job DROP COLUMN contact_email;
+ALTER TABLE job DROP COLUMN contact_phone;
+ALTER TABLE job DROP COLUMN contact_name;
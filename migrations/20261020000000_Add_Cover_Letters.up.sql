@@ -0,0 +1,13 @@
+-- Add up migration script here
+ALTER TABLE job ADD COLUMN contact_name text;
+ALTER TABLE job ADD COLUMN contact_phone text;
+ALTER TABLE job ADD COLUMN contact_email text;
+
+CREATE TABLE cover_letter_template (
+    id bigserial PRIMARY KEY,
+    owner varchar(255) NOT NULL,
+    name varchar(255) NOT NULL,
+    body text NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (owner, name)
+);
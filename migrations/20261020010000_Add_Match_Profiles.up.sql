@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE match_profile (
+    owner varchar(255) PRIMARY KEY,
+    skills text[] NOT NULL DEFAULT '{}',
+    seniority varchar(16),
+    home varchar(255) REFERENCES home_location (name) ON UPDATE CASCADE ON DELETE SET NULL,
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE api_token;
+DROP TABLE app_user;
@@ -0,0 +1,21 @@
+-- Add up migration script here
+-- `user` is a reserved word
+CREATE TABLE app_user (
+    id bigserial PRIMARY KEY,
+    -- what the personal data of the user is owned by
+    username varchar(255) NOT NULL UNIQUE,
+    -- in the PHC string format of argon2
+    password_hash text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- tokens for scripts, of which only a hash is kept
+CREATE TABLE api_token (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES app_user(id) ON DELETE CASCADE,
+    name varchar(255) NOT NULL,
+    token_hash char(64) NOT NULL UNIQUE,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    last_used_at timestamptz
+);
+CREATE INDEX api_token_user_idx ON api_token (user_id);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP FUNCTION job_is_hidden;
+DROP TABLE hidden_company;
+DROP TABLE job_mark;
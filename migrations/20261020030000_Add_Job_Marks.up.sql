@@ -0,0 +1,37 @@
+-- Add up migration script here
+-- what a user has starred, hidden or noted of a job; a row that marks
+-- nothing is deleted
+CREATE TABLE job_mark (
+    user_id bigint NOT NULL REFERENCES app_user(id) ON DELETE CASCADE,
+    job_id bigint NOT NULL REFERENCES job(id) ON DELETE CASCADE,
+    starred boolean NOT NULL DEFAULT false,
+    hidden boolean NOT NULL DEFAULT false,
+    note text,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+
+    CONSTRAINT job_mark_pk PRIMARY KEY (user_id, job_id)
+);
+
+-- the companies a user hides every job of
+CREATE TABLE hidden_company (
+    user_id bigint NOT NULL REFERENCES app_user(id) ON DELETE CASCADE,
+    company_id bigint NOT NULL REFERENCES company(id) ON DELETE CASCADE,
+    hidden_at timestamptz NOT NULL DEFAULT now(),
+
+    CONSTRAINT hidden_company_pk PRIMARY KEY (user_id, company_id)
+);
+
+-- whether the user has hidden the job or its company
+CREATE FUNCTION job_is_hidden(user_id bigint, job_id bigint, company_id bigint)
+    RETURNS boolean
+    LANGUAGE sql STABLE PARALLEL SAFE
+    RETURN EXISTS (
+        SELECT 1 FROM job_mark
+        WHERE job_mark.user_id = job_is_hidden.user_id
+        AND job_mark.job_id = job_is_hidden.job_id
+        AND job_mark.hidden
+    ) OR EXISTS (
+        SELECT 1 FROM hidden_company
+        WHERE hidden_company.user_id = job_is_hidden.user_id
+        AND hidden_company.company_id = job_is_hidden.company_id
+    );
@@ -0,0 +1,3 @@
+-- Add down migration script here
+-- which job was canonical before is not kept, and the newest one is as
+-- good a canonical job under the old rule
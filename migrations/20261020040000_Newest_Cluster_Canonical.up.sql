@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- the newest job of a cluster is its canonical job, also where an older
+-- posting was stored after it
+UPDATE job_cluster SET canonical_job_id = (
+    SELECT job.id FROM job
+    WHERE job.cluster_id = job_cluster.id
+    ORDER BY job.created_at DESC NULLS LAST, job.id DESC
+    LIMIT 1
+);
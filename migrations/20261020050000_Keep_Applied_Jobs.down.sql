@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE job_application
+    DROP CONSTRAINT job_application_job_id_fkey,
+    ADD CONSTRAINT job_application_job_id_fkey
+        FOREIGN KEY (job_id) REFERENCES job(id) ON DELETE CASCADE;
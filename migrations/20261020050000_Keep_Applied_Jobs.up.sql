@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- an application keeps its job, so deleting jobs can not take the history,
+-- documents and reminders of an application with it
+ALTER TABLE job_application
+    DROP CONSTRAINT job_application_job_id_fkey,
+    ADD CONSTRAINT job_application_job_id_fkey
+        FOREIGN KEY (job_id) REFERENCES job(id) ON DELETE RESTRICT;
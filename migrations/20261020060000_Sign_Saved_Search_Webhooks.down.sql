@@ -0,0 +1,7 @@
+-- Add down migration script here
+UPDATE saved_search
+SET notify = (
+    SELECT coalesce(jsonb_agg(target - 'secret'), '[]')
+    FROM jsonb_array_elements(notify) AS target
+)
+WHERE notify @> '[{"kind": "webhook"}]';
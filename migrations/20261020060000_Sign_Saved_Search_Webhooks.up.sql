@@ -0,0 +1,13 @@
+-- Add up migration script here
+-- webhook targets of saved searches are signed with a secret of their own
+-- now, so the targets stored without one are dropped
+UPDATE saved_search
+SET notify = (
+    SELECT coalesce(jsonb_agg(target), '[]')
+    FROM jsonb_array_elements(notify) AS target
+    WHERE target->>'kind' <> 'webhook' OR target ? 'secret'
+)
+WHERE EXISTS (
+    SELECT FROM jsonb_array_elements(notify) AS target
+    WHERE target->>'kind' = 'webhook' AND NOT target ? 'secret'
+);
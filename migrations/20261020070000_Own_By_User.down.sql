@@ -0,0 +1,48 @@
+-- Add down migration script here
+ALTER TABLE match_profile ADD COLUMN owner varchar(255);
+UPDATE match_profile SET owner = app_user.username
+FROM app_user WHERE app_user.id = match_profile.user_id;
+ALTER TABLE match_profile
+    DROP COLUMN user_id,
+    ADD PRIMARY KEY (owner);
+
+ALTER TABLE cover_letter_template ADD COLUMN owner varchar(255);
+UPDATE cover_letter_template SET owner = app_user.username
+FROM app_user WHERE app_user.id = cover_letter_template.user_id;
+ALTER TABLE cover_letter_template
+    ALTER COLUMN owner SET NOT NULL,
+    DROP COLUMN user_id,
+    ADD CONSTRAINT cover_letter_template_owner_name_key UNIQUE (owner, name);
+
+ALTER TABLE cv ADD COLUMN owner varchar(255);
+UPDATE cv SET owner = app_user.username
+FROM app_user WHERE app_user.id = cv.user_id;
+ALTER TABLE cv
+    DROP COLUMN user_id,
+    ADD PRIMARY KEY (owner);
+
+ALTER TABLE job_application ADD COLUMN owner varchar(255);
+UPDATE job_application SET owner = app_user.username
+FROM app_user WHERE app_user.id = job_application.user_id;
+ALTER TABLE job_application
+    ALTER COLUMN owner SET NOT NULL,
+    DROP COLUMN user_id,
+    ADD CONSTRAINT job_application_owner_job_key UNIQUE (owner, job_id);
+CREATE INDEX job_application_owner_status_idx
+    ON job_application (owner, status);
+
+ALTER TABLE notification ADD COLUMN owner varchar(255);
+UPDATE notification SET owner = app_user.username
+FROM app_user WHERE app_user.id = notification.user_id;
+ALTER TABLE notification
+    ALTER COLUMN owner SET NOT NULL,
+    DROP COLUMN user_id;
+CREATE INDEX notification_owner_idx ON notification (owner, created_at DESC);
+
+ALTER TABLE saved_search ADD COLUMN owner varchar(255);
+UPDATE saved_search SET owner = app_user.username
+FROM app_user WHERE app_user.id = saved_search.user_id;
+ALTER TABLE saved_search
+    ALTER COLUMN owner SET NOT NULL,
+    DROP COLUMN user_id,
+    ADD CONSTRAINT saved_search_owner_name_key UNIQUE (owner, name);
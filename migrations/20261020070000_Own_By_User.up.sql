@@ -0,0 +1,68 @@
+-- Add up migration script here
+-- personal data is owned by an app_user instead of by a username, so it goes
+-- with its user. Owners from before there were accounts get one that can not
+-- sign in, '!' being no PHC string, so their data is kept.
+INSERT INTO app_user (username, password_hash)
+SELECT owner, '!' FROM (
+    SELECT owner FROM saved_search
+    UNION SELECT owner FROM notification
+    UNION SELECT owner FROM job_application
+    UNION SELECT owner FROM cv
+    UNION SELECT owner FROM cover_letter_template
+    UNION SELECT owner FROM match_profile
+) AS owners
+ON CONFLICT (username) DO NOTHING;
+
+ALTER TABLE saved_search
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE saved_search SET user_id = app_user.id
+FROM app_user WHERE app_user.username = saved_search.owner;
+ALTER TABLE saved_search
+    ALTER COLUMN user_id SET NOT NULL,
+    DROP COLUMN owner,
+    ADD CONSTRAINT saved_search_user_name_key UNIQUE (user_id, name);
+
+ALTER TABLE notification
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE notification SET user_id = app_user.id
+FROM app_user WHERE app_user.username = notification.owner;
+ALTER TABLE notification
+    ALTER COLUMN user_id SET NOT NULL,
+    DROP COLUMN owner;
+CREATE INDEX notification_user_idx ON notification (user_id, created_at DESC);
+
+ALTER TABLE job_application
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE job_application SET user_id = app_user.id
+FROM app_user WHERE app_user.username = job_application.owner;
+ALTER TABLE job_application
+    ALTER COLUMN user_id SET NOT NULL,
+    DROP COLUMN owner,
+    ADD CONSTRAINT job_application_user_job_key UNIQUE (user_id, job_id);
+CREATE INDEX job_application_user_status_idx
+    ON job_application (user_id, status);
+
+ALTER TABLE cv
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE cv SET user_id = app_user.id
+FROM app_user WHERE app_user.username = cv.owner;
+ALTER TABLE cv
+    DROP COLUMN owner,
+    ADD PRIMARY KEY (user_id);
+
+ALTER TABLE cover_letter_template
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE cover_letter_template SET user_id = app_user.id
+FROM app_user WHERE app_user.username = cover_letter_template.owner;
+ALTER TABLE cover_letter_template
+    ALTER COLUMN user_id SET NOT NULL,
+    DROP COLUMN owner,
+    ADD CONSTRAINT cover_letter_template_user_name_key UNIQUE (user_id, name);
+
+ALTER TABLE match_profile
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+UPDATE match_profile SET user_id = app_user.id
+FROM app_user WHERE app_user.username = match_profile.owner;
+ALTER TABLE match_profile
+    DROP COLUMN owner,
+    ADD PRIMARY KEY (user_id);
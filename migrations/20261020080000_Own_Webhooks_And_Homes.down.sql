@@ -0,0 +1,16 @@
+-- Add down migration script here
+-- homes are shared again, keeping the first one of each name
+ALTER TABLE match_profile DROP CONSTRAINT match_profile_home_fkey;
+DELETE FROM home_location
+WHERE id NOT IN (SELECT min(id) FROM home_location GROUP BY name);
+ALTER TABLE home_location
+    DROP COLUMN user_id,
+    ADD CONSTRAINT home_location_name_key UNIQUE (name);
+ALTER TABLE match_profile
+    ADD CONSTRAINT match_profile_home_fkey FOREIGN KEY (home)
+        REFERENCES home_location (name)
+        ON UPDATE CASCADE ON DELETE SET NULL;
+
+ALTER TABLE webhook DROP COLUMN user_id;
+
+ALTER TABLE app_user DROP COLUMN admin;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- admins run what is done for everyone, like syncing the regions
+ALTER TABLE app_user ADD COLUMN admin boolean NOT NULL DEFAULT false;
+
+-- webhooks were registered without an account, so none of them can be told
+-- to be of a user, and they are dropped with their deliveries
+DELETE FROM webhook;
+ALTER TABLE webhook
+    ADD COLUMN user_id bigint NOT NULL REFERENCES app_user(id) ON DELETE CASCADE;
+CREATE INDEX webhook_user_idx ON webhook (user_id);
+
+-- home locations were shared. Each user whose profile measures from one
+-- gets a copy of their own, and the homes nobody uses are dropped.
+ALTER TABLE match_profile DROP CONSTRAINT match_profile_home_fkey;
+ALTER TABLE home_location
+    DROP CONSTRAINT home_location_name_key,
+    ADD COLUMN user_id bigint REFERENCES app_user(id) ON DELETE CASCADE;
+INSERT INTO home_location (user_id, name, address, x, y, commute_mode)
+SELECT match_profile.user_id, home_location.name, home_location.address,
+    home_location.x, home_location.y, home_location.commute_mode
+FROM match_profile
+INNER JOIN home_location ON home_location.name = match_profile.home;
+DELETE FROM home_location WHERE user_id IS NULL;
+ALTER TABLE home_location
+    ALTER COLUMN user_id SET NOT NULL,
+    ADD CONSTRAINT home_location_user_name_key UNIQUE (user_id, name);
+ALTER TABLE match_profile
+    ADD CONSTRAINT match_profile_home_fkey FOREIGN KEY (user_id, home)
+        REFERENCES home_location (user_id, name)
+        ON UPDATE CASCADE ON DELETE SET NULL (home);
@@ -0,0 +1,269 @@
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum_extra::extract::Query;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::application_service::board;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    ApplicationDocument, ApplicationStatus, DocumentKind, JobApplication,
+    JobApplications, Reminder, User,
+};
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApplicationBody {
+    job_id: i64,
+    #[serde(default = "default_status")]
+    status: ApplicationStatus,
+    #[serde(default)]
+    notes: String,
+}
+
+fn default_status() -> ApplicationStatus {
+    ApplicationStatus::Interested
+}
+
+/// What to change of an application, leaving out what stays.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApplicationPatch {
+    status: Option<ApplicationStatus>,
+    notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocumentQuery {
+    name: String,
+    #[serde(default = "default_kind")]
+    kind: DocumentKind,
+}
+
+fn default_kind() -> DocumentKind {
+    DocumentKind::Other
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReminderBody {
+    remind_at: DateTime<Utc>,
+    #[serde(default)]
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReminderQuery {
+    /// Only the reminders that are due now.
+    #[serde(default)]
+    due: bool,
+}
+
+fn not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "not found")
+}
+
+async fn get_application(
+    database: &DataBase,
+    user_id: i64,
+    id: i64,
+) -> Result<JobApplication, ApiError> {
+    database
+        .get_applications(user_id, Some(id))
+        .await?
+        .pop()
+        .ok_or_else(not_found)
+}
+
+/// The applications of the user as a Kanban board.
+pub async fn list_applications(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<JobApplications>, ApiError> {
+    let applications = database.get_applications(user.id, None).await?;
+    Ok(Json(board(applications)))
+}
+
+pub async fn create_application(
+    State(database): State<DataBase>,
+    user: User,
+    Json(body): Json<ApplicationBody>,
+) -> Result<(StatusCode, Json<JobApplication>), ApiError> {
+    let id = database
+        .insert_application(user.id, body.job_id, body.status, &body.notes)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such job"))?;
+    let application = get_application(&database, user.id, id).await?;
+    Ok((StatusCode::CREATED, Json(application)))
+}
+
+pub async fn show_application(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<Json<JobApplication>, ApiError> {
+    Ok(Json(get_application(&database, user.id, id).await?))
+}
+
+/// Moves the application on in the workflow and writes its notes. A move
+/// the workflow does not allow is a conflict.
+pub async fn update_application(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+    Json(patch): Json<ApplicationPatch>,
+) -> Result<Json<JobApplication>, ApiError> {
+    let application = get_application(&database, user.id, id).await?;
+    if let Some(status) = patch.status.filter(|&s| s != application.status) {
+        if !application.status.can_move_to(status) {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                format!(
+                    "cannot go from {} to {}",
+                    application.status.as_str(),
+                    status.as_str()
+                ),
+            ));
+        }
+        let moved = database
+            .set_application_status(user.id, id, application.status, status)
+            .await?;
+        if !moved {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "the status changed meanwhile",
+            ));
+        }
+    }
+    if let Some(notes) = &patch.notes {
+        database.set_application_notes(user.id, id, notes).await?;
+    }
+    Ok(Json(get_application(&database, user.id, id).await?))
+}
+
+pub async fn delete_application(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_application(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
+
+/// Attaches the body as a document, with the content type it is sent with.
+pub async fn attach_document(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+    Query(query): Query<DocumentQuery>,
+    headers: HeaderMap,
+    content: Bytes,
+) -> Result<(StatusCode, Json<ApplicationDocument>), ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    if query.name.trim().is_empty() {
+        return Err(bad_request("name is empty"));
+    }
+    if content.is_empty() {
+        return Err(bad_request("the document is empty"));
+    }
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream");
+
+    let document = database
+        .insert_application_document(
+            user.id,
+            id,
+            query.kind,
+            &query.name,
+            content_type,
+            &content,
+        )
+        .await?
+        .ok_or_else(not_found)?;
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+pub async fn download_document(
+    State(database): State<DataBase>,
+    Path((id, document_id)): Path<(i64, i64)>,
+    user: User,
+) -> Result<([(axum::http::HeaderName, String); 2], Vec<u8>), ApiError> {
+    let (document, content) = database
+        .get_application_document(user.id, id, document_id)
+        .await?
+        .ok_or_else(not_found)?;
+    // a quote would end the file name early
+    let file_name = document.name.replace(['"', '\\'], "_");
+    Ok((
+        [
+            (CONTENT_TYPE, document.content_type),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        content,
+    ))
+}
+
+pub async fn delete_document(
+    State(database): State<DataBase>,
+    Path((id, document_id)): Path<(i64, i64)>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database
+        .delete_application_document(user.id, id, document_id)
+        .await?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
+
+pub async fn create_reminder(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+    Json(body): Json<ReminderBody>,
+) -> Result<(StatusCode, Json<Reminder>), ApiError> {
+    let reminder = database
+        .insert_reminder(user.id, id, body.remind_at, &body.note)
+        .await?
+        .ok_or_else(not_found)?;
+    Ok((StatusCode::CREATED, Json(reminder)))
+}
+
+/// The open follow-up reminders of the user, soonest first.
+pub async fn list_reminders(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<ReminderQuery>,
+) -> Result<Json<Vec<Reminder>>, ApiError> {
+    let due_by = query.due.then(Utc::now);
+    Ok(Json(database.get_reminders(user.id, due_by).await?))
+}
+
+pub async fn complete_reminder(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.complete_reminder(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
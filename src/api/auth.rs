@@ -0,0 +1,237 @@
+use std::convert::Infallible;
+
+use axum::Json;
+use axum::extract::{
+    FromRequestParts, OptionalFromRequestParts, Path, Request, State,
+};
+use axum::http::header::{AUTHORIZATION, COOKIE};
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::auth_service::{
+    API_TOKEN_PREFIX, MIN_PASSWORD_LEN, SESSION_KEYS, api_token_hash,
+    hash_password, new_api_token, session_from_cookies, verify_password,
+};
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    ApiToken, NewApiToken, Session, User,
+};
+use crate::util::options::ApiError;
+
+const MAX_USERNAME_LEN: usize = 255;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenBody {
+    /// What the token is for.
+    name: String,
+}
+
+fn unauthorized(message: &'static str) -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, message)
+}
+
+/// Puts the `User` of the bearer token of the request, a session or an API
+/// token, in its extensions. Without one, the session cookie of the web
+/// pages is used instead. A request without either goes on without a user,
+/// and is turned away by the handlers that need one.
+pub async fn authenticate(
+    State(database): State<DataBase>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(header) = request.headers().get(AUTHORIZATION) else {
+        // an expired cookie just signs the browser out, the pages show the
+        // login form to those that need it
+        let id = request
+            .headers()
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|cookies| cookies.to_str().ok())
+            .find_map(session_from_cookies)
+            .and_then(|token| SESSION_KEYS.user_id(token));
+        let user = match id {
+            Some(id) => database.get_user(id).await?,
+            None => None,
+        };
+        if let Some(user) = user {
+            request.extensions_mut().insert(user);
+        }
+        return Ok(next.run(request).await);
+    };
+    let token = header
+        .to_str()
+        .ok()
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| unauthorized("expected a bearer token"))?
+        .trim();
+
+    let user = if token.starts_with(API_TOKEN_PREFIX) {
+        database.use_api_token(&api_token_hash(token)).await?
+    } else {
+        match SESSION_KEYS.user_id(token) {
+            Some(id) => database.get_user(id).await?,
+            None => None,
+        }
+    };
+    let user = user.ok_or_else(|| unauthorized("invalid or expired token"))?;
+    request.extensions_mut().insert(user);
+    Ok(next.run(request).await)
+}
+
+/// The signed in user, see `authenticate`.
+impl<S: Send + Sync> FromRequestParts<S> for User {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<User>()
+            .cloned()
+            .ok_or_else(|| unauthorized("sign in first"))
+    }
+}
+
+/// A signed in user who is an admin, for the routes that do something for
+/// everyone, like syncing the regions or sending every saved search.
+pub struct Admin(pub User);
+
+impl<S: Send + Sync> FromRequestParts<S> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let user =
+            <User as FromRequestParts<S>>::from_request_parts(parts, state)
+                .await?;
+        if !user.admin {
+            return Err(ApiError::new(StatusCode::FORBIDDEN, "admins only"));
+        }
+        Ok(Admin(user))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for User {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<User>().cloned())
+    }
+}
+
+pub async fn register(
+    State(database): State<DataBase>,
+    Json(credentials): Json<Credentials>,
+) -> Result<(StatusCode, Json<User>), ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    let username = credentials.username;
+    if username.trim().is_empty() || username.trim() != username {
+        return Err(bad_request(
+            "username should not be empty or start or end in a space"
+                .to_owned(),
+        ));
+    }
+    if username.chars().count() > MAX_USERNAME_LEN {
+        return Err(bad_request(format!(
+            "username should be at most {MAX_USERNAME_LEN} characters"
+        )));
+    }
+    if credentials.password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(bad_request(format!(
+            "password should be at least {MIN_PASSWORD_LEN} characters"
+        )));
+    }
+
+    let password = credentials.password;
+    let password_hash =
+        tokio::task::spawn_blocking(move || hash_password(&password))
+            .await
+            .expect("hashing should not panic");
+    let user = database.insert_user(&username, &password_hash).await?;
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+/// The user of `credentials`, if the password is theirs.
+pub async fn verified_user(
+    database: &DataBase,
+    credentials: Credentials,
+) -> Result<Option<User>, ApiError> {
+    let login = database.get_login(&credentials.username).await?;
+    let (user, password_hash) = login.unzip();
+    let password = credentials.password;
+    let verified = tokio::task::spawn_blocking(move || {
+        verify_password(&password, password_hash.as_deref())
+    })
+    .await
+    .expect("verifying should not panic");
+
+    Ok(user.filter(|_| verified))
+}
+
+/// Starts a session, whose token is sent as `Authorization: Bearer <token>`.
+pub async fn login(
+    State(database): State<DataBase>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<Session>, ApiError> {
+    match verified_user(&database, credentials).await? {
+        Some(user) => Ok(Json(SESSION_KEYS.issue(&user, Utc::now()))),
+        None => Err(unauthorized("wrong username or password")),
+    }
+}
+
+pub async fn me(user: User) -> Json<User> {
+    Json(user)
+}
+
+pub async fn list_tokens(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<ApiToken>>, ApiError> {
+    Ok(Json(database.get_api_tokens(user.id).await?))
+}
+
+pub async fn create_token(
+    State(database): State<DataBase>,
+    user: User,
+    Json(body): Json<TokenBody>,
+) -> Result<(StatusCode, Json<NewApiToken>), ApiError> {
+    if body.name.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "name is empty"));
+    }
+    let (secret, hash) = new_api_token();
+    let token = database
+        .insert_api_token(user.id, &body.name, &hash)
+        .await?;
+    Ok((StatusCode::CREATED, Json(NewApiToken { token, secret })))
+}
+
+pub async fn delete_token(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_api_token(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
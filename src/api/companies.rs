@@ -0,0 +1,25 @@
+use axum::Json;
+use axum::extract::State;
+use axum_extra::extract::Query;
+use serde::Deserialize;
+
+use crate::job_fetchers::company::company_key;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::Company;
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompanyQuery {
+    /// Any name the company posts under, e.g. "Netcompany" finds
+    /// "Netcompany A/S".
+    name: Option<String>,
+}
+
+pub async fn list_companies(
+    State(database): State<DataBase>,
+    Query(query): Query<CompanyQuery>,
+) -> Result<Json<Vec<Company>>, ApiError> {
+    let key = query.name.as_deref().map(company_key);
+    Ok(Json(database.get_companies(key.as_deref()).await?))
+}
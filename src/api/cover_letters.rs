@@ -0,0 +1,126 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    ApplicationDocument, CoverLetterTemplate, DocFormat, DocumentKind, User,
+};
+use crate::services::doc_service::{
+    DEFAULT_COVER_LETTER, DOC_SERVICE, LetterFields,
+};
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateBody {
+    /// Markdown with placeholders, see `LetterFields`.
+    body: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DraftBody {
+    /// The name of a template of the user, or the built-in one.
+    template: Option<String>,
+    #[serde(default)]
+    format: DocFormat,
+}
+
+fn not_found(message: &'static str) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, message)
+}
+
+pub async fn list_templates(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<CoverLetterTemplate>>, ApiError> {
+    Ok(Json(
+        database.get_cover_letter_templates(user.id, None).await?,
+    ))
+}
+
+pub async fn put_template(
+    State(database): State<DataBase>,
+    Path(name): Path<String>,
+    user: User,
+    Json(body): Json<TemplateBody>,
+) -> Result<Json<CoverLetterTemplate>, ApiError> {
+    DOC_SERVICE
+        .check_cover_letter(&body.body)
+        .map_err(|error| {
+            ApiError::new(StatusCode::BAD_REQUEST, error.to_string())
+        })?;
+
+    let template = database
+        .upsert_cover_letter_template(user.id, &name, &body.body)
+        .await?;
+    Ok(Json(template))
+}
+
+pub async fn delete_template(
+    State(database): State<DataBase>,
+    Path(name): Path<String>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database
+        .delete_cover_letter_template(user.id, &name)
+        .await?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found("not found"))
+    }
+}
+
+/// Drafts a cover letter for the job of the application and attaches it
+/// to the application.
+pub async fn draft_cover_letter(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+    Json(body): Json<DraftBody>,
+) -> Result<(StatusCode, Json<ApplicationDocument>), ApiError> {
+    let application = database
+        .get_applications(user.id, Some(id))
+        .await?
+        .pop()
+        .ok_or_else(|| not_found("not found"))?;
+    let job = database
+        .get_job(application.job.id)
+        .await?
+        .ok_or_else(|| not_found("no such job"))?;
+    let cv = database.get_cv(user.id).await?;
+    let source = match &body.template {
+        Some(name) => {
+            database
+                .get_cover_letter_templates(user.id, Some(name))
+                .await?
+                .pop()
+                .ok_or_else(|| not_found("no such template"))?
+                .body
+        }
+        None => DEFAULT_COVER_LETTER.to_owned(),
+    };
+
+    let fields = LetterFields::new(&job, cv.as_ref(), Utc::now().date_naive());
+    let letter = DOC_SERVICE
+        .cover_letter(&source, &fields, body.format)
+        .map_err(|error| {
+            ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, error.to_string())
+        })?;
+    let document = database
+        .insert_application_document(
+            user.id,
+            id,
+            DocumentKind::CoverLetter,
+            &format!("cover_letter.{}", body.format.extension()),
+            body.format.content_type(),
+            &letter,
+        )
+        .await?
+        .ok_or_else(|| not_found("not found"))?;
+    Ok((StatusCode::CREATED, Json(document)))
+}
@@ -0,0 +1,119 @@
+use axum::Json;
+use axum::extract::State;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::Html;
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{Cv, Job, User};
+use crate::services::doc_service::DOC_SERVICE;
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenderQuery {
+    /// The job to tailor the CV to.
+    job_id: Option<i64>,
+}
+
+fn render_error(error: minijinja::Error) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
+
+/// The CV of the user and the job it is rendered for.
+async fn cv_and_job(
+    database: &DataBase,
+    user: &User,
+    query: &RenderQuery,
+) -> Result<(Cv, Option<Job>), ApiError> {
+    let cv = database
+        .get_cv(user.id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no CV"))?;
+    let job = match query.job_id {
+        Some(job_id) => {
+            Some(database.get_job(job_id).await?.ok_or_else(|| {
+                ApiError::new(StatusCode::NOT_FOUND, "no such job")
+            })?)
+        }
+        None => None,
+    };
+    Ok((cv, job))
+}
+
+pub async fn show_cv(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Cv>, ApiError> {
+    let cv = database
+        .get_cv(user.id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no CV"))?;
+    Ok(Json(cv))
+}
+
+pub async fn put_cv(
+    State(database): State<DataBase>,
+    user: User,
+    Json(cv): Json<Cv>,
+) -> Result<Json<Cv>, ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    if cv.name.trim().is_empty() {
+        return Err(bad_request("name is empty"));
+    }
+    let ends_before_start = cv
+        .experience
+        .iter()
+        .map(|entry| (entry.start, entry.end))
+        .chain(cv.education.iter().map(|entry| (entry.start, entry.end)))
+        .any(|(start, end)| end.is_some_and(|end| end < start));
+    if ends_before_start {
+        return Err(bad_request("an entry ends before it starts"));
+    }
+
+    database.upsert_cv(user.id, &cv).await?;
+    Ok(Json(cv))
+}
+
+pub async fn delete_cv(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_cv(user.id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
+
+pub async fn cv_html(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<RenderQuery>,
+) -> Result<Html<String>, ApiError> {
+    let (cv, job) = cv_and_job(&database, &user, &query).await?;
+    let html = DOC_SERVICE
+        .cv_html(&cv, job.as_ref())
+        .map_err(render_error)?;
+    Ok(Html(html))
+}
+
+pub async fn cv_pdf(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<RenderQuery>,
+) -> Result<([(axum::http::HeaderName, &'static str); 2], Vec<u8>), ApiError> {
+    let (cv, job) = cv_and_job(&database, &user, &query).await?;
+    let pdf = DOC_SERVICE
+        .cv_pdf(&cv, job.as_ref())
+        .map_err(render_error)?;
+    Ok((
+        [
+            (CONTENT_TYPE, "application/pdf"),
+            (CONTENT_DISPOSITION, "inline; filename=\"cv.pdf\""),
+        ],
+        pdf,
+    ))
+}
@@ -0,0 +1,73 @@
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use reqwest::StatusCode;
+
+use crate::api::jobs::{commute_home, view_as};
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{ExportFormat, User};
+use crate::services::export_service::{ExportStream, export_jobs};
+use crate::services::notification_service::search_filter;
+use crate::util::options::{ApiError, JobFilter};
+
+/// The export as a download named `name`, sent as it is written.
+fn download(
+    name: &str,
+    format: ExportFormat,
+    export: ExportStream,
+) -> Response {
+    (
+        [
+            (CONTENT_TYPE, format.content_type().to_owned()),
+            (
+                CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{name}.{}\"",
+                    format.extension()
+                ),
+            ),
+        ],
+        Body::from_stream(export),
+    )
+        .into_response()
+}
+
+/// The jobs `/jobs` would list for the same query, on every page unless the
+/// query asks for one.
+pub async fn export_job_list(
+    State(database): State<DataBase>,
+    user: Option<User>,
+    Path(format): Path<ExportFormat>,
+    mut filter: JobFilter,
+) -> Result<Response, ApiError> {
+    let profile = view_as(&database, user.as_ref(), &mut filter).await?;
+    let home = commute_home(&database, &filter).await?;
+    let export = export_jobs(
+        &database,
+        &filter,
+        profile.as_ref(),
+        home.as_ref(),
+        format,
+    )
+    .await?;
+    Ok(download("jobs", format, export))
+}
+
+/// The stored jobs the saved search matches, leaving out the ones the user
+/// has hidden.
+pub async fn export_saved_search(
+    State(database): State<DataBase>,
+    user: User,
+    Path((id, format)): Path<(i64, ExportFormat)>,
+) -> Result<Response, ApiError> {
+    let search = database
+        .get_saved_searches(Some(user.id))
+        .await?
+        .into_iter()
+        .find(|search| search.id == id)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "not found"))?;
+    let filter = search_filter(&search);
+    let export = export_jobs(&database, &filter, None, None, format).await?;
+    Ok(download(&format!("saved_search_{id}"), format, export))
+}
@@ -0,0 +1,73 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::commute_service::geocode;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    CommuteMode, HomeLocation, User,
+};
+use crate::util::geo::GeoPoint;
+use crate::util::options::ApiError;
+
+/// A home location is given by its coordinates or an address to geocode.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HomeBody {
+    address: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    #[serde(default)]
+    commute_mode: CommuteMode,
+}
+
+pub async fn list_homes(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<HomeLocation>>, ApiError> {
+    Ok(Json(database.get_home_locations(user.id, None).await?))
+}
+
+pub async fn put_home(
+    State(database): State<DataBase>,
+    user: User,
+    Path(name): Path<String>,
+    Json(body): Json<HomeBody>,
+) -> Result<Json<HomeLocation>, ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    let location = match (body.lat, body.lon, body.address.as_deref()) {
+        (Some(lat), Some(lon), _) => {
+            GeoPoint::new(lat, lon).map_err(bad_request)?
+        }
+        (None, None, Some(address)) => geocode(&database, address)
+            .await?
+            .ok_or_else(|| bad_request("the address could not be found"))?,
+        _ => {
+            return Err(bad_request(
+                "please provide either lat and lon or address",
+            ));
+        }
+    };
+
+    let home = HomeLocation {
+        name,
+        address: body.address,
+        location,
+        commute_mode: body.commute_mode,
+    };
+    database.upsert_home_location(user.id, &home).await?;
+    Ok(Json(home))
+}
+
+pub async fn delete_home(
+    State(database): State<DataBase>,
+    user: User,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_home_location(user.id, &name).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
@@ -0,0 +1,41 @@
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::auth::Admin;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{ImportFormat, ImportReport};
+use crate::services::import_service::import_jobs;
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportQuery {
+    /// Only check the file and report what importing it would do.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Imports the jobs of the file in the body, see `import_service`. An import
+/// overwrites the stored jobs with the same url, which everyone sees, so it
+/// is for admins.
+pub async fn import_job_file(
+    State(database): State<DataBase>,
+    _: Admin,
+    Path(format): Path<ImportFormat>,
+    Query(query): Query<ImportQuery>,
+    content: Bytes,
+) -> Result<Json<ImportReport>, ApiError> {
+    if content.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "the file is empty",
+        ));
+    }
+    Ok(Json(
+        import_jobs(&database, format, &content, query.dry_run).await?,
+    ))
+}
@@ -0,0 +1,277 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::marks::mark_jobs;
+use crate::services::commute_service::{
+    MAX_COMMUTE_CANDIDATES, ROUTING, commute_jobs, estimate_commutes,
+};
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    CommuteHit, CommuteMode, CommuteResults, GeoResults, HomeLocation, Job,
+    JobMatch, JobPage, MatchProfile, User,
+};
+use crate::services::match_service::{job_match, rank_jobs};
+use crate::util::geo::{GeoArea, MAX_RADIUS_KM};
+use crate::util::options::{
+    ApiError, HiddenMode, JobFilter, JobSort, SizeOptions,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NearQuery {
+    lat: Option<f64>,
+    lon: Option<f64>,
+    radius_km: Option<f64>,
+    /// The edges of a map view, in place of a radius.
+    south: Option<f64>,
+    west: Option<f64>,
+    north: Option<f64>,
+    east: Option<f64>,
+    /// Whether the jobs whose deadline passed are listed too.
+    #[serde(default)]
+    expired: bool,
+    page_size: Option<usize>,
+    page: Option<usize>,
+    jobs: Option<usize>,
+}
+
+/// The stored jobs matching the filter. For a signed in user, the jobs the
+/// user has hidden are left out and the marks of the user are shown.
+pub async fn list_jobs(
+    State(database): State<DataBase>,
+    user: Option<User>,
+    mut filter: JobFilter,
+) -> Result<Json<JobPage>, ApiError> {
+    let mut page = find_jobs(&database, user.as_ref(), &mut filter).await?;
+    if let Some(user) = &user {
+        mark_jobs(&database, user, &mut page.jobs).await?;
+    }
+    Ok(Json(page))
+}
+
+/// The stored jobs matching `filter` as `user` sees them, by commute from
+/// the `home` of the filter or by how well they fit the profile of `user`
+/// when asked to.
+pub async fn find_jobs(
+    database: &DataBase,
+    user: Option<&User>,
+    filter: &mut JobFilter,
+) -> Result<JobPage, ApiError> {
+    let profile = view_as(database, user, filter).await?;
+    let home = commute_home(database, filter).await?;
+    Ok(match (&home, &profile) {
+        (Some(home), _) => commute_jobs(database, filter, home).await?,
+        (None, Some(profile)) => rank_jobs(database, filter, profile).await?,
+        (None, None) => database.filter_jobs(filter).await?,
+    })
+}
+
+/// Makes `user` the viewer of `filter`, turning away what needs a user when
+/// there is none. The profile of the user is returned when the jobs are to be
+/// sorted by how well they fit it.
+pub async fn view_as(
+    database: &DataBase,
+    user: Option<&User>,
+    filter: &mut JobFilter,
+) -> Result<Option<MatchProfile>, ApiError> {
+    let needs_user = |message| ApiError::new(StatusCode::UNAUTHORIZED, message);
+    if user.is_none() && (filter.starred || filter.hidden == HiddenMode::Only) {
+        return Err(needs_user("starred and hidden=only need a user"));
+    }
+    filter.viewer = user.map(|user| user.id);
+    if filter.sort != JobSort::Match {
+        return Ok(None);
+    }
+    let user = user.ok_or_else(|| needs_user("sort=match needs a user"))?;
+    Ok(Some(match_profile(database, user.id).await?))
+}
+
+/// The home location the commutes of `filter` are from, if it has a `home`.
+/// Homes belong to a user, so `filter` needs a viewer for it.
+pub async fn commute_home(
+    database: &DataBase,
+    filter: &JobFilter,
+) -> Result<Option<HomeLocation>, ApiError> {
+    let Some(name) = &filter.home else {
+        return Ok(None);
+    };
+    let user_id = filter.viewer.ok_or_else(|| {
+        ApiError::new(StatusCode::UNAUTHORIZED, "home needs a user")
+    })?;
+    let home = database
+        .get_home_locations(user_id, Some(name))
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such home"))?;
+    Ok(Some(home))
+}
+
+async fn match_profile(
+    database: &DataBase,
+    user_id: i64,
+) -> Result<MatchProfile, ApiError> {
+    database
+        .get_match_profile(user_id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no profile"))
+}
+
+/// The stored job with all it was fetched with, like the evidence of each of
+/// its tags.
+pub async fn show_job(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+) -> Result<Json<Job>, ApiError> {
+    let job = database
+        .get_job(id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such job"))?;
+    Ok(Json(job))
+}
+
+/// How well the job fits the profile of the user, and why.
+pub async fn show_job_match(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<Json<JobMatch>, ApiError> {
+    let profile = match_profile(&database, user.id).await?;
+    let job_match = job_match(&database, &profile, id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such job"))?;
+    Ok(Json(job_match))
+}
+
+pub async fn jobs_near(
+    State(database): State<DataBase>,
+    Query(query): Query<NearQuery>,
+) -> Result<Json<GeoResults>, ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    let area = GeoArea::from_params(
+        query.lat,
+        query.lon,
+        query.radius_km,
+        query.south,
+        query.west,
+        query.north,
+        query.east,
+    )
+    .map_err(bad_request)?;
+    let size_options =
+        SizeOptions::from_params(query.page_size, query.page, query.jobs)
+            .map_err(bad_request)?;
+
+    Ok(Json(
+        database
+            .jobs_near(&area, query.expired, &size_options)
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommuteQuery {
+    /// The name of a home location.
+    home: String,
+    max_minutes: Option<f64>,
+    /// In place of the commute mode of the home location.
+    mode: Option<CommuteMode>,
+    #[serde(default)]
+    sort: CommuteSort,
+    /// Whether the jobs whose deadline passed are listed too.
+    #[serde(default)]
+    expired: bool,
+    page_size: Option<usize>,
+    page: Option<usize>,
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommuteSort {
+    #[default]
+    Commute,
+    Distance,
+}
+
+/// The jobs within a commute of a home location. Jobs further away than
+/// the commute could possibly reach are left out before any routing, and
+/// only the `MAX_COMMUTE_CANDIDATES` nearest jobs are routed to.
+pub async fn jobs_by_commute(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<CommuteQuery>,
+) -> Result<Json<CommuteResults>, ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    let size_options =
+        SizeOptions::from_params(query.page_size, query.page, query.jobs)
+            .map_err(bad_request)?;
+    if query
+        .max_minutes
+        .is_some_and(|minutes| minutes.is_nan() || minutes <= 0.0)
+    {
+        return Err(bad_request("max_minutes should be above 0"));
+    }
+    let home = database
+        .get_home_locations(user.id, Some(&query.home))
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such home"))?;
+    let mode = query.mode.unwrap_or(home.commute_mode);
+
+    let radius_km = query
+        .max_minutes
+        .map_or(MAX_RADIUS_KM, |minutes| mode.reach_km(minutes))
+        .min(MAX_RADIUS_KM);
+    if radius_km <= 0.0 {
+        return Ok(Json(CommuteResults {
+            total: 0,
+            hits: Vec::new(),
+        }));
+    }
+    let area = GeoArea::Radius {
+        center: home.location,
+        radius_km,
+    };
+    let nearest = SizeOptions::NotPaged {
+        jobs: MAX_COMMUTE_CANDIDATES,
+    };
+    let candidates = database
+        .jobs_near(&area, query.expired, &nearest)
+        .await?
+        .hits;
+
+    let locations: Vec<_> = candidates
+        .iter()
+        .map(|candidate| candidate.location)
+        .collect();
+    let commutes =
+        estimate_commutes(ROUTING.as_ref(), home.location, &locations, mode)
+            .await;
+    let mut hits: Vec<CommuteHit> = candidates
+        .into_iter()
+        .zip(commutes)
+        .map(|(job, commute)| CommuteHit { job, commute })
+        .filter(|hit| {
+            query
+                .max_minutes
+                .is_none_or(|minutes| hit.commute.minutes <= minutes)
+        })
+        .collect();
+    // the candidates are nearest first already
+    if query.sort == CommuteSort::Commute {
+        hits.sort_by(|a, b| a.commute.minutes.total_cmp(&b.commute.minutes));
+    }
+
+    let total = hits.len();
+    let (limit, offset) = size_options.limit_offset();
+    let hits = hits
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect();
+    Ok(Json(CommuteResults { total, hits }))
+}
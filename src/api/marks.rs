@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    HiddenCompany, JobMark, JobSummary, SearchHit, User,
+};
+use crate::util::options::ApiError;
+
+/// What to change of a mark, leaving out what stays.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarkPatch {
+    starred: Option<bool>,
+    hidden: Option<bool>,
+    /// An empty note removes the note.
+    note: Option<String>,
+}
+
+fn not_found(message: &'static str) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, message)
+}
+
+/// The marks of `user` on the jobs with the ids `job_ids`, by job id.
+async fn marks(
+    database: &DataBase,
+    user: &User,
+    job_ids: Vec<i64>,
+) -> Result<HashMap<i64, JobMark>, sqlx::Error> {
+    Ok(database
+        .get_job_marks(user.id, &job_ids)
+        .await?
+        .into_iter()
+        .map(|mark| (mark.job_id, mark))
+        .collect())
+}
+
+/// Shows the marks of `user` on the jobs.
+pub async fn mark_jobs(
+    database: &DataBase,
+    user: &User,
+    jobs: &mut [JobSummary],
+) -> Result<(), sqlx::Error> {
+    let mut marks =
+        marks(database, user, jobs.iter().map(|job| job.id).collect()).await?;
+    for job in jobs {
+        job.mark = marks.remove(&job.id);
+    }
+    Ok(())
+}
+
+/// Shows the marks of `user` on the jobs of the search hits.
+pub async fn mark_hits(
+    database: &DataBase,
+    user: &User,
+    hits: &mut [SearchHit],
+) -> Result<(), sqlx::Error> {
+    let mut marks =
+        marks(database, user, hits.iter().map(|hit| hit.id).collect()).await?;
+    for hit in hits {
+        hit.mark = marks.remove(&hit.id);
+    }
+    Ok(())
+}
+
+/// Stars, hides or notes the job for the user.
+pub async fn update_mark(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+    Json(patch): Json<MarkPatch>,
+) -> Result<Json<JobMark>, ApiError> {
+    let mark = database
+        .update_job_mark(
+            user.id,
+            id,
+            patch.starred,
+            patch.hidden,
+            patch.note.as_deref(),
+        )
+        .await?
+        .ok_or_else(|| not_found("no such job"))?;
+    Ok(Json(mark))
+}
+
+pub async fn list_hidden_companies(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<HiddenCompany>>, ApiError> {
+    Ok(Json(database.get_hidden_companies(user.id).await?))
+}
+
+/// Hides every job of the company from the user, also the ones to come.
+pub async fn hide_company(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if database.hide_company(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found("no such company"))
+    }
+}
+
+pub async fn unhide_company(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if database.unhide_company(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found("not found"))
+    }
+}
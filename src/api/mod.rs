@@ -0,0 +1,152 @@
+pub mod applications;
+pub mod auth;
+pub mod companies;
+pub mod cover_letters;
+pub mod cv;
+pub mod exports;
+pub mod homes;
+pub mod imports;
+pub mod jobs;
+pub mod marks;
+pub mod pages;
+pub mod profiles;
+pub mod regions;
+pub mod saved_searches;
+pub mod search;
+pub mod webhooks;
+
+use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::routing::{delete, get, patch, post, put};
+
+use crate::services::database_service::database::DataBase;
+use crate::services::import_service::MAX_IMPORT_BYTES;
+
+pub fn router() -> Router<DataBase> {
+    Router::new()
+        .route(
+            "/applications",
+            get(applications::list_applications)
+                .post(applications::create_application),
+        )
+        .route(
+            "/applications/{id}",
+            get(applications::show_application)
+                .patch(applications::update_application)
+                .delete(applications::delete_application),
+        )
+        .route(
+            "/applications/{id}/documents",
+            post(applications::attach_document),
+        )
+        .route(
+            "/applications/{id}/documents/{document_id}",
+            get(applications::download_document)
+                .delete(applications::delete_document),
+        )
+        .route(
+            "/applications/{id}/cover_letters",
+            post(cover_letters::draft_cover_letter),
+        )
+        .route(
+            "/applications/{id}/reminders",
+            post(applications::create_reminder),
+        )
+        .route("/applications/reminders", get(applications::list_reminders))
+        .route(
+            "/applications/reminders/{id}/done",
+            post(applications::complete_reminder),
+        )
+        .route("/auth/register", post(auth::register))
+        .route("/auth/login", post(auth::login))
+        .route("/auth/me", get(auth::me))
+        .route(
+            "/auth/tokens",
+            get(auth::list_tokens).post(auth::create_token),
+        )
+        .route("/auth/tokens/{id}", delete(auth::delete_token))
+        .route("/companies", get(companies::list_companies))
+        .route("/companies/hidden", get(marks::list_hidden_companies))
+        .route(
+            "/companies/{id}/hidden",
+            put(marks::hide_company).delete(marks::unhide_company),
+        )
+        .route("/jobs", get(jobs::list_jobs))
+        .route("/jobs/export/{format}", get(exports::export_job_list))
+        .route(
+            "/jobs/import/{format}",
+            post(imports::import_job_file)
+                .layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/jobs/near", get(jobs::jobs_near))
+        .route("/jobs/commute", get(jobs::jobs_by_commute))
+        .route("/jobs/{id}", get(jobs::show_job))
+        .route("/jobs/{id}/mark", patch(marks::update_mark))
+        .route("/jobs/{id}/match", get(jobs::show_job_match))
+        .route(
+            "/cover_letter_templates",
+            get(cover_letters::list_templates),
+        )
+        .route(
+            "/cover_letter_templates/{name}",
+            put(cover_letters::put_template)
+                .delete(cover_letters::delete_template),
+        )
+        .route(
+            "/cv",
+            get(cv::show_cv).put(cv::put_cv).delete(cv::delete_cv),
+        )
+        .route("/cv/html", get(cv::cv_html))
+        .route("/cv/pdf", get(cv::cv_pdf))
+        .route("/homes", get(homes::list_homes))
+        .route(
+            "/homes/{name}",
+            put(homes::put_home).delete(homes::delete_home),
+        )
+        .route(
+            "/profile",
+            get(profiles::show_profile)
+                .put(profiles::put_profile)
+                .delete(profiles::delete_profile),
+        )
+        .route("/profile/cv", put(profiles::upload_cv))
+        .route("/regions", get(regions::list_regions))
+        .route("/regions/sync", post(regions::sync))
+        .route(
+            "/saved_searches",
+            get(saved_searches::list_saved_searches)
+                .post(saved_searches::create_saved_search),
+        )
+        .route(
+            "/saved_searches/{id}",
+            delete(saved_searches::delete_saved_search),
+        )
+        .route(
+            "/saved_searches/{id}/export/{format}",
+            get(exports::export_saved_search),
+        )
+        .route("/saved_searches/notify", post(saved_searches::notify))
+        .route("/notifications", get(saved_searches::list_notifications))
+        .route(
+            "/notifications/{id}/read",
+            post(saved_searches::read_notification),
+        )
+        .route("/search", get(search::search_jobs))
+        .route("/ui", get(pages::search_page))
+        .route("/ui/applications", get(pages::applications_page))
+        .route("/ui/login", get(pages::login_page).post(pages::login))
+        .route("/ui/logout", post(pages::logout))
+        .route("/ui/saved_searches", get(pages::saved_searches_page))
+        .route(
+            "/webhooks",
+            get(webhooks::list_webhooks).post(webhooks::create_webhook),
+        )
+        .route("/webhooks/{id}", delete(webhooks::delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(webhooks::list_deliveries))
+        .route("/webhooks/dead_letters", get(webhooks::list_dead_letters))
+        .route(
+            "/webhooks/deliveries/{id}/retry",
+            post(webhooks::retry_delivery),
+        )
+        .route("/webhooks/deliver", post(webhooks::deliver))
+}
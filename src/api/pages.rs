@@ -0,0 +1,168 @@
+use axum::Form;
+use axum::extract::{RawQuery, State};
+use axum::http::header::SET_COOKIE;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use chrono::Utc;
+use minijinja::context;
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use crate::api::auth::{Credentials, verified_user};
+use crate::api::jobs::find_jobs;
+use crate::services::application_service::board;
+use crate::services::auth_service::{
+    SESSION_KEYS, expired_session_cookie, session_cookie,
+};
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{SavedSearch, User};
+use crate::services::page_service::{
+    JobCard, MAX_PAGE_SIZE, PAGE_SERVICE, form_values, markers, page_link,
+    search_link, search_params, too_many_jobs,
+};
+use crate::util::options::{ApiError, JobFilter, SizeOptions};
+
+/// A saved search with the link that shows its jobs.
+#[derive(Debug, Serialize)]
+struct SearchRow<'a> {
+    #[serde(flatten)]
+    search: &'a SavedSearch,
+    link: String,
+}
+
+fn render(
+    name: &str,
+    context: impl Serialize,
+) -> Result<Html<String>, ApiError> {
+    PAGE_SERVICE
+        .render(name, context)
+        .map(Html)
+        .map_err(|error| {
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        })
+}
+
+fn to_login() -> Response {
+    Redirect::to("/ui/login").into_response()
+}
+
+/// The search form, and the stored jobs matching it once it is sent. A
+/// signed in user does not see the jobs and companies the user has hidden.
+pub async fn search_page(
+    State(database): State<DataBase>,
+    user: Option<User>,
+    RawQuery(query): RawQuery,
+) -> Result<Response, ApiError> {
+    let params = search_params(query.as_deref().unwrap_or_default());
+    let form = form_values(&params);
+    if params.is_empty() {
+        let page = render("search.html", context! { user, form })?;
+        return Ok(page.into_response());
+    }
+
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(&params)
+        .finish();
+    let mut filter: JobFilter = match serde_html_form::from_str(&query) {
+        Ok(filter) => filter,
+        Err(error) => {
+            let error = error.to_string();
+            let page = render("search.html", context! { user, form, error })?;
+            return Ok((StatusCode::BAD_REQUEST, page).into_response());
+        }
+    };
+    if too_many_jobs(&filter.size_options) {
+        let error = format!("a page shows at most {MAX_PAGE_SIZE} jobs");
+        let page = render("search.html", context! { user, form, error })?;
+        return Ok((StatusCode::BAD_REQUEST, page).into_response());
+    }
+    let found = find_jobs(&database, user.as_ref(), &mut filter).await?;
+    let ids: Vec<i64> = found.jobs.iter().map(|job| job.id).collect();
+    let jobs = database.get_jobs_by_id(&ids).await?;
+
+    let (previous, next) = match filter.size_options {
+        SizeOptions::Page { page_size, page } => {
+            let shown = (page + 1).saturating_mul(page_size);
+            (
+                (page > 0).then(|| page_link(&params, page - 1)),
+                (found.total > shown as i64)
+                    .then(|| page_link(&params, page + 1)),
+            )
+        }
+        _ => (None, None),
+    };
+    let cards: Vec<JobCard> = jobs.iter().map(JobCard::from).collect();
+    let page = render(
+        "search.html",
+        context! {
+            user,
+            form,
+            jobs => cards,
+            markers => markers(&jobs),
+            previous,
+            next,
+        },
+    )?;
+    Ok(page.into_response())
+}
+
+pub async fn login_page(user: Option<User>) -> Result<Response, ApiError> {
+    if user.is_some() {
+        return Ok(Redirect::to("/ui").into_response());
+    }
+    Ok(render("login.html", context! {})?.into_response())
+}
+
+/// Signs in from the login form, keeping the session in a cookie.
+pub async fn login(
+    State(database): State<DataBase>,
+    Form(credentials): Form<Credentials>,
+) -> Result<Response, ApiError> {
+    let username = credentials.username.clone();
+    let Some(user) = verified_user(&database, credentials).await? else {
+        let error = "wrong username or password";
+        let page = render("login.html", context! { username, error })?;
+        return Ok((StatusCode::UNAUTHORIZED, page).into_response());
+    };
+    let now = Utc::now();
+    let session = SESSION_KEYS.issue(&user, now);
+    let cookie = session_cookie(&session, now);
+    Ok(([(SET_COOKIE, cookie)], Redirect::to("/ui")).into_response())
+}
+
+pub async fn logout() -> Response {
+    let cookie = expired_session_cookie();
+    ([(SET_COOKIE, cookie)], Redirect::to("/ui")).into_response()
+}
+
+/// The applications of the user as a board with a column per status.
+pub async fn applications_page(
+    State(database): State<DataBase>,
+    user: Option<User>,
+) -> Result<Response, ApiError> {
+    let Some(user) = user else {
+        return Ok(to_login());
+    };
+    let applications = database.get_applications(user.id, None).await?;
+    let board = board(applications);
+    Ok(render("applications.html", context! { user, board })?.into_response())
+}
+
+/// The saved searches of the user, with links to their jobs and exports.
+pub async fn saved_searches_page(
+    State(database): State<DataBase>,
+    user: Option<User>,
+) -> Result<Response, ApiError> {
+    let Some(user) = user else {
+        return Ok(to_login());
+    };
+    let searches = database.get_saved_searches(Some(user.id)).await?;
+    let searches: Vec<SearchRow> = searches
+        .iter()
+        .map(|search| SearchRow {
+            search,
+            link: search_link(search),
+        })
+        .collect();
+    let page = render("saved_searches.html", context! { user, searches })?;
+    Ok(page.into_response())
+}
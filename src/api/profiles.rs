@@ -0,0 +1,118 @@
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{MatchProfile, Seniority, User};
+use crate::services::match_service::{
+    canonical_skill, cv_text, extract_profile,
+};
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileBody {
+    /// Tag names or any way of writing them, the strongest first.
+    #[serde(default)]
+    skills: Vec<String>,
+    seniority: Option<Seniority>,
+    /// The name of a home location.
+    home: Option<String>,
+}
+
+fn not_found(message: &'static str) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, message)
+}
+
+pub async fn show_profile(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<MatchProfile>, ApiError> {
+    let profile = database
+        .get_match_profile(user.id)
+        .await?
+        .ok_or_else(|| not_found("not found"))?;
+    Ok(Json(profile))
+}
+
+pub async fn put_profile(
+    State(database): State<DataBase>,
+    user: User,
+    Json(body): Json<ProfileBody>,
+) -> Result<Json<MatchProfile>, ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    let mut skills: Vec<String> = Vec::new();
+    for skill in &body.skills {
+        let tag = canonical_skill(skill)
+            .ok_or_else(|| bad_request(format!("unknown skill: {skill}")))?;
+        if !skills.iter().any(|known| known == tag) {
+            skills.push(tag.to_owned());
+        }
+    }
+    if let Some(home) = &body.home
+        && database
+            .get_home_locations(user.id, Some(home))
+            .await?
+            .is_empty()
+    {
+        return Err(not_found("no such home"));
+    }
+
+    let profile = database
+        .upsert_match_profile(
+            user.id,
+            &skills,
+            body.seniority,
+            body.home.as_deref(),
+        )
+        .await?;
+    Ok(Json(profile))
+}
+
+/// Replaces the skills and seniority of the profile with what is found in
+/// the CV in the body, keeping its home.
+pub async fn upload_cv(
+    State(database): State<DataBase>,
+    user: User,
+    headers: HeaderMap,
+    content: Bytes,
+) -> Result<Json<MatchProfile>, ApiError> {
+    if content.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "the CV is empty"));
+    }
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/plain");
+    let text =
+        cv_text(content_type, content.to_vec())
+            .await
+            .map_err(|message| {
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+            })?;
+
+    let (skills, seniority) = extract_profile(&text);
+    let home = database
+        .get_match_profile(user.id)
+        .await?
+        .and_then(|profile| profile.home);
+    let profile = database
+        .upsert_match_profile(user.id, &skills, seniority, home.as_deref())
+        .await?;
+    Ok(Json(profile))
+}
+
+pub async fn delete_profile(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_match_profile(user.id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found("not found"))
+    }
+}
@@ -0,0 +1,35 @@
+use axum::Json;
+use axum::extract::State;
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::auth::Admin;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{RegionMatch, RegionSync};
+use crate::services::region_service::{find_regions, sync_regions};
+use crate::util::options::ApiError;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegionQuery {
+    q: String,
+}
+
+/// The regions `q` could mean, to pick the `region` of `/jobs` from.
+pub async fn list_regions(
+    State(database): State<DataBase>,
+    Query(query): Query<RegionQuery>,
+) -> Result<Json<RegionMatch>, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "q is empty"));
+    }
+    Ok(Json(find_regions(&database, &query.q).await?))
+}
+
+pub async fn sync(
+    State(database): State<DataBase>,
+    _: Admin,
+) -> Result<Json<RegionSync>, ApiError> {
+    Ok(Json(sync_regions(&database).await?))
+}
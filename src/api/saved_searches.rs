@@ -0,0 +1,117 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::auth::Admin;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    FeedItem, NotifyReport, NotifyTarget, SavedSearch, User,
+};
+use crate::services::notification_service::{
+    notify_new_matches, search_filter,
+};
+use crate::util::options::{ApiError, FilterOptions, QueryOptions};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedQuery {
+    #[serde(default)]
+    unread: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedSearchBody {
+    name: String,
+    #[serde(default)]
+    query_options: QueryOptions,
+    #[serde(default)]
+    filter_options: FilterOptions,
+    /// Only the in-app feed when left out.
+    #[serde(default = "default_notify")]
+    notify: Vec<NotifyTarget>,
+}
+
+fn default_notify() -> Vec<NotifyTarget> {
+    vec![NotifyTarget::Feed]
+}
+
+pub async fn list_saved_searches(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<SavedSearch>>, ApiError> {
+    Ok(Json(database.get_saved_searches(Some(user.id)).await?))
+}
+
+/// Saves the search. The jobs it matches already are taken as seen, so only
+/// jobs stored from now on are notified.
+pub async fn create_saved_search(
+    State(database): State<DataBase>,
+    user: User,
+    Json(body): Json<SavedSearchBody>,
+) -> Result<(StatusCode, Json<SavedSearch>), ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    if body.name.trim().is_empty() {
+        return Err(bad_request("name is empty"));
+    }
+    for target in &body.notify {
+        target.validate().map_err(bad_request)?;
+    }
+
+    let search = database
+        .insert_saved_search(
+            user.id,
+            &body.name,
+            &body.query_options,
+            &body.filter_options,
+            &body.notify,
+        )
+        .await?;
+    database
+        .record_new_matches(search.id, &search_filter(&search), false)
+        .await?;
+    Ok((StatusCode::CREATED, Json(search)))
+}
+
+pub async fn delete_saved_search(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_saved_search(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
+
+/// Runs the saved searches over the stored jobs, for crawls that store jobs
+/// some other way than `Jobs::crawl`.
+pub async fn notify(
+    State(database): State<DataBase>,
+    _: Admin,
+) -> Result<Json<NotifyReport>, ApiError> {
+    Ok(Json(notify_new_matches(&database).await?))
+}
+
+pub async fn list_notifications(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<Vec<FeedItem>>, ApiError> {
+    Ok(Json(database.get_feed(user.id, query.unread).await?))
+}
+
+pub async fn read_notification(
+    State(database): State<DataBase>,
+    Path(id): Path<i64>,
+    user: User,
+) -> Result<StatusCode, ApiError> {
+    if database.mark_feed_item_read(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
@@ -0,0 +1,63 @@
+use axum::Json;
+use axum::extract::State;
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::marks::mark_hits;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{SearchResults, User};
+use crate::util::options::{ApiError, HiddenMode, SizeOptions};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchQuery {
+    q: String,
+    /// Whether the jobs the user has hidden are shown.
+    #[serde(default)]
+    hidden: HiddenMode,
+    /// Whether the jobs whose deadline passed are searched too.
+    #[serde(default)]
+    expired: bool,
+    page_size: Option<usize>,
+    page: Option<usize>,
+    jobs: Option<usize>,
+}
+
+/// The jobs matching the query. For a signed in user, the jobs the user has
+/// hidden are left out and the marks of the user are shown.
+pub async fn search_jobs(
+    State(database): State<DataBase>,
+    user: Option<User>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResults>, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "q is empty"));
+    }
+    if user.is_none() && query.hidden == HiddenMode::Only {
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "hidden=only needs a user",
+        ));
+    }
+    let size_options =
+        SizeOptions::from_params(query.page_size, query.page, query.jobs)
+            .map_err(|message| {
+                ApiError::new(StatusCode::BAD_REQUEST, message)
+            })?;
+
+    let viewer = user.as_ref().map(|user| user.id);
+    let mut results = database
+        .search_jobs(
+            &query.q,
+            viewer,
+            query.hidden,
+            query.expired,
+            &size_options,
+        )
+        .await?;
+    if let Some(user) = &user {
+        mark_hits(&database, user, &mut results.hits).await?;
+    }
+    Ok(Json(results))
+}
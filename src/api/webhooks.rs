@@ -0,0 +1,152 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum_extra::extract::Query;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use url::Url;
+
+use crate::api::auth::Admin;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    DeliveryStatus, JobEvent, User, WebhookDelivery, WebhookSubscription,
+};
+use crate::services::webhook_service::{
+    DeliveryRun, MIN_SECRET_LEN, deliver_due,
+};
+use crate::util::options::ApiError;
+
+const DEFAULT_LOG_LIMIT: i64 = 100;
+const MAX_LOG_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookBody {
+    url: String,
+    /// The key the bodies are signed with.
+    secret: String,
+    /// Every event when left out.
+    #[serde(default)]
+    events: Vec<JobEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeliveryQuery {
+    status: Option<DeliveryStatus>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeadLetterQuery {
+    limit: Option<i64>,
+}
+
+fn log_limit(limit: Option<i64>) -> Result<i64, ApiError> {
+    match limit.unwrap_or(DEFAULT_LOG_LIMIT) {
+        limit @ 1..=MAX_LOG_LIMIT => Ok(limit),
+        _ => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "limit should be between 1 and 1000",
+        )),
+    }
+}
+
+pub async fn list_webhooks(
+    State(database): State<DataBase>,
+    user: User,
+) -> Result<Json<Vec<WebhookSubscription>>, ApiError> {
+    Ok(Json(database.get_webhooks(user.id).await?))
+}
+
+pub async fn create_webhook(
+    State(database): State<DataBase>,
+    user: User,
+    Json(body): Json<WebhookBody>,
+) -> Result<(StatusCode, Json<WebhookSubscription>), ApiError> {
+    let bad_request = |message| ApiError::new(StatusCode::BAD_REQUEST, message);
+    match Url::parse(&body.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => return Err(bad_request("url should be an http or https URL")),
+    }
+    if body.secret.chars().count() < MIN_SECRET_LEN {
+        return Err(bad_request("secret should be at least 16 characters"));
+    }
+
+    let events: Vec<JobEvent> = JobEvent::ALL
+        .into_iter()
+        .filter(|event| body.events.contains(event))
+        .collect();
+    let webhook = database
+        .insert_webhook(user.id, &body.url, &body.secret, &events)
+        .await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+pub async fn delete_webhook(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if database.delete_webhook(user.id, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "not found"))
+    }
+}
+
+/// The delivery log of the webhook, newest first.
+pub async fn list_deliveries(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+    Query(query): Query<DeliveryQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let limit = log_limit(query.limit)?;
+    Ok(Json(
+        database
+            .get_webhook_deliveries(user.id, Some(id), query.status, limit)
+            .await?,
+    ))
+}
+
+/// The deliveries of every webhook of the user that ran out of attempts,
+/// newest first.
+pub async fn list_dead_letters(
+    State(database): State<DataBase>,
+    user: User,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let limit = log_limit(query.limit)?;
+    Ok(Json(
+        database
+            .get_webhook_deliveries(
+                user.id,
+                None,
+                Some(DeliveryStatus::Dead),
+                limit,
+            )
+            .await?,
+    ))
+}
+
+/// Takes a dead delivery out of the dead-letter queue to be sent again.
+pub async fn retry_delivery(
+    State(database): State<DataBase>,
+    user: User,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if database.retry_dead_delivery(user.id, id).await? {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "no dead delivery"))
+    }
+}
+
+/// Sends what is due now rather than on the next round of the worker.
+pub async fn deliver(
+    State(database): State<DataBase>,
+    _: Admin,
+) -> Result<Json<DeliveryRun>, ApiError> {
+    Ok(Json(deliver_due(&database, &reqwest::Client::new()).await?))
+}
@@ -0,0 +1,522 @@
+//! `jobhunter`, what the server does for its routes and on its own, run from
+//! scripts and cron jobs against the database in `DATABASE_URL`.
+
+mod tui;
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use axum::extract::FromRequestParts;
+use chrono_tz::Europe::Copenhagen;
+use clap::{Parser, Subcommand, ValueEnum};
+use futures::TryStreamExt;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use jobhunter::api::jobs::{commute_home, view_as};
+use jobhunter::job_fetchers::JobFetcher;
+use jobhunter::job_fetchers::job_index::fetcher::JobIndex;
+use jobhunter::services::database_service::database::DataBase;
+use jobhunter::services::database_service::types::{
+    ExportFormat, ImportFormat, Job, User,
+};
+use jobhunter::services::export_service::export_jobs;
+use jobhunter::services::import_service::import_jobs;
+use jobhunter::services::jobs_service::Jobs;
+use jobhunter::services::notification_service::search_filter;
+use jobhunter::util::options::{ApiError, FetchOptions, JobFilter};
+
+use crate::tui::Source;
+
+#[derive(Debug, Parser)]
+#[command(name = "jobhunter", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Fetches jobs from JobIndex and prints them.
+    Fetch {
+        /// The parameters of `FetchOptions`, like `job_name=rust` or
+        /// `page_size=20`, each of which can be given more than once.
+        #[arg(value_parser = parse_param)]
+        params: Vec<(String, String)>,
+        #[arg(long, value_enum, default_value_t = Output::Table)]
+        output: Output,
+        /// Stores the jobs too, and sends the saved searches the new ones.
+        #[arg(long)]
+        store: bool,
+    },
+    /// Imports jobs from a JSON, NDJSON or CSV file.
+    Import {
+        file: PathBuf,
+        /// The format of the file, by default told by its extension.
+        #[arg(long, value_parser = parse_name::<ImportFormat>)]
+        format: Option<ImportFormat>,
+        /// Reports what would be imported without storing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Exports the stored jobs as CSV, XLSX, NDJSON or iCalendar.
+    Export {
+        #[arg(value_parser = parse_name::<ExportFormat>)]
+        format: ExportFormat,
+        /// The parameters `/jobs` takes, like `job_tags=rust` or
+        /// `sort=deadline`.
+        #[arg(value_parser = parse_param, conflicts_with = "saved_search")]
+        params: Vec<(String, String)>,
+        /// Exports the jobs of this saved search of `--user` instead.
+        #[arg(long, requires = "user")]
+        saved_search: Option<i64>,
+        /// The user the jobs are listed for, as on a signed in request.
+        #[arg(long)]
+        user: Option<String>,
+        /// The file to write to, by default standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Applies the migrations the database has not had yet.
+    Migrate,
+    /// Marks the jobs whose deadline passed as expired.
+    SweepExpired,
+    /// Lets a user import jobs, sync the regions and send the saved searches
+    /// and webhooks for everyone.
+    Admin {
+        username: String,
+        /// Takes it away again.
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Maintains the tags of the stored jobs.
+    Tags {
+        #[command(subcommand)]
+        command: TagsCommand,
+    },
+    /// Browses the stored jobs in the terminal, or freshly fetched ones.
+    Tui {
+        /// The parameters `/jobs` takes, or with `--fetch` those of
+        /// `FetchOptions`.
+        #[arg(value_parser = parse_param)]
+        params: Vec<(String, String)>,
+        /// Fetches the jobs from JobIndex, storing the ones that are marked.
+        #[arg(long)]
+        fetch: bool,
+        /// The user who stars, hides and applies for the jobs.
+        #[arg(long)]
+        user: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TagsCommand {
+    /// Matches the tags of every stored job again, after the tag table
+    /// changed.
+    Retag,
+}
+
+/// How `fetch` prints the jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+fn parse_param(param: &str) -> Result<(String, String), String> {
+    param
+        .split_once('=')
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got `{param}`"))
+}
+
+/// A value by the name it has in the API, like `ndjson`.
+fn parse_name<T: DeserializeOwned>(name: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_owned()))
+        .map_err(|_| format!("unknown name `{name}`"))
+}
+
+/// The options an HTTP route would extract from the same parameters in its
+/// query string.
+async fn from_params<T>(params: &[(String, String)]) -> Result<T, ApiError>
+where
+    T: FromRequestParts<(), Rejection = ApiError>,
+{
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    let (mut parts, _) = axum::http::Request::builder()
+        .uri(format!("/?{query}"))
+        .body(())
+        .expect("an encoded query should make a valid uri")
+        .into_parts();
+    T::from_request_parts(&mut parts, &()).await
+}
+
+async fn connect() -> Result<DataBase, Box<dyn Error>> {
+    let database_url =
+        std::env::var("DATABASE_URL").map_err(|_| "DATABASE_URL is not set")?;
+    Ok(DataBase::connect(&database_url).await?)
+}
+
+async fn user(
+    database: &DataBase,
+    username: Option<&str>,
+) -> Result<Option<User>, Box<dyn Error>> {
+    let Some(username) = username else {
+        return Ok(None);
+    };
+    let (user, _) = database
+        .get_login(username)
+        .await?
+        .ok_or_else(|| format!("no user named `{username}`"))?;
+    Ok(Some(user))
+}
+
+const HEADER: [&str; 5] = ["TITLE", "COMPANY", "DEADLINE", "TAGS", "URL"];
+
+fn job_row(job: &Job) -> [String; 5] {
+    let tags = job
+        .job_info
+        .job_tags
+        .iter()
+        .map(|tag| tag.name)
+        .collect::<Vec<_>>()
+        .join(",");
+    [
+        cut(&job.job_info.title.0, 48),
+        cut(&job.company_info.name, 24),
+        job.last_date
+            .map(|date| {
+                date.with_timezone(&Copenhagen)
+                    .format("%Y-%m-%d")
+                    .to_string()
+            })
+            .unwrap_or_default(),
+        cut(&tags, 32),
+        job.job_info.job_url.0.clone(),
+    ]
+}
+
+/// `text` cut to at most `max` characters, ending in an ellipsis if it was
+/// longer.
+fn cut(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// The rows under `header`, every column as wide as its widest cell.
+fn table<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) -> String {
+    let rows: Vec<[String; N]> = std::iter::once(header.map(str::to_owned))
+        .chain(rows)
+        .collect();
+    let widths: [usize; N] = std::array::from_fn(|column| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or(0)
+    });
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+async fn fetch(
+    params: &[(String, String)],
+    output: Output,
+    store: bool,
+) -> Result<(), Box<dyn Error>> {
+    let options: FetchOptions = from_params(params).await?;
+    let (jobs, report) = if store {
+        let (jobs, report) = Jobs::new()
+            .add_database(connect().await?)
+            .add_fetchers([JobIndex::default()])
+            .crawl(options)
+            .await
+            .ok_or("could not fetch and store the jobs from JobIndex")?;
+        (jobs, Some(report))
+    } else {
+        let jobs = JobIndex::default()
+            .fetch_all_jobs_with_options(&options)
+            .await
+            .ok_or("could not fetch the jobs from JobIndex")?;
+        (jobs, None)
+    };
+    match output {
+        Output::Table => print!("{}", table(HEADER, jobs.iter().map(job_row))),
+        Output::Json => println!("{}", serde_json::to_string_pretty(&jobs)?),
+    }
+    if let Some(report) = report {
+        eprintln!(
+            "stored {} jobs, {} new matches in {} saved searches",
+            jobs.len(),
+            report.new_matches,
+            report.searches,
+        );
+    }
+    Ok(())
+}
+
+async fn import(
+    file: PathBuf,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let format = format
+        .or_else(|| ImportFormat::from_path(&file))
+        .ok_or("cannot tell the format of the file, pass --format")?;
+    let content = tokio::fs::read(&file).await?;
+    let database = connect().await?;
+    let report = import_jobs(&database, format, &content, dry_run).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+async fn export(
+    format: ExportFormat,
+    params: &[(String, String)],
+    saved_search: Option<i64>,
+    username: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let database = connect().await?;
+    let user = user(&database, username.as_deref()).await?;
+    let (filter, profile) = match (saved_search, &user) {
+        (Some(id), Some(user)) => {
+            let search = database
+                .get_saved_searches(Some(user.id))
+                .await?
+                .into_iter()
+                .find(|search| search.id == id)
+                .ok_or_else(|| format!("no saved search {id}"))?;
+            (search_filter(&search), None)
+        }
+        _ => {
+            let mut filter: JobFilter = from_params(params).await?;
+            let profile =
+                view_as(&database, user.as_ref(), &mut filter).await?;
+            (filter, profile)
+        }
+    };
+    let home = commute_home(&database, &filter).await?;
+    let export = export_jobs(
+        &database,
+        &filter,
+        profile.as_ref(),
+        home.as_ref(),
+        format,
+    )
+    .await?;
+
+    let mut writer: Box<dyn AsyncWrite + Unpin> = match output {
+        Some(path) => Box::new(tokio::fs::File::create(path).await?),
+        None => Box::new(tokio::io::stdout()),
+    };
+    let mut export = export;
+    while let Some(chunk) = export.try_next().await? {
+        writer.write_all(&chunk).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Fetch {
+            params,
+            output,
+            store,
+        } => fetch(&params, output, store).await,
+        Command::Import {
+            file,
+            format,
+            dry_run,
+        } => import(file, format, dry_run).await,
+        Command::Export {
+            format,
+            params,
+            saved_search,
+            user,
+            output,
+        } => export(format, &params, saved_search, user, output).await,
+        Command::Migrate => {
+            connect().await?.migrate().await?;
+            println!("the database is up to date");
+            Ok(())
+        }
+        Command::SweepExpired => {
+            let expired = connect().await?.expire_jobs().await?;
+            println!("{expired} jobs expired");
+            Ok(())
+        }
+        Command::Admin { username, revoke } => {
+            if !connect().await?.set_admin(&username, !revoke).await? {
+                return Err(format!("no user named `{username}`").into());
+            }
+            let verb = if revoke { "is no longer" } else { "is now" };
+            println!("{username} {verb} an admin");
+            Ok(())
+        }
+        Command::Tags {
+            command: TagsCommand::Retag,
+        } => {
+            let retagged = connect().await?.retag_jobs().await?;
+            println!("{retagged} jobs retagged");
+            Ok(())
+        }
+        Command::Tui {
+            params,
+            fetch,
+            user: username,
+        } => {
+            let database = connect().await?;
+            let user = user(&database, username.as_deref()).await?;
+            let source = if fetch {
+                Source::Fetched(from_params(&params).await?)
+            } else {
+                let mut filter: JobFilter = from_params(&params).await?;
+                view_as(&database, user.as_ref(), &mut filter).await?;
+                Source::Stored(filter)
+            };
+            tui::run(database, user, source).await
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("jobhunter: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use jobhunter::util::options::{QueryOptions, SizeOptions};
+
+    #[test]
+    fn cli_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        let cli = Cli::try_parse_from([
+            "jobhunter",
+            "export",
+            "ics",
+            "job_tags=rust",
+            "sort=deadline",
+            "--output",
+            "deadlines.ics",
+        ])
+        .expect("should parse");
+        let Command::Export {
+            format,
+            params,
+            output,
+            ..
+        } = cli.command
+        else {
+            panic!("should be an export, not {:?}", cli.command);
+        };
+        assert_eq!(format, ExportFormat::Ics);
+        assert_eq!(params[1], ("sort".to_owned(), "deadline".to_owned()));
+        assert_eq!(output, Some(PathBuf::from("deadlines.ics")));
+
+        let cli = Cli::try_parse_from(["jobhunter", "tags", "retag"]);
+        assert!(matches!(
+            cli.map(|cli| cli.command),
+            Ok(Command::Tags {
+                command: TagsCommand::Retag
+            })
+        ));
+        // a saved search belongs to a user
+        assert!(
+            Cli::try_parse_from([
+                "jobhunter",
+                "export",
+                "csv",
+                "--saved-search",
+                "3"
+            ])
+            .is_err()
+        );
+        assert!(Cli::try_parse_from(["jobhunter", "fetch", "rust"]).is_err());
+
+        let cli =
+            Cli::try_parse_from(["jobhunter", "admin", "ada", "--revoke"]);
+        assert!(matches!(
+            cli.map(|cli| cli.command),
+            Ok(Command::Admin { username, revoke: true }) if username == "ada"
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_options_from_params() {
+        let params = [
+            ("job_name", "rust & go"),
+            ("job_tags", "backend"),
+            ("page_size", "20"),
+            ("page", "2"),
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()));
+        let options: FetchOptions =
+            from_params(&params).await.expect("should parse");
+
+        let QueryOptions::Query {
+            job_name, job_tags, ..
+        } = options.query_options
+        else {
+            panic!("should be a query");
+        };
+        assert_eq!(job_name.as_deref(), Some("rust & go"));
+        assert_eq!(job_tags, ["backend"]);
+        assert!(matches!(
+            options.size_options,
+            SizeOptions::Page {
+                page_size: 20,
+                page: 2
+            }
+        ));
+
+        let params = [("no_such_param".to_owned(), "1".to_owned())];
+        assert!(from_params::<FetchOptions>(&params).await.is_err());
+    }
+
+    #[test]
+    fn table_pads_columns() {
+        let rows = [
+            ["Rust udvikler".to_owned(), "Netcompany".to_owned()],
+            [cut("Senior Backend Engineer", 10), "Ørsted".to_owned()],
+        ];
+        assert_eq!(
+            table(["TITLE", "COMPANY"], rows),
+            "TITLE          COMPANY\n\
+             Rust udvikler  Netcompany\n\
+             Senior Ba…     Ørsted\n"
+        );
+    }
+}
@@ -0,0 +1,728 @@
+//! `jobhunter tui`, the stored jobs or freshly fetched ones in the terminal,
+//! to read through and star, hide or apply for.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Europe::Copenhagen;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
+use futures::StreamExt;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use tokio::sync::mpsc;
+
+use jobhunter::api::marks::mark_jobs;
+use jobhunter::job_fetchers::JobFetcher;
+use jobhunter::job_fetchers::job_index::fetcher::JobIndex;
+use jobhunter::services::database_service::database::DataBase;
+use jobhunter::services::database_service::types::{
+    ApplicationStatus, Job, JobSummary, User,
+};
+use jobhunter::util::options::{
+    FetchOptions, FilterOptions, JobFilter, SizeOptions,
+};
+
+/// Where the jobs of the list come from.
+pub enum Source {
+    /// The stored jobs `/jobs` lists for the filter.
+    Stored(JobFilter),
+    /// Jobs fetched from JobIndex, which are stored once they are marked.
+    Fetched(FetchOptions),
+}
+
+/// Stored jobs are loaded this many at a time.
+const PAGE_SIZE: usize = 50;
+
+/// A job in the list.
+struct Entry {
+    /// `None` while a fetched job is not stored.
+    id: Option<i64>,
+    title: String,
+    company: String,
+    /// The `share_url` of a JobIndex job.
+    url: String,
+    tags: Vec<String>,
+    deadline: Option<DateTime<Utc>>,
+    /// Loaded once the job is selected, for a stored job.
+    description: Option<String>,
+    /// A fetched job, kept to be stored when it is marked.
+    job: Option<Job>,
+    starred: bool,
+    hidden: bool,
+    applied: bool,
+}
+
+impl Entry {
+    fn stored(job: JobSummary, applied: &HashSet<String>) -> Self {
+        let url = job.job_url.unwrap_or_default();
+        Entry {
+            id: Some(job.id),
+            title: job.title.unwrap_or_default(),
+            company: job.company.unwrap_or_default(),
+            applied: applied.contains(&url),
+            url,
+            tags: job.tags,
+            deadline: job.last_date,
+            description: None,
+            job: None,
+            starred: job.mark.as_ref().is_some_and(|mark| mark.starred),
+            hidden: job.mark.as_ref().is_some_and(|mark| mark.hidden),
+        }
+    }
+
+    fn fetched(job: Job, applied: &HashSet<String>) -> Self {
+        Entry {
+            id: None,
+            title: job.job_info.title.0.clone(),
+            company: job.company_info.name.clone(),
+            url: job.job_info.job_url.0.clone(),
+            tags: job
+                .job_info
+                .job_tags
+                .iter()
+                .map(|tag| tag.name.to_owned())
+                .collect(),
+            deadline: job.last_date,
+            description: Some(job.job_info.description.0.clone()),
+            applied: applied.contains(&job.job_info.job_url.0),
+            job: Some(job),
+            starred: false,
+            hidden: false,
+        }
+    }
+}
+
+/// What the background load sends the list.
+enum Update {
+    Jobs(Vec<Entry>),
+    Done,
+    Failed(String),
+}
+
+/// Sends the jobs of `source` a page at a time, unless the options ask for
+/// a single page. `applied` are the urls of the jobs `user` has applied for.
+async fn load(
+    database: &DataBase,
+    user: Option<&User>,
+    source: Source,
+    applied: HashSet<String>,
+    updates: mpsc::UnboundedSender<Update>,
+) {
+    let update =
+        match send_jobs(database, user, source, &applied, &updates).await {
+            Ok(()) => Update::Done,
+            Err(error) => Update::Failed(error),
+        };
+    // the list is gone when the UI quit first
+    let _ = updates.send(update);
+}
+
+async fn send_jobs(
+    database: &DataBase,
+    user: Option<&User>,
+    source: Source,
+    applied: &HashSet<String>,
+    updates: &mpsc::UnboundedSender<Update>,
+) -> Result<(), String> {
+    let send = |jobs| updates.send(Update::Jobs(jobs)).is_ok();
+    match source {
+        Source::Stored(mut filter) => {
+            let paged = matches!(filter.size_options, SizeOptions::All);
+            for page in 0.. {
+                if paged {
+                    filter.size_options = SizeOptions::Page {
+                        page_size: PAGE_SIZE,
+                        page,
+                    };
+                }
+                let mut jobs = database
+                    .filter_jobs(&filter)
+                    .await
+                    .map_err(|error| error.to_string())?
+                    .jobs;
+                if let Some(user) = user {
+                    mark_jobs(database, user, &mut jobs)
+                        .await
+                        .map_err(|error| error.to_string())?;
+                }
+                let last = !paged || jobs.len() < PAGE_SIZE;
+                let jobs = jobs
+                    .into_iter()
+                    .map(|job| Entry::stored(job, applied))
+                    .collect();
+                if !send(jobs) || last {
+                    break;
+                }
+            }
+        }
+        Source::Fetched(options) => {
+            let fetcher = JobIndex::default();
+            // a filtered fetch reads on until it is full, so only an
+            // unfiltered one is shown a JobIndex page at a time
+            let limit = match options.size_options {
+                SizeOptions::NotPaged { jobs }
+                    if options.filter_options.is_empty() =>
+                {
+                    jobs
+                }
+                SizeOptions::All if options.filter_options.is_empty() => {
+                    usize::MAX
+                }
+                _ => {
+                    let jobs = fetcher
+                        .fetch_all_jobs_with_options(&options)
+                        .await
+                        .ok_or("could not fetch the jobs from JobIndex")?;
+                    send(
+                        jobs.into_iter()
+                            .map(|job| Entry::fetched(job, applied))
+                            .collect(),
+                    );
+                    return Ok(());
+                }
+            };
+            let mut fetched = 0;
+            for page in 0.. {
+                let page_options = FetchOptions {
+                    query_options: options.query_options.clone(),
+                    size_options: SizeOptions::Page {
+                        page_size: JobIndex::PAGE_SIZE,
+                        page,
+                    },
+                    filter_options: FilterOptions::default(),
+                };
+                let jobs = match fetcher
+                    .fetch_all_jobs_with_options(&page_options)
+                    .await
+                {
+                    Some(jobs) if !jobs.is_empty() => jobs,
+                    // a page past the last one can fail as well as be empty
+                    None if page == 0 => {
+                        return Err(
+                            "could not fetch the jobs from JobIndex".to_owned()
+                        );
+                    }
+                    _ => break,
+                };
+                let jobs: Vec<Entry> = jobs
+                    .into_iter()
+                    .take(limit - fetched)
+                    .map(|job| Entry::fetched(job, applied))
+                    .collect();
+                fetched += jobs.len();
+                if !send(jobs) || fetched >= limit {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What a key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Quit,
+    /// Moves the selection by this many jobs.
+    Move(isize),
+    First,
+    Last,
+    /// Scrolls the description by this many lines.
+    Scroll(i16),
+    Open,
+    Mark(Mark),
+}
+
+/// What the user marks the selected job as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Star,
+    Hide,
+    Applied,
+}
+
+impl Input {
+    fn of(key: KeyEvent) -> Option<Self> {
+        Some(match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Input::Quit,
+            KeyCode::Char('j') | KeyCode::Down => Input::Move(1),
+            KeyCode::Char('k') | KeyCode::Up => Input::Move(-1),
+            KeyCode::Char('g') | KeyCode::Home => Input::First,
+            KeyCode::Char('G') | KeyCode::End => Input::Last,
+            KeyCode::Char('J') | KeyCode::PageDown => Input::Scroll(10),
+            KeyCode::Char('K') | KeyCode::PageUp => Input::Scroll(-10),
+            KeyCode::Char('o') | KeyCode::Enter => Input::Open,
+            KeyCode::Char('s') => Input::Mark(Mark::Star),
+            KeyCode::Char('h') => Input::Mark(Mark::Hide),
+            KeyCode::Char('a') => Input::Mark(Mark::Applied),
+            _ => return None,
+        })
+    }
+}
+
+const HELP: &str = "j/k move  J/K scroll  o open  s star  h hide  a applied  \
+                    q quit";
+
+struct App {
+    entries: Vec<Entry>,
+    list: ListState,
+    /// How many lines the description is scrolled down.
+    scroll: u16,
+    loading: bool,
+    status: String,
+}
+
+impl App {
+    fn new() -> Self {
+        App {
+            entries: Vec::new(),
+            list: ListState::default(),
+            scroll: 0,
+            loading: true,
+            status: "loading jobs…".to_owned(),
+        }
+    }
+
+    fn update(&mut self, update: Update) {
+        match update {
+            Update::Jobs(jobs) => {
+                let listed: HashSet<String> = self
+                    .entries
+                    .iter()
+                    .map(|entry| entry.url.clone())
+                    .collect();
+                self.entries.extend(
+                    jobs.into_iter().filter(|job| !listed.contains(&job.url)),
+                );
+                if self.list.selected().is_none() && !self.entries.is_empty() {
+                    self.list.select(Some(0));
+                }
+                self.status = format!("loading jobs… {}", self.entries.len());
+            }
+            Update::Done => {
+                self.loading = false;
+                self.status = format!("{} jobs", self.entries.len());
+            }
+            Update::Failed(error) => {
+                self.loading = false;
+                self.status = error;
+            }
+        }
+    }
+
+    fn selected(&mut self) -> Option<&mut Entry> {
+        self.entries.get_mut(self.list.selected()?)
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        self.list.select(Some(index.min(self.entries.len() - 1)));
+        self.scroll = 0;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .areas(frame.area());
+        let [list, detail] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ])
+        .areas(main);
+
+        let items: Vec<ListItem> = self.entries.iter().map(list_item).collect();
+        let title = format!(" Jobs ({}) ", self.entries.len());
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::new().bg(Color::DarkGray))
+                .highlight_symbol("> "),
+            list,
+            &mut self.list,
+        );
+
+        let text = match self.list.selected().and_then(|i| self.entries.get(i))
+        {
+            Some(entry) => detail_text(entry),
+            None => Text::from("no jobs"),
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .block(Block::bordered())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            detail,
+        );
+
+        frame.render_widget(
+            Line::from(vec![
+                Span::raw(format!(" {} ", self.status)).bold(),
+                Span::raw(HELP).dark_gray(),
+            ]),
+            status,
+        );
+    }
+}
+
+fn tag_chips(tags: &[String]) -> Vec<Span<'_>> {
+    tags.iter()
+        .flat_map(|tag| {
+            [
+                Span::styled(
+                    format!(" {tag} "),
+                    Style::new().fg(Color::Black).bg(Color::Cyan),
+                ),
+                Span::raw(" "),
+            ]
+        })
+        .collect()
+}
+
+fn list_item(entry: &Entry) -> ListItem<'_> {
+    let marks = format!(
+        "{}{} ",
+        if entry.starred { '★' } else { ' ' },
+        if entry.applied { '✓' } else { ' ' },
+    );
+    let mut details =
+        vec![Span::raw("   "), Span::raw(&entry.company).italic()];
+    if !entry.tags.is_empty() {
+        details.push(Span::raw("  "));
+        details.extend(tag_chips(&entry.tags));
+    }
+    let item = ListItem::new(vec![
+        Line::from(vec![
+            Span::raw(marks).yellow(),
+            Span::raw(&entry.title).bold(),
+        ]),
+        Line::from(details),
+    ]);
+    if entry.hidden {
+        item.style(Style::new().fg(Color::DarkGray))
+    } else {
+        item
+    }
+}
+
+fn detail_text(entry: &Entry) -> Text<'_> {
+    let deadline = entry.deadline.map_or("none".to_owned(), |deadline| {
+        deadline
+            .with_timezone(&Copenhagen)
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+    let mut marks = Vec::new();
+    for (marked, name) in [
+        (entry.starred, "starred"),
+        (entry.hidden, "hidden"),
+        (entry.applied, "applied"),
+    ] {
+        if marked {
+            marks.push(name);
+        }
+    }
+    let mut lines = vec![
+        Line::from(entry.title.as_str()).bold(),
+        Line::from(entry.company.as_str()).italic(),
+        Line::from(format!("Deadline: {deadline}")),
+        Line::from(Span::raw(&entry.url).underlined().blue()),
+        Line::from(tag_chips(&entry.tags)),
+    ];
+    if !marks.is_empty() {
+        lines.push(Line::from(marks.join(", ")).yellow());
+    }
+    lines.push(Line::default());
+    lines.extend(
+        entry
+            .description
+            .as_deref()
+            .unwrap_or("loading…")
+            .lines()
+            .map(Line::from),
+    );
+    Text::from(lines)
+}
+
+/// Marks `entry` for `user`, storing it first if it was fetched. Returns
+/// what is shown in the status line.
+async fn mark(
+    database: &DataBase,
+    user: Option<&User>,
+    entry: &mut Entry,
+    mark: Mark,
+) -> Result<String, Box<dyn Error>> {
+    let user = user.ok_or("starring, hiding and applying need --user")?;
+    let id = match (entry.id, &entry.job) {
+        (Some(id), _) => id,
+        (None, Some(job)) => database.insert_job(job).await?,
+        (None, None) => return Err("the job is not stored".into()),
+    };
+    entry.id = Some(id);
+
+    let status = match mark {
+        Mark::Star | Mark::Hide => {
+            let (starred, hidden) = match mark {
+                Mark::Star => (Some(!entry.starred), None),
+                _ => (None, Some(!entry.hidden)),
+            };
+            let job_mark = database
+                .update_job_mark(user.id, id, starred, hidden, None)
+                .await?
+                .ok_or("the job is gone")?;
+            entry.starred = job_mark.starred;
+            entry.hidden = job_mark.hidden;
+            match mark {
+                Mark::Star if entry.starred => "starred",
+                Mark::Star => "unstarred",
+                _ if entry.hidden => "hidden",
+                _ => "unhidden",
+            }
+        }
+        Mark::Applied => {
+            let application = database
+                .get_applications(user.id, None)
+                .await?
+                .into_iter()
+                .find(|application| application.job.id == id);
+            match application {
+                Some(application) if application.status.has_applied() => {
+                    "already applied"
+                }
+                Some(application)
+                    if application
+                        .status
+                        .can_move_to(ApplicationStatus::Applied) =>
+                {
+                    database
+                        .set_application_status(
+                            user.id,
+                            application.id,
+                            application.status,
+                            ApplicationStatus::Applied,
+                        )
+                        .await?;
+                    "marked applied"
+                }
+                Some(_) => return Err("the application was rejected".into()),
+                None => {
+                    database
+                        .insert_application(
+                            user.id,
+                            id,
+                            ApplicationStatus::Applied,
+                            "",
+                        )
+                        .await?
+                        .ok_or("the job is gone")?;
+                    "marked applied"
+                }
+            }
+        }
+    };
+    entry.applied |= mark == Mark::Applied;
+    Ok(status.to_owned())
+}
+
+/// The description of the selected job, if it is stored and not loaded yet.
+async fn load_description(
+    database: &DataBase,
+    app: &mut App,
+) -> Result<(), sqlx::Error> {
+    let Some(entry) = app.selected() else {
+        return Ok(());
+    };
+    if entry.description.is_some() {
+        return Ok(());
+    }
+    let Some(id) = entry.id else {
+        return Ok(());
+    };
+    let job = database.get_job(id).await?;
+    entry.description = Some(
+        job.map(|job| job.job_info.description.0)
+            .unwrap_or_default(),
+    );
+    Ok(())
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    database: &DataBase,
+    user: Option<&User>,
+    updates: &mut mpsc::UnboundedReceiver<Update>,
+) -> Result<(), Box<dyn Error>> {
+    let mut app = App::new();
+    let mut events = EventStream::new();
+    loop {
+        load_description(database, &mut app).await?;
+        terminal.draw(|frame| app.draw(frame))?;
+
+        let key = tokio::select! {
+            update = updates.recv(), if app.loading => {
+                match update {
+                    Some(update) => app.update(update),
+                    None => app.loading = false,
+                }
+                continue;
+            }
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    key
+                }
+                // a resize is drawn on the next round
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => return Err(error.into()),
+                None => return Ok(()),
+            },
+        };
+
+        let selected = app.list.selected().unwrap_or(0);
+        match Input::of(key) {
+            None => {}
+            Some(Input::Quit) => return Ok(()),
+            Some(Input::Move(by)) => {
+                app.select(selected.saturating_add_signed(by))
+            }
+            Some(Input::First) => app.select(0),
+            Some(Input::Last) => app.select(usize::MAX),
+            Some(Input::Scroll(by)) => {
+                app.scroll = app.scroll.saturating_add_signed(by)
+            }
+            Some(Input::Open) => {
+                if let Some(entry) = app.selected() {
+                    let url = entry.url.clone();
+                    app.status = match open::that_detached(&url) {
+                        Ok(()) => format!("opened {url}"),
+                        Err(error) => format!("could not open {url}: {error}"),
+                    };
+                }
+            }
+            Some(Input::Mark(kind)) => {
+                if let Some(entry) = app.selected() {
+                    let status = mark(database, user, entry, kind).await;
+                    app.status =
+                        status.unwrap_or_else(|error| error.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Lists the jobs of `source` as they come in, until the user quits.
+pub async fn run(
+    database: DataBase,
+    user: Option<User>,
+    source: Source,
+) -> Result<(), Box<dyn Error>> {
+    let applied = match &user {
+        Some(user) => database
+            .get_applications(user.id, None)
+            .await?
+            .into_iter()
+            .filter(|application| application.status.has_applied())
+            .filter_map(|application| application.job.job_url)
+            .collect(),
+        None => HashSet::new(),
+    };
+    let (sender, mut updates) = mpsc::unbounded_channel();
+    let mut terminal = ratatui::init();
+
+    // both on this task, as the futures of the fetchers are not `Send`
+    let loader = load(&database, user.as_ref(), source, applied, sender);
+    let event_loop =
+        event_loop(&mut terminal, &database, user.as_ref(), &mut updates);
+    tokio::pin!(loader, event_loop);
+    let mut loaded = false;
+    let result = loop {
+        tokio::select! {
+            result = &mut event_loop => break result,
+            () = &mut loader, if !loaded => loaded = true,
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn entry(title: &str, url: &str) -> Entry {
+        Entry {
+            id: Some(1),
+            title: title.to_owned(),
+            company: "Netcompany".to_owned(),
+            url: url.to_owned(),
+            tags: vec!["rust".to_owned(), "postgres".to_owned()],
+            deadline: None,
+            description: Some("Build the backend.".to_owned()),
+            job: None,
+            starred: true,
+            hidden: false,
+            applied: false,
+        }
+    }
+
+    #[test]
+    fn jobs_stream_into_the_list() {
+        let mut app = App::new();
+        app.update(Update::Jobs(vec![entry("Rust Developer", "https://a")]));
+        assert_eq!(app.list.selected(), Some(0));
+        assert!(app.loading);
+
+        // a job listed already is not listed twice
+        app.update(Update::Jobs(vec![
+            entry("Rust Developer", "https://a"),
+            entry("Backend Engineer", "https://b"),
+        ]));
+        app.update(Update::Done);
+        assert_eq!(app.entries.len(), 2);
+        assert!(!app.loading);
+        assert_eq!(app.status, "2 jobs");
+
+        app.select(usize::MAX);
+        assert_eq!(app.list.selected(), Some(1));
+    }
+
+    #[test]
+    fn keys_map_to_inputs() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(Input::of(key(KeyCode::Down)), Some(Input::Move(1)));
+        assert_eq!(Input::of(key(KeyCode::Char('k'))), Some(Input::Move(-1)));
+        assert_eq!(Input::of(key(KeyCode::Enter)), Some(Input::Open));
+        assert_eq!(
+            Input::of(key(KeyCode::Char('h'))),
+            Some(Input::Mark(Mark::Hide))
+        );
+        assert_eq!(Input::of(key(KeyCode::Char('x'))), None);
+    }
+
+    #[test]
+    fn draws_the_selected_job() {
+        let mut app = App::new();
+        app.update(Update::Jobs(vec![entry("Rust Developer", "https://a")]));
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("> ★  Rust Developer"));
+        assert!(screen.contains(" rust "));
+        assert!(screen.contains("Build the backend."));
+    }
+}
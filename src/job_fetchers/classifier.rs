@@ -0,0 +1,327 @@
+use std::sync::LazyLock;
+
+use crate::job_fetchers::tag_matcher::TagMatcher;
+use crate::services::database_service::types::{
+    Description, EmploymentType, JobClassification, JobTag, Language,
+    Seniority, Title,
+};
+
+// The rules are matched like job tags, so they are case folded and bound to
+// whole words. The names are the ones `Seniority::from_name` and
+// `EmploymentType::from_name` understand.
+pub const SENIORITY_RULES: &[(&str, &[&str])] = &[
+    (
+        "lead",
+        &[
+            "lead",
+            "team lead",
+            "tech lead",
+            "principal",
+            "head of",
+            "teamleder",
+            "teamlead",
+            "udviklingschef",
+        ],
+    ),
+    (
+        "senior",
+        &[
+            "senior",
+            "sr.",
+            "experienced",
+            "erfaren",
+            "architect",
+            "arkitekt",
+        ],
+    ),
+    (
+        "junior",
+        &[
+            "junior",
+            "jr.",
+            "graduate",
+            "nyuddannet",
+            "dimittend",
+            "entry level",
+            "entry-level",
+            "trainee",
+        ],
+    ),
+];
+
+pub const EMPLOYMENT_TYPE_RULES: &[(&str, &[&str])] = &[
+    (
+        "student",
+        &[
+            "student",
+            "studiejob",
+            "studentermedhjælper",
+            "studentermedarbejder",
+            "studentermedhjælp",
+            "student assistant",
+            "student help",
+            "student worker",
+        ],
+    ),
+    (
+        "internship",
+        &["internship", "praktikant", "praktikplads", "praktik"],
+    ),
+    (
+        "part_time",
+        &["part-time", "part time", "deltid", "deltidsstilling"],
+    ),
+    (
+        "temporary",
+        &[
+            "temporary",
+            "fixed-term",
+            "fixed term",
+            "vikariat",
+            "barselsvikariat",
+            "tidsbegrænset",
+        ],
+    ),
+    ("freelance", &["freelance", "freelancer"]),
+    (
+        "full_time",
+        &[
+            "full-time",
+            "full time",
+            "fuldtid",
+            "fuldtidsstilling",
+            "fastansættelse",
+            "fast stilling",
+            "permanent position",
+        ],
+    ),
+];
+
+pub const REMOTE_RULES: &[(&str, &[&str])] = &[(
+    "remote",
+    &[
+        "remote",
+        "remote-first",
+        "work remotely",
+        "working remotely",
+        "work from home",
+        "working from home",
+        "hjemmearbejde",
+        "arbejde hjemmefra",
+        "arbejder hjemmefra",
+        "fjernarbejde",
+    ],
+)];
+
+// Common words that only occur in one of the two languages.
+const DANISH_WORDS: &[&str] = &[
+    "og", "at", "er", "til", "på", "med", "som", "vi", "du", "har", "af",
+    "den", "det", "der", "vil", "skal", "os", "vores", "dig", "din", "dine",
+    "kan", "ikke", "også", "hos", "være", "bliver", "søger", "eller",
+];
+const ENGLISH_WORDS: &[&str] = &[
+    "and", "the", "to", "of", "is", "are", "you", "we", "our", "your", "with",
+    "will", "be", "as", "on", "this", "that", "have", "who", "or", "looking",
+    "join",
+];
+/// Fewer stop words than this and the language is left undecided.
+const MIN_LANGUAGE_EVIDENCE: usize = 3;
+
+static SENIORITY_MATCHER: LazyLock<TagMatcher> =
+    LazyLock::new(|| TagMatcher::new(SENIORITY_RULES));
+static EMPLOYMENT_TYPE_MATCHER: LazyLock<TagMatcher> =
+    LazyLock::new(|| TagMatcher::new(EMPLOYMENT_TYPE_RULES));
+static REMOTE_MATCHER: LazyLock<TagMatcher> =
+    LazyLock::new(|| TagMatcher::new(REMOTE_RULES));
+
+pub fn classify_job(
+    title: &Title,
+    description: &Description,
+) -> JobClassification {
+    JobClassification {
+        seniority: strongest_rule(&SENIORITY_MATCHER, title, description)
+            .and_then(Seniority::from_name),
+        employment_type: strongest_rule(
+            &EMPLOYMENT_TYPE_MATCHER,
+            title,
+            description,
+        )
+        .and_then(EmploymentType::from_name),
+        language: detect_language(&format!("{} {}", title.0, description.0)),
+        remote: strongest_rule(&REMOTE_MATCHER, title, description).is_some(),
+    }
+}
+
+/// The rule with the highest weight, where a match in the title beats any
+/// number of matches in the body. Ties go to the rule listed first.
+fn strongest_rule(
+    matcher: &TagMatcher,
+    title: &Title,
+    description: &Description,
+) -> Option<&'static str> {
+    matcher
+        .job_tags(&title.0, &description.0)
+        .into_iter()
+        .reduce(|best: JobTag, rule| {
+            if rule.weight > best.weight {
+                rule
+            } else {
+                best
+            }
+        })
+        .map(|rule| rule.name)
+}
+
+pub fn detect_language(text: &str) -> Option<Language> {
+    let (mut danish, mut english) = (0, 0);
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+    {
+        danish += usize::from(DANISH_WORDS.contains(&word.as_str()));
+        english += usize::from(ENGLISH_WORDS.contains(&word.as_str()));
+    }
+
+    match danish.max(english) {
+        count if count < MIN_LANGUAGE_EVIDENCE => None,
+        _ if danish > english => Some(Language::Danish),
+        _ if english > danish => Some(Language::English),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct CorpusEntry {
+        tid: String,
+        text: String,
+    }
+
+    fn sample_ads() -> HashMap<String, String> {
+        let corpus = std::fs::read_to_string("jobindex_files/tag_corpus.json")
+            .expect("corpus should exist");
+        serde_json::from_str::<Vec<CorpusEntry>>(&corpus)
+            .expect("corpus should parse")
+            .into_iter()
+            .map(|entry| (entry.tid, entry.text))
+            .collect()
+    }
+
+    fn classify(title: &str, description: &str) -> JobClassification {
+        classify_job(
+            &Title(title.to_owned()),
+            &Description(description.to_owned()),
+        )
+    }
+
+    fn classify_sample(
+        ads: &HashMap<String, String>,
+        tid: &str,
+    ) -> JobClassification {
+        // the corpus entries are the headline followed by the first paragraph
+        let (title, description) =
+            ads[tid].split_once(". ").expect("should have a headline");
+        classify(title, description)
+    }
+
+    #[test]
+    fn language_of_sample_ads() {
+        let ads = sample_ads();
+        for tid in ["h1587208", "h1587025", "h1587317", "h1584011", "h1577664"]
+        {
+            assert_eq!(
+                classify_sample(&ads, tid).language,
+                Some(Language::Danish),
+                "{tid}"
+            );
+        }
+        for tid in ["h1587118", "h1580929", "h1587086", "h1579770"] {
+            assert_eq!(
+                classify_sample(&ads, tid).language,
+                Some(Language::English),
+                "{tid}"
+            );
+        }
+    }
+
+    #[test]
+    fn seniority_of_sample_ads() {
+        let ads = sample_ads();
+        let expected = [
+            ("h1580326", Some(Seniority::Senior)),
+            ("h1585175", Some(Seniority::Senior)),
+            ("h1587118", Some(Seniority::Lead)),
+            ("h1578366", Some(Seniority::Senior)),
+            ("h1579770", None),
+            ("h1587208", None),
+        ];
+        for (tid, seniority) in expected {
+            assert_eq!(
+                classify_sample(&ads, tid).seniority,
+                seniority,
+                "{tid}"
+            );
+        }
+    }
+
+    #[test]
+    fn employment_type_of_sample_ads() {
+        let ads = sample_ads();
+        let expected = [
+            ("r13264571", Some(EmploymentType::Student)),
+            ("h1578818", Some(EmploymentType::Student)),
+            ("h1587317", None),
+            ("h1580326", None),
+        ];
+        for (tid, employment_type) in expected {
+            assert_eq!(
+                classify_sample(&ads, tid).employment_type,
+                employment_type,
+                "{tid}"
+            );
+        }
+    }
+
+    #[test]
+    fn title_beats_body() {
+        let classification = classify(
+            "Junior udvikler",
+            "Du bliver en del af et team med erfarne og senior kolleger, \
+             og stillingen er på deltid.",
+        );
+        assert_eq!(classification.seniority, Some(Seniority::Junior));
+        assert_eq!(
+            classification.employment_type,
+            Some(EmploymentType::PartTime)
+        );
+        assert_eq!(classification.language, Some(Language::Danish));
+    }
+
+    #[test]
+    fn remote_jobs() {
+        assert!(classify("Remote Rust Developer", "").remote);
+        assert!(
+            classify(
+                "Backend-udvikler",
+                "Du kan arbejde hjemmefra to dage om ugen."
+            )
+            .remote
+        );
+        assert!(
+            !classify("Backend-udvikler", "Kontoret ligger i Aarhus.").remote
+        );
+    }
+
+    #[test]
+    fn too_little_text_has_no_language() {
+        assert_eq!(detect_language("Rust Developer"), None);
+    }
+}
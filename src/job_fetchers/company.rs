@@ -0,0 +1,112 @@
+/// Legal forms that are dropped from company names. Trailing dots are
+/// trimmed before these are matched.
+const LEGAL_SUFFIXES: &[&str] = &[
+    "A.M.B.A", "AMBA", "A/S", "ApS", "IVS", "I/S", "P/S", "K/S", "AB", "ASA",
+    "GmbH", "Ltd", "Inc",
+];
+/// The suffixes that are distinct enough to be stripped when they are glued
+/// to the name.
+const UNSPACED_SUFFIXES: &[&str] = &["A/S", "ApS", "I/S", "P/S", "K/S"];
+
+/// Names a company also posts under, as keys, see `company_key`. The first
+/// key is the one the others resolve to.
+const COMPANY_ALIASES: &[&[&str]] = &[
+    &[
+        "dtu",
+        "danmarkstekniskeuniversitet",
+        "dtudanmarkstekniskeuniversitet",
+    ],
+    &["ku", "kobenhavnsuniversitet", "københavnsuniversitet"],
+    &["au", "aarhusuniversitet"],
+    &["dsb", "danskestatsbaner"],
+    &["novonordisk", "novo"],
+    &["netcompany", "netcompanyitandbusinessconsulting"],
+    &["beumergroup", "beumergroupdenmark"],
+];
+
+/// The company name without its legal form, e.g. "Netcompany A/S" becomes
+/// "Netcompany".
+///
+/// JobIndex sometimes sends names with their spaces removed, like
+/// "AteaA/S", so the Danish forms are also stripped without a space in front
+/// of them, but then only when written exactly like the legal form.
+pub fn strip_legal_suffix(name: &str) -> &str {
+    let name = name.trim().trim_end_matches([',', '.']).trim_end();
+    LEGAL_SUFFIXES
+        .iter()
+        .find_map(|suffix| {
+            let split = name.len().checked_sub(suffix.len())?;
+            let (rest, end) = name.split_at_checked(split)?;
+            let spaced = rest.ends_with([' ', ',']);
+            let matches = if spaced {
+                end.eq_ignore_ascii_case(suffix)
+            } else {
+                UNSPACED_SUFFIXES.contains(suffix) && end == *suffix
+            };
+            (matches && !rest.trim().is_empty())
+                .then(|| rest.trim_end_matches([' ', ',']))
+        })
+        .unwrap_or(name)
+}
+
+/// What a company is identified by across postings and sources: the
+/// lowercased letters and digits of its name without the legal form, resolved
+/// through `COMPANY_ALIASES`.
+pub fn company_key(name: &str) -> String {
+    let key: String = strip_legal_suffix(name)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    COMPANY_ALIASES
+        .iter()
+        .find(|aliases| aliases.contains(&key.as_str()))
+        .map(|aliases| aliases[0].to_owned())
+        .unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legal_suffixes_are_stripped() {
+        assert_eq!(strip_legal_suffix("Netcompany A/S"), "Netcompany");
+        assert_eq!(strip_legal_suffix("Formalize ApS"), "Formalize");
+        assert_eq!(strip_legal_suffix("Startup IVS"), "Startup");
+        assert_eq!(strip_legal_suffix("Acme a/s"), "Acme");
+        assert_eq!(strip_legal_suffix("Acme, A/S."), "Acme");
+        assert_eq!(strip_legal_suffix("AteaA/S"), "Atea");
+        assert_eq!(strip_legal_suffix("BAtechnologiesApS"), "BAtechnologies");
+    }
+
+    #[test]
+    fn names_that_only_end_like_a_suffix_are_kept() {
+        assert_eq!(strip_legal_suffix("Snaps"), "Snaps");
+        assert_eq!(strip_legal_suffix("Divs"), "Divs");
+        assert_eq!(strip_legal_suffix("A/S"), "A/S");
+        assert_eq!(strip_legal_suffix("DSB"), "DSB");
+        assert_eq!(strip_legal_suffix("SAAB"), "SAAB");
+        assert_eq!(strip_legal_suffix("Saab AB"), "Saab");
+    }
+
+    #[test]
+    fn spacing_and_case_do_not_matter() {
+        assert_eq!(company_key("BEUMER Group A/S"), "beumergroup");
+        assert_eq!(company_key("BEUMERGroupA/S"), "beumergroup");
+        assert_eq!(
+            company_key("Körber Supply Chain DK A/S"),
+            "körbersupplychaindk"
+        );
+        assert_eq!(company_key("Netcompany A/S"), company_key("Netcompany"));
+    }
+
+    #[test]
+    fn aliases_resolve() {
+        assert_eq!(company_key("DTU, Danmarks Tekniske Universitet"), "dtu");
+        assert_eq!(company_key("Danmarks Tekniske Universitet"), "dtu");
+        assert_eq!(company_key("Københavns Universitet"), "ku");
+        assert_eq!(company_key("Kamstrup A/S"), "kamstrup");
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use crate::job_fetchers::company::company_key;
+use crate::services::database_service::types::{Job, JobCluster, JobPosting};
+
+/// Postings with the same title are duplicates when their SimHashes differ in
+/// at most this many bits, which allows for small edits between reposts.
+const SAME_TITLE_DISTANCE: u32 = 12;
+/// A retitled posting has to be close to identical.
+const RETITLED_DISTANCE: u32 = 4;
+/// Words per shingle.
+const SHINGLE_SIZE: usize = 3;
+
+/// What a job is compared by to find reposts of it, both in the fetched jobs
+/// and in the jobs already in the database. Only jobs of the same company,
+/// see `company_key`, are compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobFingerprint {
+    pub title: String,
+    /// Rounded coordinates of the locations, sorted.
+    pub locations: Vec<String>,
+    /// SimHash of the word shingles of the description.
+    pub simhash: u64,
+}
+
+impl JobFingerprint {
+    pub fn new(job: &Job) -> Self {
+        let mut locations: Vec<String> = job
+            .locations
+            .iter()
+            .map(|location| {
+                let (x, y) = location.geo_location;
+                // two decimals is about a kilometre
+                format!("{x:.2},{y:.2}")
+            })
+            .collect();
+        locations.sort();
+        locations.dedup();
+
+        JobFingerprint {
+            title: words(&job.job_info.title.0).join(" "),
+            locations,
+            simhash: simhash(&job.job_info.description.0),
+        }
+    }
+
+    pub fn is_near_duplicate(&self, other: &JobFingerprint) -> bool {
+        let locations_overlap = self.locations.is_empty()
+            || other.locations.is_empty()
+            || self
+                .locations
+                .iter()
+                .any(|location| other.locations.contains(location));
+        let distance = (self.simhash ^ other.simhash).count_ones();
+
+        locations_overlap
+            && (distance <= RETITLED_DISTANCE
+                || self.title == other.title && distance <= SAME_TITLE_DISTANCE)
+    }
+}
+
+/// Groups near-duplicate jobs into clusters, in the order their first job
+/// appears. The newest job of a cluster is its canonical job.
+pub fn cluster_jobs(jobs: Vec<Job>) -> Vec<JobCluster> {
+    let fingerprints: Vec<JobFingerprint> =
+        jobs.iter().map(JobFingerprint::new).collect();
+
+    let mut by_company: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, job) in jobs.iter().enumerate() {
+        by_company
+            .entry(company_key(&job.company_info.name))
+            .or_default()
+            .push(idx);
+    }
+
+    // union-find where every cluster is rooted at its first job
+    let mut parent: Vec<usize> = (0..jobs.len()).collect();
+    fn root(parent: &[usize], mut idx: usize) -> usize {
+        while parent[idx] != idx {
+            idx = parent[idx];
+        }
+        idx
+    }
+    for company_jobs in by_company.values() {
+        for (nth, &job) in company_jobs.iter().enumerate() {
+            for &other in &company_jobs[..nth] {
+                if fingerprints[job].is_near_duplicate(&fingerprints[other]) {
+                    let (a, b) = (root(&parent, job), root(&parent, other));
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut members: Vec<Vec<Job>> =
+        (0..jobs.len()).map(|_| Vec::new()).collect();
+    for (idx, job) in jobs.into_iter().enumerate() {
+        members[root(&parent, idx)].push(job);
+    }
+
+    members
+        .into_iter()
+        .filter(|cluster| !cluster.is_empty())
+        .map(|mut cluster| {
+            let newest = cluster
+                .iter()
+                .enumerate()
+                .max_by(|(a_idx, a), (b_idx, b)| {
+                    // ties go to the job listed first
+                    a.created_at.cmp(&b.created_at).then(b_idx.cmp(a_idx))
+                })
+                .map(|(idx, _)| idx)
+                .unwrap_or_default();
+            let canonical = cluster.remove(newest);
+            JobCluster {
+                canonical,
+                duplicates: cluster.into_iter().map(JobPosting::from).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Lowercased alphanumeric words.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// SimHash over the word shingles of `text`, so similar texts get hashes a
+/// small Hamming distance apart.
+pub fn simhash(text: &str) -> u64 {
+    let words = words(text);
+    let mut counts = [0i32; 64];
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len()).max(1)) {
+        let hash = fnv1a(&shingle.join(" "));
+        for (bit, count) in counts.iter_mut().enumerate() {
+            *count += if (hash >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+/// The hashes are stored, so this has to be stable across builds, which
+/// `DefaultHasher` is not.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+    use crate::services::database_service::types::{
+        CompanyInfo, Description, JobClassification, JobInfo, JobUrl, Location,
+        Title,
+    };
+
+    const AD: &str = "Vi søger en erfaren backend-udvikler til vores team i \
+        Aarhus. Du kommer til at arbejde med Rust og PostgreSQL på vores \
+        betalingsplatform, og du får ansvar for drift, overvågning og \
+        videreudvikling af vores services. Vi tilbyder en uformel kultur, \
+        fleksible arbejdstider og gode muligheder for efteruddannelse.";
+
+    fn job(
+        url: &str,
+        company: &str,
+        title: &str,
+        description: &str,
+        day: u32,
+    ) -> Job {
+        Job {
+            job_info: JobInfo {
+                job_url: JobUrl(url.to_owned()),
+                title: Title(title.to_owned()),
+                description: Description(description.to_owned()),
+                job_tags: Vec::new(),
+                classification: JobClassification::default(),
+                salary: None,
+            },
+            created_at: DateTime::<Utc>::from_timestamp(
+                i64::from(day) * 86_400,
+                0,
+            )
+            .unwrap(),
+            last_date: None,
+            company_info: CompanyInfo {
+                name: company.to_owned(),
+                logo_url: String::new(),
+                profile_url: None,
+                rating: None,
+            },
+            locations: vec![Location {
+                address: "Aarhus".to_owned(),
+                geo_location: (56.1567, 10.2108),
+            }],
+            geoarea_ids: Vec::new(),
+            contact_info: None,
+        }
+    }
+
+    fn urls(cluster: &JobCluster) -> Vec<&str> {
+        std::iter::once(&cluster.canonical.job_info.job_url)
+            .chain(cluster.duplicates.iter().map(|posting| &posting.job_url))
+            .map(|url| url.0.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn similar_texts_have_close_hashes() {
+        let repost = AD.replace("gode muligheder", "rigtig gode muligheder");
+        let other = "We are looking for a designer to shape the look and \
+            feel of our mobile apps together with product and marketing.";
+
+        let close = (simhash(AD) ^ simhash(&repost)).count_ones();
+        let far = (simhash(AD) ^ simhash(other)).count_ones();
+        assert!(close <= SAME_TITLE_DISTANCE, "{close}");
+        assert!(far > SAME_TITLE_DISTANCE, "{far}");
+        assert_eq!(simhash(AD), simhash(&AD.to_uppercase()));
+    }
+
+    #[test]
+    fn reposts_are_clustered() {
+        let repost = AD.replace("Vi tilbyder", "Til gengæld tilbyder vi");
+        let clusters = cluster_jobs(vec![
+            job("a", "Acme A/S", "Backend-udvikler", AD, 1),
+            job("b", "Acme A/S", "Backend udvikler", &repost, 3),
+            job(
+                "c",
+                "Acme A/S",
+                "Frontend-udvikler",
+                "Vi søger en \
+                 frontend-udvikler med erfaring i React og TypeScript.",
+                2,
+            ),
+        ]);
+
+        assert_eq!(clusters.len(), 2);
+        // the newest posting is canonical
+        assert_eq!(urls(&clusters[0]), ["b", "a"]);
+        assert_eq!(urls(&clusters[1]), ["c"]);
+    }
+
+    #[test]
+    fn same_ad_from_another_company_is_kept() {
+        let clusters = cluster_jobs(vec![
+            job("a", "Acme A/S", "Backend-udvikler", AD, 1),
+            job("b", "Globex ApS", "Backend-udvikler", AD, 1),
+        ]);
+        assert_eq!(clusters.len(), 2);
+    }
+
+    #[test]
+    fn same_ad_in_another_city_is_kept() {
+        let mut copenhagen = job("b", "Acme A/S", "Backend-udvikler", AD, 1);
+        copenhagen.locations[0].geo_location = (55.6761, 12.5683);
+
+        let clusters = cluster_jobs(vec![
+            job("a", "Acme A/S", "Backend-udvikler", AD, 1),
+            copenhagen,
+        ]);
+        assert_eq!(clusters.len(), 2);
+    }
+
+    #[test]
+    fn retitled_copies_are_clustered() {
+        let clusters = cluster_jobs(vec![
+            job("a", "Acme A/S", "Backend-udvikler", AD, 1),
+            job("b", "ACME A/S", "Senior Backend Engineer", AD, 2),
+        ]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(urls(&clusters[0]), ["b", "a"]);
+    }
+}
@@ -2,6 +2,7 @@ use std::pin::pin;
 use std::sync::Arc;
 
 use bytes::Bytes;
+use chrono_tz::Tz;
 use futures::StreamExt;
 
 use tokio::io::AsyncWriteExt;
@@ -15,7 +16,7 @@ use crate::job_fetchers::preview::unique_job;
 use crate::job_fetchers::{Job, JobFetcher};
 use crate::services::database_service::database::DataBase;
 use crate::util::from_query::CreateQuery;
-use crate::util::options::FetchOptions;
+use crate::util::options::{FetchOptions, FilterOptions, SizeOptions};
 
 use async_compression::tokio::write::GzipDecoder;
 
@@ -25,7 +26,9 @@ pub struct JobIndex {
     pub(super) urls: JobIndexUrls,
 }
 impl DateFormat for JobIndex {
-    const DATE_FORMAT: &'static str = "%Y-%M-%D";
+    const DATE_FORMATS: &'static [&'static str] =
+        &["%Y-%m-%d", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"];
+    const TIME_ZONE: Tz = chrono_tz::Europe::Copenhagen;
 }
 pub(super) struct JobIndexUrls {
     job_search: Url,
@@ -60,7 +63,90 @@ impl JobFetcher for JobIndex {
         options: &'a FetchOptions,
         database: Option<&'a DataBase>,
     ) -> Option<Vec<Job>> {
-        let database = database;
+        let filter = &options.filter_options;
+        let mut page = match options.size_options {
+            _ if filter.is_empty() => {
+                return self.fetch_pages(options, database).await;
+            }
+            SizeOptions::All => {
+                let jobs = self.fetch_pages(options, database).await?;
+                return Some(filter.filter(jobs));
+            }
+            SizeOptions::Page { page_size, page } => {
+                FilteredPage::new(page_size.saturating_mul(page), page_size)
+            }
+            SizeOptions::NotPaged { jobs } => FilteredPage::new(0, jobs),
+        };
+
+        // the filters are on what is classified from the ads, so JobIndex
+        // pages are read until enough of their jobs pass
+        for number in 0..Self::MAX_FILTERED_PAGES {
+            let page_options = FetchOptions {
+                query_options: options.query_options.clone(),
+                size_options: SizeOptions::Page {
+                    page_size: Self::PAGE_SIZE,
+                    page: number,
+                },
+                filter_options: FilterOptions::default(),
+            };
+            let jobs = match self.fetch_pages(&page_options, database).await {
+                Some(jobs) => jobs,
+                // a page past the last one can fail as well as be empty
+                None if number == 0 => return None,
+                None => break,
+            };
+            let last = jobs.len() < Self::PAGE_SIZE;
+            if page.add(filter, jobs) || last {
+                break;
+            }
+        }
+        Some(page.jobs)
+    }
+}
+
+/// The jobs of a page of filtered results, collected from the JobIndex
+/// pages in order.
+struct FilteredPage {
+    /// Matching jobs on the pages before this one.
+    skip: usize,
+    take: usize,
+    jobs: Vec<Job>,
+}
+
+impl FilteredPage {
+    fn new(skip: usize, take: usize) -> Self {
+        FilteredPage {
+            skip,
+            take,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Adds the next JobIndex page, and tells whether the page is full.
+    fn add(&mut self, filter: &FilterOptions, jobs: Vec<Job>) -> bool {
+        for job in filter.filter(jobs) {
+            if self.skip > 0 {
+                self.skip -= 1;
+            } else if self.jobs.len() < self.take {
+                self.jobs.push(job);
+            }
+        }
+        self.jobs.len() >= self.take
+    }
+}
+
+impl JobIndex {
+    /// The most JobIndex pages read to fill a filtered page, so a filter
+    /// that hardly matches does not read every ad there is.
+    pub const MAX_FILTERED_PAGES: usize = 25;
+
+    /// The jobs of `options` as JobIndex pages them, leaving the filters
+    /// out.
+    async fn fetch_pages(
+        &self,
+        options: &FetchOptions,
+        database: Option<&DataBase>,
+    ) -> Option<Vec<Job>> {
         let (offset, queries) = self.create_query(options).await.ok()?;
 
         let stream = queries.map(async move |(jobs, url)| {
@@ -124,3 +210,109 @@ impl JobIndex {
             .map(|str| str.as_bytes().to_vec())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::services::database_service::types::{
+        CompanyInfo, Currency, Description, JobClassification, JobInfo, JobUrl,
+        Salary, SalaryPeriod, Seniority, Title,
+    };
+
+    fn job(number: usize) -> Job {
+        Job {
+            job_info: JobInfo {
+                job_url: JobUrl(format!("https://example.com/jobs/{number}")),
+                title: Title(format!("Job {number}")),
+                description: Description(String::new()),
+                job_tags: Vec::new(),
+                classification: JobClassification::default(),
+                salary: None,
+            },
+            created_at: Utc::now(),
+            last_date: None,
+            company_info: CompanyInfo {
+                name: "Acme".to_owned(),
+                logo_url: String::new(),
+                profile_url: None,
+                rating: None,
+            },
+            locations: Vec::new(),
+            geoarea_ids: Vec::new(),
+            contact_info: None,
+        }
+    }
+
+    /// A JobIndex page of `PAGE_SIZE` jobs from `first` on, where every
+    /// `every`th one is senior.
+    fn seniority_page(first: usize, every: usize) -> Vec<Job> {
+        (first..first + JobIndex::PAGE_SIZE)
+            .map(|number| {
+                let mut job = job(number);
+                if number % every == 0 {
+                    job.job_info.classification.seniority =
+                        Some(Seniority::Senior);
+                }
+                job
+            })
+            .collect()
+    }
+
+    fn numbers(page: &FilteredPage) -> Vec<String> {
+        page.jobs
+            .iter()
+            .map(|job| job.job_info.title.0.replace("Job ", ""))
+            .collect()
+    }
+
+    #[test]
+    fn filtered_pages_are_filled_from_later_pages() {
+        let filter = FilterOptions {
+            seniority: vec![Seniority::Senior],
+            ..Default::default()
+        };
+        // the second page of three senior jobs, one in every ten ads
+        let mut page = FilteredPage::new(3, 3);
+        assert!(!page.add(&filter, seniority_page(0, 10)));
+        assert!(!page.add(&filter, seniority_page(20, 10)));
+        assert!(page.add(&filter, seniority_page(40, 10)));
+        assert_eq!(numbers(&page), ["30", "40", "50"]);
+
+        // the last page falls short when the ads run out
+        let mut page = FilteredPage::new(0, 30);
+        assert!(!page.add(&filter, seniority_page(0, 10)));
+        assert_eq!(numbers(&page), ["0", "10"]);
+    }
+
+    #[test]
+    fn salary_bounds_fill_pages_like_the_other_filters() {
+        let filter = FilterOptions {
+            salary_min: Some(50_000),
+            ..Default::default()
+        };
+        // every fifth ad states a salary, rising with the ad
+        let page = |first: usize| -> Vec<Job> {
+            (first..first + JobIndex::PAGE_SIZE)
+                .map(|number| {
+                    let mut job = job(number);
+                    if number % 5 == 0 {
+                        job.job_info.salary = Some(Salary {
+                            min: Some(40_000 + 1_000 * number as i32),
+                            max: None,
+                            currency: Some(Currency::Dkk),
+                            period: Some(SalaryPeriod::Month),
+                            negotiable: false,
+                        });
+                    }
+                    job
+                })
+                .collect()
+        };
+        let mut filtered = FilteredPage::new(0, 3);
+        assert!(!filtered.add(&filter, page(0)));
+        assert!(filtered.add(&filter, page(20)));
+        assert_eq!(numbers(&filtered), ["10", "15", "20"]);
+    }
+}
@@ -1,31 +1,29 @@
+use std::borrow::Cow;
+
 use chrono::{DateTime, Utc};
 use memchr::memmem;
 use serde::Deserialize;
 use serde::de::Error;
 
+use crate::job_fetchers::classifier::classify_job;
 use crate::job_fetchers::preview::{JobPreview, parse_date};
+use crate::job_fetchers::salary::parse_salary;
 use crate::{
-    job_fetchers::{JOB_TAGS, job_index::fetcher::JobIndex},
+    job_fetchers::{job_index::fetcher::JobIndex, tag_matcher::TAG_MATCHER},
     services::database_service::types::{
-        CompanyInfo, Description, Job, JobInfo, JobTag, JobUrl, Location, Title,
+        CompanyInfo, CompanyRating, Description, Job, JobInfo, JobTag, JobUrl,
+        Location, Title,
     },
 };
 
-pub struct JobIndexHtmlInfo((Vec<JobTag>, Description));
+pub struct JobIndexHtmlInfo(Description);
 
 impl JobIndexHtmlInfo {
-    fn extract_jobs_tags(description: &str) -> Vec<JobTag> {
-        JOB_TAGS
-            .iter()
-            .filter_map(|(tag, matchers)| {
-                matchers
-                    .iter()
-                    .find_map(|needle: &&str| {
-                        memmem::find(description.as_bytes(), needle.as_bytes())
-                    })
-                    .map(|_| JobTag { name: tag })
-            })
-            .collect()
+    fn extract_jobs_tags(
+        title: &Title,
+        description: &Description,
+    ) -> Vec<JobTag> {
+        TAG_MATCHER.job_tags(&title.0, &description.0)
     }
 }
 
@@ -34,26 +32,29 @@ impl<'de> Deserialize<'de> for JobIndexHtmlInfo {
     where
         D: serde::Deserializer<'de>,
     {
-        let start_seq = b"</div>\\n\\n<p>";
-        let end_seq = b"</p>\\n";
-        let string = <&str>::deserialize(deserializer)?;
+        let start_seq = b"</div>\n\n<p>";
+        let end_seq = b"</p>\n";
+        // owned, as the html is full of escaped quotes and newlines
+        let string = String::deserialize(deserializer)?;
 
         let start_idx = memmem::find(string.as_bytes(), start_seq)
             .ok_or(serde::de::Error::custom("start seq dosent exist"))?
             + start_seq.len();
 
-        let end_idx = memmem::find(string.as_bytes(), end_seq)
-            .ok_or(serde::de::Error::custom("start seq dosent exist"))?;
+        // an ad that ends in a list has no closing paragraph, but it still
+        // ends with its line
+        let rest = &string.as_bytes()[start_idx..];
+        let end_idx = memmem::find(rest, end_seq)
+            .or_else(|| memchr::memchr(b'\n', rest))
+            .ok_or(serde::de::Error::custom("end seq dosent exist"))?
+            + start_idx;
 
         let description: String = string[start_idx..end_idx]
             .split("<p>")
             .flat_map(|x| x.split("</p>"))
             .filter(|x| !x.is_empty())
             .collect();
-        Ok(JobIndexHtmlInfo((
-            JobIndexHtmlInfo::extract_jobs_tags(&description),
-            Description(description),
-        )))
+        Ok(JobIndexHtmlInfo(Description(description)))
     }
 }
 
@@ -82,23 +83,20 @@ impl<'de> Deserialize<'de> for JobIndexLocation {
         D: serde::Deserializer<'de>,
     {
         #[derive(Deserialize)]
-        struct Addresses<'a> {
-            city: &'a str,
+        struct Addresses {
             #[serde(
                 deserialize_with = "JobIndexLocation::deserialize_location"
             )]
             coordinates: (f64, f64),
             #[serde(rename(deserialize = "simple_string"))]
-            address: &'a str,
-            #[serde(rename(deserialize = "zipcode"))]
-            zip_code: &'a str,
+            address: String,
         }
 
         Ok(JobIndexLocation(
             Vec::<Addresses>::deserialize(deserializer)?
                 .into_iter()
                 .map(|loc| Location {
-                    address: loc.address.to_owned(),
+                    address: loc.address,
                     geo_location: loc.coordinates,
                 })
                 .collect(),
@@ -116,14 +114,28 @@ impl<'de> Deserialize<'de> for JobIndexCompany {
         struct Company {
             name: String,
             get_logo_company: String,
+            logo: Option<String>,
+            companyprofile_url: Option<String>,
         }
         let company_info: Company = Company::deserialize(deserializer)?;
         Ok(JobIndexCompany(CompanyInfo {
             name: company_info.name,
-            logo_url: company_info.get_logo_company,
+            // the full logo when there is one, the company page logo otherwise
+            logo_url: company_info
+                .logo
+                .unwrap_or(company_info.get_logo_company),
+            profile_url: company_info.companyprofile_url,
+            // the rating is sent next to the company, see `JobIndexRating`
+            rating: None,
         }))
     }
 }
+#[derive(Deserialize)]
+struct JobIndexRating {
+    score: f64,
+    ratings: i32,
+}
+
 struct JobIndexJobUrl(JobUrl);
 impl<'de> Deserialize<'de> for JobIndexJobUrl {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -163,45 +175,85 @@ impl<'de> Deserialize<'de> for JobIndexDate {
 #[derive(Deserialize)]
 struct JobIndexData {
     html: JobIndexHtmlInfo,
-    company: JobIndexCompany,
+    /// `null` for a company JobIndex has no page for, which only has its
+    /// name in `companytext`.
+    company: Option<JobIndexCompany>,
+    #[serde(default)]
+    companytext: Option<String>,
+    /// `headline` in the result pages.
+    #[serde(alias = "headline")]
     title: JobIndexTitle,
+    /// `share_url` in the result pages, as `url` goes through a click
+    /// counter.
+    #[serde(alias = "share_url")]
     job_url: JobIndexJobUrl,
 
     #[serde(rename(deserialize = "addresses"))]
     locations: JobIndexLocation,
     #[serde(rename(deserialize = "lastdate"))]
     last_date: JobIndexDate,
+    #[serde(default)]
+    rating: Option<JobIndexRating>,
+    /// Every area the job lies in, like the country, the region and the
+    /// postcode of each address.
+    #[serde(default)]
+    geoareaid: Vec<i64>,
 }
 
 impl<'de> TryFrom<&'de [u8]> for JobPreview<'de, JobIndex> {
-    type Error = ();
+    type Error = serde_json::Error;
     fn try_from(full_post: &'de [u8]) -> Result<Self, Self::Error> {
         #[derive(Deserialize)]
 
         struct Tmp<'a> {
-            job_url: &'a str,
+            #[serde(borrow, alias = "share_url")]
+            job_url: Cow<'a, str>,
+            #[serde(alias = "firstdate")]
             created_at: JobIndexDate,
         }
 
         let Tmp {
             job_url,
             created_at: JobIndexDate(date),
-        } = serde_json::from_slice(full_post).map_err(|_| ())?;
+        } = serde_json::from_slice(full_post)?;
         Ok(JobPreview::new(job_url, date, full_post))
     }
 }
 
 impl<'a> TryFrom<JobPreview<'a, JobIndex>> for Job {
-    type Error = ();
+    type Error = serde_json::Error;
     fn try_from(value: JobPreview<'a, JobIndex>) -> Result<Self, Self::Error> {
         let JobIndexData {
-            html: JobIndexHtmlInfo((job_tags, description)),
-            company: JobIndexCompany(company_info),
+            html: JobIndexHtmlInfo(description),
+            company,
+            companytext,
             title: JobIndexTitle(title),
             job_url: JobIndexJobUrl(job_url),
             locations: JobIndexLocation(locations),
             last_date: JobIndexDate(last_date),
-        } = serde_json::from_slice(value.full_post).map_err(|_| ())?;
+            rating,
+            geoareaid,
+        } = serde_json::from_slice(value.full_post)?;
+        let job_tags =
+            JobIndexHtmlInfo::extract_jobs_tags(&title, &description);
+        let mut company_info = match (company, companytext) {
+            (Some(JobIndexCompany(company_info)), _) => company_info,
+            (None, Some(name)) => CompanyInfo {
+                name,
+                logo_url: String::new(),
+                profile_url: None,
+                rating: None,
+            },
+            (None, None) => {
+                return Err(Error::custom("the job has no company"));
+            }
+        };
+        let classification = classify_job(&title, &description);
+        let salary = parse_salary(&description.0);
+        company_info.rating = rating.map(|rating| CompanyRating {
+            score: rating.score,
+            count: rating.ratings,
+        });
 
         Ok(Job {
             job_info: JobInfo {
@@ -209,11 +261,14 @@ impl<'a> TryFrom<JobPreview<'a, JobIndex>> for Job {
                 title,
                 description,
                 job_tags,
+                classification,
+                salary,
             },
             created_at: value.date,
             last_date: Some(last_date),
             company_info,
             locations,
+            geoarea_ids: geoareaid,
             contact_info: None,
         })
     }
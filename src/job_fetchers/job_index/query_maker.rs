@@ -5,6 +5,10 @@ use sqlx::types::JsonRawValue;
 
 use crate::{
     job_fetchers::job_index::fetcher::JobIndex,
+    services::{
+        database_service::types::{Region, RegionKind},
+        region_service::region_query_ids,
+    },
     util::{
         from_query::CreateQuery,
         options::{FetchOptions, QueryOptions, SizeOptions},
@@ -33,21 +37,22 @@ impl CreateQuery<&QueryOptions> for JobIndex {
             QueryOptions::Query {
                 job_name,
                 job_regions,
-                ..
-            } => Ok(self.get_region_query(job_regions).await.chain(
-                stream::once(async {
-                    job_name.as_ref().map(|job_name| {
-                        ("q".into(), job_name.to_owned().into())
-                    })
-                })
-                .filter_map(async |x| x),
-            )),
+                job_tags,
+            } => {
+                let words = search_words(job_name.as_deref(), job_tags);
+                Ok(self.get_region_query(job_regions).await.chain(
+                    stream::iter(words.map(|words| ("q".into(), words.into()))),
+                ))
+            }
             QueryOptions::All => Err(()),
         }
     }
 }
 
 impl JobIndex {
+    /// How many regions a name is resolved among.
+    const REGION_CANDIDATES: usize = 10;
+
     pub async fn get_region_query(
         &self,
         regions: &[String],
@@ -55,12 +60,30 @@ impl JobIndex {
         let regions = regions.iter();
 
         futures::stream::iter(regions)
-            .filter_map(async |region| self.take_first_region(region).await)
+            .filter_map(async |region| self.region_query(region).await)
+            .flat_map(stream::iter)
     }
-    pub async fn take_first_region(
+
+    /// The `geoareaid` parameters for `job_region`, more than one where the
+    /// name is ambiguous, see `region_query_ids`.
+    pub async fn region_query(
         &self,
         job_region: &str,
-    ) -> Option<(Arc<str>, Arc<str>)> {
+    ) -> Option<Vec<(Arc<str>, Arc<str>)>> {
+        let candidates = self.region_candidates(job_region).await?;
+        Some(
+            region_query_ids(job_region, candidates)
+                .into_iter()
+                .map(|id| ("geoareaid".into(), id.to_string().into()))
+                .collect(),
+        )
+    }
+
+    /// The regions JobIndex completes `job_region` to, best first.
+    pub async fn region_candidates(
+        &self,
+        job_region: &str,
+    ) -> Option<Vec<Region>> {
         #[derive(Deserialize)]
         struct GeoLoc<'a> {
             #[serde(borrow)]
@@ -74,23 +97,67 @@ impl JobIndex {
         }
         #[derive(Deserialize)]
 
-        struct Uuid {
-            id: u64,
+        struct Completion {
+            id: i64,
+            #[serde(default)]
+            text: String,
+            code: Option<String>,
+            #[serde(default)]
+            typeid: i64,
         }
         let mut url = self.urls.job_regions.clone();
         url.query_pairs_mut()
             .append_pair("q", job_region)
-            .append_pair("limit", "1")
+            .append_pair("limit", &Self::REGION_CANDIDATES.to_string())
             .finish();
         let res = reqwest::get(url.as_str()).await.ok()?;
         let json = res.text().await.ok()?;
         let loc: GeoLoc = serde_json::from_str(&json).ok()?;
-        let uuid =
+        let candidates =
             serde_json::Deserializer::from_str(loc.geoareaid.completions.get())
-                .into_iter_seq::<Uuid>()
-                .find_map(|uuid| uuid.ok())?;
+                .into_iter_seq::<Completion>()
+                .filter_map(|completion| completion.ok())
+                .map(|completion| Region {
+                    id: completion.id,
+                    name: completion.text,
+                    code: completion.code,
+                    kind: region_kind(completion.typeid),
+                    parent_id: None,
+                })
+                .collect();
 
-        Some(("geoareaid".into(), uuid.id.to_string().into()))
+        Some(candidates)
+    }
+}
+
+/// The words JobIndex searches the ads for. It has no search on tags, so the
+/// tags are searched for as words next to the job name, and like those an ad
+/// needs all of them. A tag of more words is searched for as a phrase.
+fn search_words(job_name: Option<&str>, job_tags: &[String]) -> Option<String> {
+    let tags = job_tags.iter().map(|tag| {
+        if tag.contains(' ') {
+            format!("\"{tag}\"")
+        } else {
+            tag.to_owned()
+        }
+    });
+    let words: Vec<String> = job_name
+        .map(str::to_owned)
+        .into_iter()
+        .chain(tags)
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// The kind of region of a JobIndex `typeid`. Everything that is not an
+/// administrative area, like "Storkøbenhavn", is typed "Område".
+fn region_kind(type_id: i64) -> RegionKind {
+    match type_id {
+        10 => RegionKind::Country,
+        20 => RegionKind::Region,
+        30 => RegionKind::Municipality,
+        50 => RegionKind::Postcode,
+        _ => RegionKind::Area,
     }
 }
 use std::sync::Arc;
@@ -230,7 +297,7 @@ mod tests {
         let mock = server
             .mock(
                 "GET",
-                "/api/jobsearch/v3/autocomplete?&types=geoareaid&q=abc&limit=1",
+                "/api/jobsearch/v3/autocomplete?&types=geoareaid&q=abc&limit=10",
             )
             .with_body(
                 r#"{"geoareaid": {"completions": [{"id":3000,"abc":1000}]}}"#,
@@ -262,6 +329,40 @@ mod tests {
             })
     }
 
+    #[test]
+    fn searches_for_the_tags_with_the_job_name() {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let job = JobIndex::default();
+                let options = QueryOptions::Query {
+                    job_name: Some("developer".to_string()),
+                    job_regions: vec![],
+                    job_tags: vec![
+                        "Rust".to_string(),
+                        "Machine Learning".to_string(),
+                    ],
+                };
+                let query: Vec<(String, String)> = job
+                    .create_query(&options)
+                    .await
+                    .expect("should unwrap")
+                    .map(|val| (val.0.to_string(), val.1.to_string()))
+                    .collect()
+                    .await;
+
+                assert_eq!(
+                    query,
+                    [(
+                        "q".to_string(),
+                        r#"developer Rust "Machine Learning""#.to_string()
+                    )]
+                );
+            })
+    }
+
     #[test]
 
     fn test_size_options() {
@@ -271,7 +372,7 @@ mod tests {
         let mock = server
             .mock(
                 "GET",
-                "/api/jobsearch/v3/autocomplete?&types=geoareaid&q=abc&limit=1",
+                "/api/jobsearch/v3/autocomplete?&types=geoareaid&q=abc&limit=10",
             )
             .with_body(
                 r#"{"geoareaid": {"completions": [{"id":3000,"abc":1000}]}}"#,
@@ -1,7 +1,12 @@
+pub mod classifier;
+pub mod company;
+pub mod dedup;
 pub mod job_index;
 
 pub mod jobs;
 pub mod preview;
+pub mod salary;
+pub mod tag_matcher;
 use futures::{
     StreamExt,
     stream::{self, FuturesUnordered},
@@ -16,7 +21,7 @@ pub const JOB_TAGS: &[(&str, &[&str])] = &[
     ("C#", &["c#", "c-sharp", "c sharp", "csharp"]),
     ("Python", &["python"]),
     ("Rust", &["rust"]),
-    ("Go", &["go", "goLang", "go lang"]),
+    ("Go", &["go", "golang", "go lang"]),
     (
         "Javscript/Typescript",
         &["javascript", "js", "ts", "typescript"],
@@ -43,16 +48,34 @@ pub const JOB_TAGS: &[(&str, &[&str])] = &[
         ],
     ),
     ("Docker", &["kubernetes", "docker"]),
-    ("Frontend", &["frontend", "front end"]),
-    ("Backend", &["backend", "back end"]),
+    (
+        "Frontend",
+        &[
+            "frontend",
+            "front end",
+            "fullstack",
+            "full-stack",
+            "full stack",
+        ],
+    ),
+    (
+        "Backend",
+        &[
+            "backend",
+            "back end",
+            "fullstack",
+            "full-stack",
+            "full stack",
+        ],
+    ),
     (
         "AI",
-        &["machine learning", "ai", "machineLearning", "ml", "llm"],
+        &["machine learning", "ai", "machinelearning", "ml", "llm"],
     ),
-    ("Angular", &["angularJs", "angular"]),
+    ("Angular", &["angularjs", "angular"]),
     ("React", &["reactjs", "react js", "react"]),
-    (".Net", &[".net", "dot net", "asp.net", "dot-net"]),
-    ("Spring", &["Javaspring", "spring", "spring-framework"]),
+    (".Net", &[".net", "dot net", "asp.net", "dot-net", "blazor"]),
+    ("Spring", &["javaspring", "spring", "spring-framework"]),
 ];
 
 impl<const N: usize, J: JobFetcher> JobFetcher for [J; N] {
@@ -73,6 +96,10 @@ impl<const N: usize, J: JobFetcher> JobFetcher for [J; N] {
     }
 }
 
+#[allow(
+    async_fn_in_trait,
+    reason = "JobIndex holds parsed pages, which are not Send, across awaits"
+)]
 pub trait JobFetcher {
     async fn fetch_all_jobs_with_options_and_db<'a>(
         &'a self,
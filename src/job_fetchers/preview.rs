@@ -1,23 +1,145 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::marker::PhantomData;
 
-use chrono::{DateTime, NaiveDateTime, ParseError, Utc};
+use chrono::{
+    DateTime, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
 use futures::{StreamExt, stream};
 use serde::Deserialize;
 
+/// How a source writes its dates.
+pub trait DateFormat {
+    /// Accepted formats, tried in order. A format without a time part is
+    /// read as midnight in `TIME_ZONE`.
+    const DATE_FORMATS: &'static [&'static str];
+    /// The timezone dates without an offset are written in.
+    const TIME_ZONE: Tz = Tz::UTC;
+    /// Whether dates like "2 days ago" or "i går" are accepted.
+    const RELATIVE_DATES: bool = false;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    /// The date matched none of the formats of the source.
+    Format(String),
+    /// The local time is skipped by a daylight saving transition.
+    NonExistent(NaiveDateTime),
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateError::Format(date) => {
+                write!(f, "{date:?} matches no known date format")
+            }
+            DateError::NonExistent(date) => {
+                write!(f, "{date} does not exist in the source timezone")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
 pub fn parse_date<J: DateFormat>(
     date: &str,
-) -> Result<DateTime<Utc>, ParseError> {
-    let dt = NaiveDateTime::parse_from_str(&date, J::DATE_FORMAT)?;
+) -> Result<DateTime<Utc>, DateError> {
+    parse_date_at::<J>(date, Utc::now())
+}
+
+/// Like `parse_date`, with relative dates counted back from `now`.
+pub fn parse_date_at<J: DateFormat>(
+    date: &str,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, DateError> {
+    let date = date.trim();
 
-    Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+    // an explicit offset always wins over the source timezone.
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Ok(date.to_utc());
+    }
+    if let Some(naive) = J::DATE_FORMATS.iter().find_map(|format| {
+        NaiveDateTime::parse_from_str(date, format)
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(date, format)
+                    .ok()
+                    .map(|date| date.and_time(NaiveTime::MIN))
+            })
+    }) {
+        // an ambiguous time, when the clocks go back, is read as the first
+        // of the two.
+        return J::TIME_ZONE
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|date| date.to_utc())
+            .ok_or(DateError::NonExistent(naive));
+    }
+    if J::RELATIVE_DATES
+        && let Some(date) = parse_relative_date(date, now)
+    {
+        return Ok(date);
+    }
+    Err(DateError::Format(date.to_owned()))
 }
-pub trait DateFormat {
-    const DATE_FORMAT: &'static str;
+
+/// Relative dates in English or Danish, e.g. "2 days ago", "an hour ago",
+/// "yesterday", "for 3 uger siden" or "i går".
+fn parse_relative_date(
+    date: &str,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let date = date.to_lowercase();
+    match date.as_str() {
+        "now" | "just now" | "today" | "lige nu" | "i dag" => {
+            return Some(now);
+        }
+        "yesterday" | "i går" => return now.checked_sub_days(Days::new(1)),
+        _ => {}
+    }
+
+    let words: Vec<&str> = date
+        .strip_prefix("for ")
+        .unwrap_or(&date)
+        .split_whitespace()
+        .collect();
+    let [count, unit, "ago" | "siden"] = words[..] else {
+        return None;
+    };
+    let count: u32 = match count {
+        "a" | "an" | "en" | "et" => 1,
+        count => count.parse().ok()?,
+    };
+
+    match unit {
+        "minute" | "minutes" | "min" | "mins" | "minut" | "minutter" => {
+            now.checked_sub_signed(TimeDelta::minutes(count.into()))
+        }
+        "hour" | "hours" | "time" | "timer" => {
+            now.checked_sub_signed(TimeDelta::hours(count.into()))
+        }
+        "day" | "days" | "dag" | "dage" => {
+            now.checked_sub_days(Days::new(count.into()))
+        }
+        "week" | "weeks" | "uge" | "uger" => {
+            now.checked_sub_days(Days::new(u64::from(count) * 7))
+        }
+        "month" | "months" | "måned" | "måneder" => {
+            now.checked_sub_months(Months::new(count))
+        }
+        "year" | "years" | "år" => {
+            now.checked_sub_months(Months::new(count.checked_mul(12)?))
+        }
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct JobPreview<'a, J> {
-    pub job_url: &'a str,
+    pub job_url: Cow<'a, str>,
     pub date: DateTime<chrono::Utc>,
     pub full_post: &'a [u8],
     _phantom: PhantomData<J>,
@@ -25,12 +147,12 @@ pub struct JobPreview<'a, J> {
 
 impl<'a, J> JobPreview<'a, J> {
     pub fn new(
-        job_url: &'a str,
+        job_url: impl Into<Cow<'a, str>>,
         date: DateTime<chrono::Utc>,
         full_post: &'a [u8],
     ) -> Self {
         Self {
-            job_url,
+            job_url: job_url.into(),
             date,
             full_post,
             _phantom: PhantomData,
@@ -92,10 +214,9 @@ where
             .flat_map(|val| Some(JobPreview::<T>::try_from(val)))
             .skip(offset)
             .take(jobs_to_take)
+            // without a newest job to stop at, every job is taken
             .take_while(move |job| match job {
-                Ok(job) => {
-                    matches!(newest_job, Some(newest_job) if newest_job > job)
-                }
+                Ok(job) => newest_job.is_none_or(|newest_job| newest_job > job),
                 Err(_) => false,
             })
             .flatten(),
@@ -103,3 +224,127 @@ where
 
     Some(jobs_iter)
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta};
+
+    use super::*;
+
+    struct Copenhagen;
+    impl DateFormat for Copenhagen {
+        const DATE_FORMATS: &'static [&'static str] =
+            &["%Y-%m-%d", "%Y-%m-%d %H:%M:%S", "%d.%m.%Y"];
+        const TIME_ZONE: Tz = chrono_tz::Europe::Copenhagen;
+        const RELATIVE_DATES: bool = true;
+    }
+
+    struct Strict;
+    impl DateFormat for Strict {
+        const DATE_FORMATS: &'static [&'static str] = &["%Y-%m-%d %H:%M"];
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn now() -> DateTime<Utc> {
+        utc(2025, 10, 11, 12, 0)
+    }
+
+    fn parse(date: &str) -> Result<DateTime<Utc>, DateError> {
+        parse_date_at::<Copenhagen>(date, now())
+    }
+
+    #[test]
+    fn date_only_is_local_midnight() {
+        // CEST, two hours ahead of UTC
+        assert_eq!(parse("2025-08-28"), Ok(utc(2025, 8, 27, 22, 0)));
+        // CET, one hour ahead
+        assert_eq!(parse("2025-12-01"), Ok(utc(2025, 11, 30, 23, 0)));
+    }
+
+    #[test]
+    fn formats_are_tried_in_order() {
+        assert_eq!(parse("2025-08-28 14:30:00"), Ok(utc(2025, 8, 28, 12, 30)));
+        assert_eq!(parse("28.08.2025"), Ok(utc(2025, 8, 27, 22, 0)));
+        assert_eq!(
+            parse_date_at::<Strict>("2025-08-28 14:30", now()),
+            Ok(utc(2025, 8, 28, 14, 30))
+        );
+    }
+
+    #[test]
+    fn explicit_offsets_win() {
+        assert_eq!(
+            parse("2025-08-28T14:30:00+00:00"),
+            Ok(utc(2025, 8, 28, 14, 30))
+        );
+        assert_eq!(parse("2025-08-28T14:30:00Z"), Ok(utc(2025, 8, 28, 14, 30)));
+        assert_eq!(
+            parse_date_at::<Strict>("2025-08-28T14:30:00+02:00", now()),
+            Ok(utc(2025, 8, 28, 12, 30))
+        );
+    }
+
+    #[test]
+    fn daylight_saving_transitions() {
+        // the clocks go back at 03:00 on 2025-10-26, so 02:30 happens twice
+        assert_eq!(parse("2025-10-26 02:30:00"), Ok(utc(2025, 10, 26, 0, 30)));
+        // and forward at 02:00 on 2025-03-30, so 02:30 never happens
+        assert!(matches!(
+            parse("2025-03-30 02:30:00"),
+            Err(DateError::NonExistent(_))
+        ));
+    }
+
+    #[test]
+    fn relative_dates() {
+        assert_eq!(parse("2 days ago"), Ok(now() - TimeDelta::days(2)));
+        assert_eq!(parse("an hour ago"), Ok(now() - TimeDelta::hours(1)));
+        assert_eq!(parse("3 weeks ago"), Ok(now() - TimeDelta::weeks(3)));
+        assert_eq!(parse("1 month ago"), Ok(utc(2025, 9, 11, 12, 0)));
+        assert_eq!(parse("Yesterday"), Ok(now() - TimeDelta::days(1)));
+        assert_eq!(parse("for 5 dage siden"), Ok(now() - TimeDelta::days(5)));
+        assert_eq!(parse("en time siden"), Ok(now() - TimeDelta::hours(1)));
+        assert_eq!(parse("i går"), Ok(now() - TimeDelta::days(1)));
+        assert_eq!(parse("i dag"), Ok(now()));
+    }
+
+    #[test]
+    fn relative_dates_need_opting_in() {
+        assert_eq!(
+            parse_date_at::<Strict>("2 days ago", now()),
+            Err(DateError::Format("2 days ago".to_owned()))
+        );
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for date in ["", "soon", "2025-13-01", "2025-02-30", "2 fortnights ago"]
+        {
+            assert!(
+                matches!(parse(date), Err(DateError::Format(_))),
+                "{date:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn job_index_dates() {
+        use crate::job_fetchers::job_index::fetcher::JobIndex;
+
+        assert_eq!(
+            parse_date::<JobIndex>("2025-10-11"),
+            Ok(utc(2025, 10, 10, 22, 0))
+        );
+        assert_eq!(
+            parse_date::<JobIndex>("2025-10-11 08:15:00"),
+            Ok(utc(2025, 10, 11, 6, 15))
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::services::database_service::types::{
+    Currency, Salary, SalaryPeriod,
+};
+
+// An amount is either grouped in thousands ("45.000", "600,000", "1 200 000")
+// or a plain number with an optional decimal part ("45", "187,50", "1,2"),
+// optionally scaled ("600k", "1,2 mio.").
+const AMOUNT: &str =
+    r"\d{1,3}(?:[., \u{a0}]\d{3})+(?:[.,]\d{1,2})?|\d+(?:[.,]\d{1,2})?";
+const SCALE: &str = r"\s?(?:k\b|mio\b\.?|millioner\b|million\b)";
+const CURRENCY: &str = r"\b(?:kr\.?|dkk|eur|euro|sek|nok|usd)|€|\$";
+
+static SALARY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    let range = |suffix: &str| {
+        format!(
+            r"(?P<min{suffix}>{AMOUNT})(?P<min_scale{suffix}>{SCALE})?(?:\s?(?:-|–|til|to)\s?(?:€|\$)?(?P<max{suffix}>{AMOUNT})(?P<max_scale{suffix}>{SCALE})?)?"
+        )
+    };
+    Regex::new(&format!(
+        r"(?P<pre>{CURRENCY})\s?{}|{}\s?(?P<post>{CURRENCY})",
+        range(""),
+        range("_post"),
+    ))
+    .expect("salary regex should compile")
+});
+
+const NEGOTIABLE: &[&str] = &[
+    "løn efter kvalifikationer",
+    "løn efter aftale",
+    "lønnen forhandles",
+    "løn forhandles",
+    "competitive salary",
+    "salary negotiable",
+    "negotiable salary",
+    "salary according to qualifications",
+];
+
+const HOUR_WORDS: &[&str] = &[
+    "timeløn", "i timen", "pr. time", "per time", "per hour", "/hour",
+    "an hour", "hourly",
+];
+const MONTH_WORDS: &[&str] = &[
+    "månedsløn",
+    "om måneden",
+    "pr. måned",
+    "pr. md",
+    "per måned",
+    "/md",
+    "/måned",
+    "månedligt",
+    "per month",
+    "a month",
+    "/month",
+    "monthly",
+];
+const YEAR_WORDS: &[&str] = &[
+    "årsløn", "om året", "pr. år", "per år", "årligt", "/år", "per year",
+    "a year", "/year", "annual", "annually", "yearly", "p.a.",
+];
+/// How far around an amount to look for the period it is paid per.
+const PERIOD_CONTEXT: usize = 40;
+
+/// Extracts the first salary mentioned in a job description.
+///
+/// Amounts are only picked up next to a currency, so "2.000 medarbejdere" or
+/// "siden 1998" are ignored. When no period is written next to the amount it
+/// is guessed from its size.
+pub fn parse_salary(text: &str) -> Option<Salary> {
+    let text = text.to_lowercase();
+    let negotiable = NEGOTIABLE.iter().any(|phrase| text.contains(phrase));
+
+    let Some(caps) = SALARY_RE.captures(&text) else {
+        return negotiable.then_some(Salary {
+            min: None,
+            max: None,
+            currency: None,
+            period: None,
+            negotiable,
+        });
+    };
+
+    let group = |name: &str| {
+        caps.name(name)
+            .or_else(|| caps.name(&format!("{name}_post")))
+            .map(|mat| mat.as_str())
+    };
+    let mut min = parse_amount(group("min")?, group("min_scale"))?;
+    let max =
+        group("max").and_then(|max| parse_amount(max, group("max_scale")));
+    // "45-50.000 kr." is short for 45.000 to 50.000
+    if let Some(max) = max
+        && min < 1_000.0
+        && max >= 1_000.0
+        && min * 1_000.0 <= max
+    {
+        min *= 1_000.0;
+    }
+
+    let currency = parse_currency(group("pre").or(group("post"))?);
+    let period = period_near(&text, &caps).unwrap_or_else(|| guess_period(min));
+
+    Some(Salary {
+        min: Some(min.round() as i32),
+        max: max.map(|max| max.round() as i32),
+        currency: Some(currency),
+        period: Some(period),
+        negotiable,
+    })
+}
+
+fn parse_amount(amount: &str, scale: Option<&str>) -> Option<f64> {
+    let amount: String = amount
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}'))
+        .collect();
+
+    // A separator followed by exactly three digits groups thousands,
+    // anything else is the decimal separator.
+    let number = match amount.rfind(['.', ',']) {
+        Some(idx) if amount.len() - idx - 1 == 3 => {
+            amount.replace(['.', ','], "")
+        }
+        Some(idx) => format!(
+            "{}.{}",
+            amount[..idx].replace(['.', ','], ""),
+            &amount[idx + 1..]
+        ),
+        None => amount,
+    };
+    let number: f64 = number.parse().ok()?;
+
+    let factor = match scale.map(str::trim) {
+        Some("k") => 1_000.0,
+        Some(_) => 1_000_000.0,
+        None => 1.0,
+    };
+    Some(number * factor)
+}
+
+fn parse_currency(currency: &str) -> Currency {
+    match currency {
+        "eur" | "euro" | "€" => Currency::Eur,
+        "sek" => Currency::Sek,
+        "nok" => Currency::Nok,
+        "usd" | "$" => Currency::Usd,
+        _ => Currency::Dkk,
+    }
+}
+
+/// The period whose wording is closest to the amount.
+fn period_near(text: &str, caps: &Captures) -> Option<SalaryPeriod> {
+    let amount = caps.get(0)?;
+    let mut start = amount.start().saturating_sub(PERIOD_CONTEXT);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (amount.end() + PERIOD_CONTEXT).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    let context = &text[start..end];
+    let amount_start = amount.start() - start;
+
+    [
+        (SalaryPeriod::Hour, HOUR_WORDS),
+        (SalaryPeriod::Month, MONTH_WORDS),
+        (SalaryPeriod::Year, YEAR_WORDS),
+    ]
+    .into_iter()
+    .filter_map(|(period, words)| {
+        words
+            .iter()
+            .filter_map(|word| context.find(word))
+            .map(|idx| idx.abs_diff(amount_start))
+            .min()
+            .map(|distance| (distance, period))
+    })
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, period)| period)
+}
+
+/// Danish pay is mostly quoted per month, so only amounts that are
+/// implausible as a monthly salary are taken to be hourly or yearly.
+fn guess_period(amount: f64) -> SalaryPeriod {
+    match amount {
+        amount if amount < 1_000.0 => SalaryPeriod::Hour,
+        amount if amount < 200_000.0 => SalaryPeriod::Month,
+        _ => SalaryPeriod::Year,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Salary {
+        parse_salary(text).expect("should find a salary")
+    }
+
+    #[test]
+    fn danish_monthly() {
+        let salary = parse("Vi tilbyder kr. 45.000 pr. måned plus pension.");
+        assert_eq!(salary.min, Some(45_000));
+        assert_eq!(salary.max, None);
+        assert_eq!(salary.currency, Some(Currency::Dkk));
+        assert_eq!(salary.period, Some(SalaryPeriod::Month));
+        assert!(!salary.negotiable);
+    }
+
+    #[test]
+    fn scaled_amounts() {
+        let salary = parse("Package around DKK 600k depending on profile");
+        assert_eq!(salary.min, Some(600_000));
+        assert_eq!(salary.period, Some(SalaryPeriod::Year));
+
+        let salary = parse("Årsløn 1,2 mio. kr.");
+        assert_eq!(salary.min, Some(1_200_000));
+        assert_eq!(salary.period, Some(SalaryPeriod::Year));
+    }
+
+    #[test]
+    fn ranges() {
+        let salary = parse("Løn: 42.000 - 48.000 kr. om måneden");
+        assert_eq!((salary.min, salary.max), (Some(42_000), Some(48_000)));
+        assert_eq!(salary.period, Some(SalaryPeriod::Month));
+
+        let salary = parse("Lønnen ligger på 45-50.000 kr.");
+        assert_eq!((salary.min, salary.max), (Some(45_000), Some(50_000)));
+
+        let salary = parse("We pay €60,000 to €70,000 per year");
+        assert_eq!((salary.min, salary.max), (Some(60_000), Some(70_000)));
+        assert_eq!(salary.currency, Some(Currency::Eur));
+        assert_eq!(salary.period, Some(SalaryPeriod::Year));
+    }
+
+    #[test]
+    fn hourly() {
+        let salary = parse("Timeløn 187,50 kr. plus tillæg");
+        assert_eq!(salary.min, Some(188));
+        assert_eq!(salary.period, Some(SalaryPeriod::Hour));
+    }
+
+    #[test]
+    fn negotiable_without_amount() {
+        let salary = parse("Løn efter kvalifikationer.");
+        assert!(salary.negotiable);
+        assert_eq!(salary.min, None);
+        assert_eq!(salary.currency, None);
+    }
+
+    #[test]
+    fn numbers_without_currency_are_ignored() {
+        assert!(
+            parse_salary(
+                "Vi er 2.000 medarbejdere og har eksisteret siden 1998"
+            )
+            .is_none()
+        );
+        assert!(parse_salary("Tiltrædelse efter aftale").is_none());
+    }
+
+    #[test]
+    fn monthly_dkk() {
+        let salary = parse("Timeløn 200 kr.");
+        assert_eq!(salary.monthly_dkk(), Some((32_067, 32_067)));
+
+        let salary = parse("EUR 84,000 per year");
+        assert_eq!(salary.monthly_dkk(), Some((52_220, 52_220)));
+
+        let salary = parse("NOK 50.000 pr. måned");
+        assert_eq!(salary.monthly_dkk(), None);
+    }
+}
@@ -0,0 +1,421 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+
+use crate::job_fetchers::JOB_TAGS;
+use crate::services::database_service::types::{JobTag, TagHit, TagSource};
+
+pub static TAG_MATCHER: LazyLock<TagMatcher> =
+    LazyLock::new(|| TagMatcher::new(JOB_TAGS));
+
+/// How many hits are kept as evidence per tag, the rest are only counted.
+const MAX_HITS: usize = 5;
+/// Bytes of context kept on each side of a hit in its snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A title match says more about the job than any number of mentions in
+/// the body, which only add logarithmically.
+const TITLE_WEIGHT: f64 = 2.0;
+
+pub fn tag_weight(title_matches: u32, body_matches: u32) -> f64 {
+    let title = if title_matches > 0 { TITLE_WEIGHT } else { 0.0 };
+    title + f64::from(body_matches).ln_1p()
+}
+
+/// A single occurrence of a tag needle, with byte offsets into the
+/// original (unfolded) text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagMatch {
+    pub tag: &'static str,
+    pub needle: &'static str,
+    pub range: Range<usize>,
+}
+
+struct Needle {
+    tag: usize,
+    needle: &'static str,
+    // whether the needle starts/ends in a word character, in which case the
+    // text must have a token boundary on that side.
+    bounded_start: bool,
+    bounded_end: bool,
+}
+
+/// Matches every needle of a tag table in a single pass over the text.
+///
+/// Both the needles and the text are case folded and have their whitespace
+/// collapsed, so "Machine\n Learning" matches "machine learning". A needle
+/// only matches on token boundaries, but only on the sides where it ends in a
+/// word character, so "c#" matches in "Blazor C#." and ".net" matches in
+/// "med .Net", while "go" does not match in "good".
+pub struct TagMatcher {
+    automaton: AhoCorasick,
+    needles: Vec<Needle>,
+    tags: &'static [(&'static str, &'static [&'static str])],
+}
+
+impl TagMatcher {
+    pub fn new(
+        tags: &'static [(&'static str, &'static [&'static str])],
+    ) -> Self {
+        let needles: Vec<Needle> = tags
+            .iter()
+            .enumerate()
+            .flat_map(|(tag, (_, needles))| {
+                needles.iter().map(move |needle| Needle {
+                    tag,
+                    needle,
+                    bounded_start: needle
+                        .chars()
+                        .next()
+                        .is_some_and(is_word_char),
+                    bounded_end: needle
+                        .chars()
+                        .next_back()
+                        .is_some_and(is_word_char),
+                })
+            })
+            .collect();
+
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::Standard)
+            .build(
+                needles
+                    .iter()
+                    .map(|needle| FoldedText::new(needle.needle).text),
+            )
+            .expect("tag needles should build an automaton");
+
+        TagMatcher {
+            automaton,
+            needles,
+            tags,
+        }
+    }
+
+    /// The tag called `name`, like it is stored in `job_tag`.
+    pub fn tag_name(&self, name: &str) -> Option<&'static str> {
+        self.tags
+            .iter()
+            .map(|&(tag, _)| tag)
+            .find(|tag| *tag == name)
+    }
+
+    /// Every non-overlapping needle occurrence in `text`, leftmost first.
+    /// Where two valid occurrences overlap the longer one wins, so "c++" is
+    /// never also reported as "c".
+    pub fn find_matches(&self, text: &str) -> Vec<TagMatch> {
+        let folded = FoldedText::new(text);
+
+        let mut candidates: Vec<(Range<usize>, &Needle)> = self
+            .automaton
+            .find_overlapping_iter(folded.text.as_str())
+            .map(|mat| (mat.range(), &self.needles[mat.pattern().as_usize()]))
+            .filter(|(range, needle)| folded.is_bounded(range.clone(), needle))
+            .collect();
+
+        candidates.sort_by(|(a, _), (b, _)| {
+            a.start.cmp(&b.start).then(b.end.cmp(&a.end))
+        });
+
+        // a needle listed under several tags, like "fullstack", is kept once
+        // for each of them
+        let mut last_kept = 0..0;
+        candidates
+            .into_iter()
+            .filter(|(range, _)| {
+                let keep = range.start >= last_kept.end || *range == last_kept;
+                if keep {
+                    last_kept = range.clone();
+                }
+                keep
+            })
+            .map(|(range, needle)| TagMatch {
+                tag: self.tags[needle.tag].0,
+                needle: needle.needle,
+                range: folded.original_range(range),
+            })
+            .collect()
+    }
+
+    /// The distinct tags found in a job's title and body, in the order of the
+    /// tag table, each with the evidence that triggered it.
+    pub fn job_tags(&self, title: &str, body: &str) -> Vec<JobTag> {
+        let title_matches = self.find_matches(title);
+        let body_matches = self.find_matches(body);
+
+        self.tags
+            .iter()
+            .filter_map(|(tag, _)| {
+                let title_hits =
+                    title_matches.iter().filter(|mat| mat.tag == *tag);
+                let body_hits =
+                    body_matches.iter().filter(|mat| mat.tag == *tag);
+                let title_count = title_hits.clone().count() as u32;
+                let body_count = body_hits.clone().count() as u32;
+                if title_count + body_count == 0 {
+                    return None;
+                }
+
+                let hits = title_hits
+                    .map(|mat| TagHit::new(TagSource::Title, title, mat))
+                    .chain(
+                        body_hits
+                            .map(|mat| TagHit::new(TagSource::Body, body, mat)),
+                    )
+                    .take(MAX_HITS)
+                    .collect();
+
+                Some(JobTag {
+                    name: tag,
+                    match_count: title_count + body_count,
+                    in_title: title_count > 0,
+                    weight: tag_weight(title_count, body_count),
+                    hits,
+                })
+            })
+            .collect()
+    }
+}
+
+impl TagHit {
+    fn new(source: TagSource, text: &str, mat: &TagMatch) -> Self {
+        let mut start = mat.range.start.saturating_sub(SNIPPET_CONTEXT);
+        while !text.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = (mat.range.end + SNIPPET_CONTEXT).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+
+        TagHit {
+            source,
+            start: mat.range.start,
+            end: mat.range.end,
+            snippet: text[start..end]
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Lowercased text with whitespace runs and html tags collapsed to a single
+/// space, keeping the original byte span of every folded byte. Ads keep their
+/// markup, and the click tracking links in it ("/c?t=h1586938") would
+/// otherwise match needles like "c".
+struct FoldedText {
+    text: String,
+    spans: Vec<(usize, usize)>,
+}
+
+impl FoldedText {
+    fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut spans = Vec::with_capacity(original.len());
+        let mut markup_end = 0;
+
+        for (idx, c) in original.char_indices() {
+            if idx < markup_end {
+                continue;
+            }
+            let span = (idx, idx + c.len_utf8());
+            if let Some(len) = markup_len(&original[idx..]) {
+                markup_end = idx + len;
+            }
+            if c.is_whitespace() || idx < markup_end {
+                if !text.is_empty() && !text.ends_with(' ') {
+                    text.push(' ');
+                    spans.push(span);
+                }
+                continue;
+            }
+            for lower in c.to_lowercase() {
+                text.push(lower);
+                spans.extend(std::iter::repeat_n(span, lower.len_utf8()));
+            }
+        }
+
+        FoldedText { text, spans }
+    }
+
+    fn is_bounded(&self, range: Range<usize>, needle: &Needle) -> bool {
+        let before = self.text[..range.start].chars().next_back();
+        let after = self.text[range.end..].chars().next();
+
+        (!needle.bounded_start || !before.is_some_and(is_word_char))
+            && (!needle.bounded_end || !after.is_some_and(is_word_char))
+    }
+
+    fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        self.spans[range.start].0..self.spans[range.end - 1].1
+    }
+}
+
+/// The byte length of the html tag at the start of `text`, if it starts with
+/// one. A `<` that does not open a tag, like in "a < b", is left as text.
+fn markup_len(text: &str) -> Option<usize> {
+    let mut chars = text.chars();
+    if chars.next() != Some('<') {
+        return None;
+    }
+    if !chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!')
+    {
+        return None;
+    }
+    text.find('>').map(|end| end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde::Deserialize;
+
+    use super::{TAG_MATCHER, tag_weight};
+    use crate::services::database_service::types::TagSource;
+
+    fn tags(text: &str) -> Vec<&'static str> {
+        TAG_MATCHER
+            .job_tags("", text)
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect()
+    }
+
+    #[test]
+    fn short_needles_need_word_boundaries() {
+        assert_eq!(
+            tags("We are a good team sending email"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(tags("Experience with Go and C is a plus"), ["Go", "C"]);
+        assert_eq!(tags("AI-powered products"), ["AI"]);
+    }
+
+    #[test]
+    fn case_is_folded() {
+        assert_eq!(tags("we write GOLANG"), ["Go"]);
+        assert_eq!(tags("JavaSpring"), ["Spring"]);
+        assert_eq!(tags("PYTHON og RUST"), ["Python", "Rust"]);
+    }
+
+    #[test]
+    fn punctuation_needles() {
+        assert_eq!(tags("Blazor C#."), ["C#", ".Net"]);
+        assert_eq!(tags("modern C++ (17/20)"), ["C++"]);
+        assert_eq!(tags("Software Arkitekt med .Net ekspertise"), [".Net"]);
+        assert_eq!(tags("C/C++ on embedded targets"), ["C", "C++"]);
+        assert_eq!(tags("ASP.NET Core"), [".Net"]);
+    }
+
+    #[test]
+    fn longer_needles_win_overlaps() {
+        assert_eq!(tags("JavaScript"), ["Javscript/Typescript"]);
+        assert_eq!(
+            tags("Java and JavaScript"),
+            ["Javscript/Typescript", "Java"]
+        );
+    }
+
+    #[test]
+    fn markup_is_not_text() {
+        assert_eq!(
+            tags("<a data-click=\"/c?t=h1586938&amp;jsp=12\">Se video</a>"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(tags("Rust<ul><li>Python</li></ul>"), ["Python", "Rust"]);
+        assert_eq!(tags("C < C++"), ["C", "C++"]);
+    }
+
+    #[test]
+    fn shared_needles_give_every_tag() {
+        assert_eq!(
+            tags("Full-stack Software Engineer"),
+            ["Frontend", "Backend"]
+        );
+    }
+
+    #[test]
+    fn phrases_span_whitespace() {
+        assert_eq!(tags("machine\n   learning"), ["AI"]);
+        assert_eq!(tags("Microsoft  SQL Server"), ["Sql"]);
+    }
+
+    #[test]
+    fn match_ranges_point_into_original_text() {
+        let text = "Vi søger en udvikler med erfaring i Machine  Learning";
+        let matches = TAG_MATCHER.find_matches(text);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(&text[matches[0].range.clone()], "Machine  Learning");
+    }
+
+    #[test]
+    fn tags_carry_evidence() {
+        let body = "We build backend services in Rust. Some Python, \
+                    but mostly rust and more Rust.";
+        let tags = TAG_MATCHER.job_tags("Senior Rust Developer", body);
+
+        let rust = tags.iter().find(|tag| tag.name == "Rust").unwrap();
+        assert_eq!(rust.match_count, 4);
+        assert!(rust.in_title);
+        assert_eq!(rust.hits[0].source, TagSource::Title);
+        assert_eq!(rust.hits[1].source, TagSource::Body);
+        assert_eq!(&body[rust.hits[1].start..rust.hits[1].end], "Rust");
+        assert!(rust.hits[1].snippet.contains("backend services in Rust"));
+
+        let python = tags.iter().find(|tag| tag.name == "Python").unwrap();
+        assert_eq!(python.match_count, 1);
+        assert!(!python.in_title);
+        assert!(rust.weight > python.weight);
+    }
+
+    #[test]
+    fn title_outweighs_body_mentions() {
+        assert!(tag_weight(1, 0) > tag_weight(0, 5));
+        assert!(tag_weight(0, 2) > tag_weight(0, 1));
+        assert_eq!(tag_weight(0, 0), 0.0);
+    }
+
+    #[derive(Deserialize)]
+    struct CorpusEntry {
+        tid: String,
+        text: String,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn precision_and_recall_on_sample_ads() {
+        let corpus = std::fs::read_to_string("jobindex_files/tag_corpus.json")
+            .expect("corpus should exist");
+        let corpus: Vec<CorpusEntry> =
+            serde_json::from_str(&corpus).expect("corpus should parse");
+
+        let (mut true_pos, mut false_pos, mut false_neg) = (0, 0, 0);
+        let mut mismatched = Vec::new();
+        for entry in &corpus {
+            let found: BTreeSet<&str> = tags(&entry.text).into_iter().collect();
+            let expected: BTreeSet<&str> =
+                entry.tags.iter().map(String::as_str).collect();
+
+            true_pos += found.intersection(&expected).count();
+            false_pos += found.difference(&expected).count();
+            false_neg += expected.difference(&found).count();
+            if found != expected {
+                mismatched.push(&entry.tid);
+            }
+        }
+
+        let precision = true_pos as f64 / (true_pos + false_pos) as f64;
+        let recall = true_pos as f64 / (true_pos + false_neg) as f64;
+        assert!(precision >= 0.9, "precision {precision}, {mismatched:?}");
+        assert!(recall >= 0.9, "recall {recall}, {mismatched:?}");
+    }
+}
@@ -0,0 +1,5 @@
+pub mod api;
+pub mod job_fetchers;
+
+pub mod services;
+pub mod util;
@@ -1,11 +1,8 @@
-pub mod job_fetchers;
-
-pub mod services;
-pub mod util;
-
-use std::sync::Arc;
-
 use axum::Router;
+use axum::middleware;
+
+use jobhunter::services::database_service::database::DataBase;
+use jobhunter::{api, services};
 
 // async fn fetch_jobs<T>(
 //     fetch_options: FetchOptions,
@@ -17,15 +14,30 @@ use axum::Router;
 
 #[tokio::main]
 async fn main() {
-    let jobs = Arc::new("");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL should be set");
+    let database = DataBase::connect(&database_url)
+        .await
+        .expect("should connect to the database");
     // let x = fetchers!(X : JobIndex = JobIndex::new());
+    tokio::spawn(services::webhook_service::run_worker(database.clone()));
 
     let app = Router::new()
         // .route("/fetch_jobs", get(fetch_jobs))
-        .with_state(jobs);
-
-    // run our app with hyper, listening globally on port
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:").await.unwrap();
+        .merge(api::router())
+        // the user of the request, for every route that needs one
+        .layer(middleware::from_fn_with_state(
+            database.clone(),
+            api::auth::authenticate,
+        ))
+        .with_state(database);
+
+    // run our app with hyper, listening globally on port 3000 unless
+    // LISTEN_ADDR says otherwise
+    let address =
+        std::env::var("LISTEN_ADDR").unwrap_or_else(|_| "0.0.0.0:3000".into());
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .expect("should listen on LISTEN_ADDR");
     axum::serve(listener, app).await.unwrap();
 }
@@ -0,0 +1,132 @@
+use crate::services::database_service::types::{
+    ApplicationColumn, ApplicationStatus, JobApplication, JobApplications,
+};
+
+impl ApplicationStatus {
+    /// Whether an application can be moved on to `next`. The workflow only
+    /// goes forward, though steps can be skipped, and an application can be
+    /// rejected at any point. A rejected application is closed.
+    pub fn can_move_to(&self, next: ApplicationStatus) -> bool {
+        match (self, next) {
+            (ApplicationStatus::Rejected, _) => false,
+            (_, ApplicationStatus::Rejected) => true,
+            (current, next) => current.step() < next.step(),
+        }
+    }
+
+    /// Whether the application has been sent by now.
+    pub fn has_applied(&self) -> bool {
+        matches!(
+            self,
+            ApplicationStatus::Applied
+                | ApplicationStatus::Interview
+                | ApplicationStatus::Offer
+        )
+    }
+
+    fn step(&self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|status| status == self)
+            .expect("every status should be in ALL")
+    }
+}
+
+/// Lays `applications` out as a Kanban board, keeping their order within
+/// each column.
+pub fn board(applications: Vec<JobApplication>) -> JobApplications {
+    let mut columns: Vec<ApplicationColumn> = ApplicationStatus::ALL
+        .into_iter()
+        .map(|status| ApplicationColumn {
+            status,
+            applications: Vec::new(),
+        })
+        .collect();
+    for application in applications {
+        columns[application.status.step()]
+            .applications
+            .push(application);
+    }
+    JobApplications { columns }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+    use crate::services::database_service::types::ApplicationStatus::{
+        Applied, Interested, Interview, Offer, Rejected,
+    };
+    use crate::services::database_service::types::JobSummary;
+
+    fn application(id: i64, status: ApplicationStatus) -> JobApplication {
+        let now = DateTime::<Utc>::from_timestamp(1_760_000_000, 0).unwrap();
+        JobApplication {
+            id,
+            user_id: 1,
+            job: JobSummary::for_test(id * 10, "Rust Developer"),
+            status,
+            notes: String::new(),
+            created_at: now,
+            updated_at: now,
+            applied_at: status.has_applied().then_some(now),
+            history: Vec::new(),
+            documents: Vec::new(),
+            reminders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn the_workflow_goes_forward() {
+        assert!(Interested.can_move_to(Applied));
+        assert!(Applied.can_move_to(Interview));
+        assert!(Interview.can_move_to(Offer));
+        // applied somewhere else and went straight to an interview
+        assert!(Interested.can_move_to(Interview));
+
+        assert!(!Offer.can_move_to(Applied));
+        assert!(!Applied.can_move_to(Interested));
+        assert!(!Applied.can_move_to(Applied));
+    }
+
+    #[test]
+    fn rejection_closes_an_application() {
+        for status in [Interested, Applied, Interview, Offer] {
+            assert!(status.can_move_to(Rejected), "{status:?}");
+        }
+        for status in ApplicationStatus::ALL {
+            assert!(!Rejected.can_move_to(status), "{status:?}");
+        }
+        assert!(!Rejected.has_applied());
+        assert!(!Interested.has_applied());
+        assert!(Offer.has_applied());
+    }
+
+    #[test]
+    fn board_has_every_column() {
+        let board = board(vec![
+            application(3, Interview),
+            application(2, Interested),
+            application(1, Interview),
+        ]);
+        let columns: Vec<(ApplicationStatus, Vec<i64>)> = board
+            .columns
+            .iter()
+            .map(|column| {
+                let ids = column.applications.iter().map(|a| a.id).collect();
+                (column.status, ids)
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                (Interested, vec![2]),
+                (Applied, vec![]),
+                (Interview, vec![3, 1]),
+                (Offer, vec![]),
+                (Rejected, vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::LazyLock;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::services::database_service::types::{Session, User};
+
+/// What API tokens start with, which tells them apart from sessions.
+pub const API_TOKEN_PREFIX: &str = "jh_";
+const API_TOKEN_BYTES: usize = 32;
+pub const MIN_PASSWORD_LEN: usize = 8;
+const SESSION_HOURS: i64 = 12;
+/// The cookie the web pages keep the session token in.
+pub const SESSION_COOKIE: &str = "session";
+
+pub static SESSION_KEYS: LazyLock<SessionKeys> =
+    LazyLock::new(SessionKeys::from_env);
+
+/// Checked against when there is no such user, so a login takes as long
+/// whether the user exists or not.
+static UNKNOWN_USER_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("not the password of anyone"));
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The argon2id hash of `password` with a fresh salt, in the PHC string
+/// format. It is slow on purpose, so it belongs on a blocking thread.
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("the default parameters should hash any password")
+        .to_string()
+}
+
+/// Whether `password` is the one `hash` was made from. A user that does not
+/// exist is checked against a hash no password matches.
+pub fn verify_password(password: &str, hash: Option<&str>) -> bool {
+    let hash = hash.unwrap_or(UNKNOWN_USER_HASH.as_str());
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
+}
+
+/// A new API token and the hash it is stored as.
+pub fn new_api_token() -> (String, String) {
+    let mut bytes = [0; API_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    let token = format!("{API_TOKEN_PREFIX}{}", hex(&bytes));
+    let hash = api_token_hash(&token);
+    (token, hash)
+}
+
+/// The hex SHA-256 of `token`. The tokens are random enough that a fast
+/// hash keeps them as safe as a password hash would.
+pub fn api_token_hash(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// The id of the user.
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Signs and checks the JWTs of sessions with HS256.
+pub struct SessionKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl SessionKeys {
+    pub fn new(secret: &[u8]) -> Self {
+        SessionKeys {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// Keyed with `JWT_SECRET`, or with a random key when it is not set, in
+    /// which case the sessions end when the server does.
+    pub fn from_env() -> Self {
+        match std::env::var("JWT_SECRET") {
+            Ok(secret) if !secret.is_empty() => Self::new(secret.as_bytes()),
+            _ => {
+                let mut secret = [0; 32];
+                OsRng.fill_bytes(&mut secret);
+                Self::new(&secret)
+            }
+        }
+    }
+
+    /// A session of `user` from `now`.
+    pub fn issue(&self, user: &User, now: DateTime<Utc>) -> Session {
+        let expires_at = now + TimeDelta::hours(SESSION_HOURS);
+        let claims = Claims {
+            sub: user.id.to_string(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token =
+            encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+                .expect("claims should always serialise");
+        Session { token, expires_at }
+    }
+
+    /// The id of the user of `token`, if it is signed with these keys and
+    /// has not expired.
+    pub fn user_id(&self, token: &str) -> Option<i64> {
+        let validation = Validation::new(Algorithm::HS256);
+        let claims = decode::<Claims>(token, &self.decoding, &validation)
+            .ok()?
+            .claims;
+        claims.sub.parse().ok()
+    }
+}
+
+/// The `Set-Cookie` value that keeps `session` in the browser until it
+/// expires. Scripts can not read it, and other sites do not get to send it
+/// along with their forms.
+pub fn session_cookie(session: &Session, now: DateTime<Utc>) -> String {
+    let max_age = (session.expires_at - now).num_seconds().max(0);
+    format!(
+        "{SESSION_COOKIE}={}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax",
+        session.token
+    )
+}
+
+/// The `Set-Cookie` value that ends the session of the browser.
+pub fn expired_session_cookie() -> String {
+    format!("{SESSION_COOKIE}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax")
+}
+
+/// The session token in the `Cookie` header `cookies`, if there is one.
+pub fn session_from_cookies(cookies: &str) -> Option<&str> {
+    cookies
+        .split(';')
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token)
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> User {
+        User {
+            id: 7,
+            username: "anna".to_owned(),
+            admin: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn verifies_only_the_password() {
+        let hash = hash_password("correct horse");
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("correct horse", Some(&hash)));
+        assert!(!verify_password("correct horsE", Some(&hash)));
+        assert!(!verify_password("correct horse", Some("not a hash")));
+        assert!(!verify_password("correct horse", None));
+        // salted, so the same password hashes differently
+        assert_ne!(hash, hash_password("correct horse"));
+    }
+
+    #[test]
+    fn api_tokens_are_stored_hashed() {
+        let (token, hash) = new_api_token();
+        assert!(token.starts_with(API_TOKEN_PREFIX));
+        assert_eq!(token.len(), API_TOKEN_PREFIX.len() + 2 * API_TOKEN_BYTES);
+        assert_eq!(hash, api_token_hash(&token));
+        assert_eq!(hash.len(), 64);
+        assert_ne!(new_api_token().0, token);
+    }
+
+    #[test]
+    fn sessions_are_signed_and_expire() {
+        let keys = SessionKeys::new(b"a secret of some length");
+        let session = keys.issue(&user(), Utc::now());
+        assert_eq!(keys.user_id(&session.token), Some(7));
+
+        let other_keys = SessionKeys::new(b"another secret");
+        assert_eq!(other_keys.user_id(&session.token), None);
+
+        let yesterday = Utc::now() - TimeDelta::days(1);
+        let expired = keys.issue(&user(), yesterday);
+        assert_eq!(keys.user_id(&expired.token), None);
+        assert_eq!(keys.user_id("not.a.token"), None);
+    }
+
+    #[test]
+    fn sessions_are_kept_in_a_cookie() {
+        let keys = SessionKeys::new(b"a secret of some length");
+        let now = Utc::now();
+        let session = keys.issue(&user(), now);
+        let cookie = session_cookie(&session, now);
+        assert!(cookie.starts_with(&format!("session={};", session.token)));
+        assert!(cookie.contains("Max-Age=43200"));
+        assert!(cookie.contains("HttpOnly"));
+
+        let header = format!("theme=dark; session={}; lang=da", session.token);
+        assert_eq!(session_from_cookies(&header), Some(&*session.token));
+        assert_eq!(session_from_cookies("theme=dark"), None);
+        assert_eq!(session_from_cookies("session="), None);
+        assert!(expired_session_cookie().contains("Max-Age=0"));
+    }
+}
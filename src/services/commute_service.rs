@@ -0,0 +1,494 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::LazyLock;
+
+use futures::future::join_all;
+use serde::Deserialize;
+use url::Url;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    Commute, CommuteMode, HomeLocation, JobPage,
+};
+use crate::util::geo::{GeoPoint, MAX_RADIUS_KM, town_location};
+use crate::util::options::{JobFilter, JobSort};
+
+/// How much longer a route is than the straight line, on average.
+const DETOUR_FACTOR: f64 = 1.3;
+
+/// The most jobs a commute is estimated to for one list, so a list without
+/// a `max_commute` does not route to every job in the country.
+pub const MAX_COMMUTE_CANDIDATES: usize = 1000;
+
+/// The routing backend at `ROUTING_URL`, if one is set.
+pub static ROUTING: LazyLock<Option<Osrm>> = LazyLock::new(Osrm::from_env);
+
+impl CommuteMode {
+    /// The average speed from door to door in km/h, over the route.
+    fn average_speed(&self) -> f64 {
+        match self {
+            CommuteMode::Car => 50.0,
+            CommuteMode::Transit => 30.0,
+            CommuteMode::Bike => 15.0,
+            CommuteMode::Walk => 5.0,
+        }
+    }
+
+    /// The fastest any part of a route goes in km/h, like a motorway or an
+    /// intercity train.
+    fn top_speed(&self) -> f64 {
+        match self {
+            CommuteMode::Car => 130.0,
+            CommuteMode::Transit => 180.0,
+            CommuteMode::Bike => 30.0,
+            CommuteMode::Walk => 7.0,
+        }
+    }
+
+    /// Minutes spent on top of moving, like parking or waiting for the bus.
+    fn overhead_minutes(&self) -> f64 {
+        match self {
+            CommuteMode::Car => 5.0,
+            CommuteMode::Transit => 10.0,
+            CommuteMode::Bike => 2.0,
+            CommuteMode::Walk => 0.0,
+        }
+    }
+
+    /// How far in a straight line a commute of `minutes` can possibly go,
+    /// by any routing backend.
+    pub fn reach_km(&self, minutes: f64) -> f64 {
+        (minutes - self.overhead_minutes()).max(0.0) / 60.0 * self.top_speed()
+    }
+}
+
+#[derive(Debug)]
+pub enum RoutingError {
+    Request(reqwest::Error),
+    /// The backend cannot route this way of commuting.
+    Unsupported(CommuteMode),
+    /// The backend answered, but with an error code.
+    Response(String),
+}
+
+impl fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingError::Request(error) => write!(f, "{error}"),
+            RoutingError::Unsupported(mode) => {
+                write!(f, "no routing by {}", mode.as_str())
+            }
+            RoutingError::Response(code) => {
+                write!(f, "routing failed with {code:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoutingError {}
+
+impl From<reqwest::Error> for RoutingError {
+    fn from(error: reqwest::Error) -> Self {
+        RoutingError::Request(error)
+    }
+}
+
+/// Something that knows how long it takes to get from one place to another.
+pub trait RoutingBackend {
+    /// The minutes from `from` to each of `to`, in order, with `None` where
+    /// there is no route.
+    fn commute_minutes(
+        &self,
+        from: GeoPoint,
+        to: &[GeoPoint],
+        mode: CommuteMode,
+    ) -> impl Future<Output = Result<Vec<Option<f64>>, RoutingError>> + Send;
+}
+
+/// Estimates commutes from the straight-line distance, which works offline
+/// and for every way of commuting.
+pub struct StraightLine;
+
+impl StraightLine {
+    pub fn minutes(from: GeoPoint, to: GeoPoint, mode: CommuteMode) -> f64 {
+        let route_km = from.distance_km(to) * DETOUR_FACTOR;
+        mode.overhead_minutes() + route_km / mode.average_speed() * 60.0
+    }
+}
+
+impl RoutingBackend for StraightLine {
+    async fn commute_minutes(
+        &self,
+        from: GeoPoint,
+        to: &[GeoPoint],
+        mode: CommuteMode,
+    ) -> Result<Vec<Option<f64>>, RoutingError> {
+        Ok(to
+            .iter()
+            .map(|&to| Some(Self::minutes(from, to, mode)))
+            .collect())
+    }
+}
+
+/// An OSRM server with the `car`, `bike` and `foot` profiles, e.g. at
+/// `http://localhost:5000/`. OSRM does not route public transport.
+pub struct Osrm {
+    client: reqwest::Client,
+    base_url: Url,
+}
+
+impl Osrm {
+    /// The most destinations in one table request. OSRM answers tables of
+    /// up to 100 coordinates unless it is started with a larger
+    /// `--max-table-size`, and the source is one of them.
+    const TABLE_DESTINATIONS: usize = 99;
+
+    pub fn new(base_url: Url) -> Self {
+        Osrm {
+            client: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let base_url = std::env::var("ROUTING_URL").ok()?;
+        Url::parse(&base_url).ok().map(Self::new)
+    }
+}
+
+impl RoutingBackend for Osrm {
+    /// Asks for a table of at most `TABLE_DESTINATIONS` at a time. A table
+    /// that fails leaves its destinations without a route, and only when
+    /// every table fails is the error returned.
+    async fn commute_minutes(
+        &self,
+        from: GeoPoint,
+        to: &[GeoPoint],
+        mode: CommuteMode,
+    ) -> Result<Vec<Option<f64>>, RoutingError> {
+        let profile = match mode {
+            CommuteMode::Car => "car",
+            CommuteMode::Bike => "bike",
+            CommuteMode::Walk => "foot",
+            CommuteMode::Transit => {
+                return Err(RoutingError::Unsupported(mode));
+            }
+        };
+        let chunks = to.chunks(Self::TABLE_DESTINATIONS);
+        let tables =
+            join_all(chunks.map(|to| self.table(profile, from, to))).await;
+        if tables.iter().all(Result::is_err) {
+            // no tables at all when there is nowhere to go
+            return match tables.into_iter().next() {
+                Some(Err(error)) => Err(error),
+                _ => Ok(Vec::new()),
+            };
+        }
+        Ok(tables
+            .into_iter()
+            .zip(to.chunks(Self::TABLE_DESTINATIONS))
+            .flat_map(|(table, to)| {
+                table.unwrap_or_else(|_| vec![None; to.len()])
+            })
+            .collect())
+    }
+}
+
+impl Osrm {
+    /// The minutes from `from` to each of `to` by `profile`, in one table
+    /// request.
+    async fn table(
+        &self,
+        profile: &str,
+        from: GeoPoint,
+        to: &[GeoPoint],
+    ) -> Result<Vec<Option<f64>>, RoutingError> {
+        #[derive(Deserialize)]
+        struct Table {
+            code: String,
+            /// Seconds from each source to each destination.
+            #[serde(default)]
+            durations: Vec<Vec<Option<f64>>>,
+        }
+
+        let coordinates = std::iter::once(&from)
+            .chain(to)
+            .map(|point| format!("{},{}", point.lon, point.lat))
+            .collect::<Vec<_>>()
+            .join(";");
+        let mut url = self
+            .base_url
+            .join(&format!("table/v1/{profile}/{coordinates}"))
+            .map_err(|error| RoutingError::Response(error.to_string()))?;
+        url.query_pairs_mut()
+            .append_pair("sources", "0")
+            .append_pair("annotations", "duration");
+
+        let body = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let table: Table = serde_json::from_str(&body)
+            .map_err(|error| RoutingError::Response(error.to_string()))?;
+        if table.code != "Ok" {
+            return Err(RoutingError::Response(table.code));
+        }
+        // the first destination is the source itself
+        let durations = table.durations.into_iter().next().unwrap_or_default();
+        Ok(durations
+            .into_iter()
+            .skip(1)
+            .map(|seconds| seconds.map(|seconds| seconds / 60.0))
+            .chain(std::iter::repeat(None))
+            .take(to.len())
+            .collect())
+    }
+}
+
+/// The commute to each of `to`, from `backend` where it has a route and
+/// estimated from the straight line where it has not, or is not there.
+pub async fn estimate_commutes<R: RoutingBackend>(
+    backend: Option<&R>,
+    from: GeoPoint,
+    to: &[GeoPoint],
+    mode: CommuteMode,
+) -> Vec<Commute> {
+    let routed = match backend {
+        // a backend that fails is as good as none, the estimate still works
+        Some(backend) => backend
+            .commute_minutes(from, to, mode)
+            .await
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    to.iter()
+        .enumerate()
+        .map(|(index, &to)| match routed.get(index).copied().flatten() {
+            Some(minutes) => Commute {
+                minutes,
+                routed: true,
+            },
+            None => Commute {
+                minutes: StraightLine::minutes(from, to, mode),
+                routed: false,
+            },
+        })
+        .collect()
+}
+
+/// The ids of the jobs `filter` lets through within its `max_commute` of
+/// `home`, on every page, in the order of its sort or the shortest commute
+/// first. The commutes are estimated for the first `MAX_COMMUTE_CANDIDATES`
+/// jobs in reach in that order, the nearest ones when sorting by commute,
+/// and the jobs after those are left out.
+pub async fn commute_job_ids(
+    database: &DataBase,
+    filter: &JobFilter,
+    home: &HomeLocation,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mode = home.commute_mode;
+    let radius_km = filter
+        .max_commute
+        .map_or(MAX_RADIUS_KM, |minutes| mode.reach_km(minutes))
+        .min(MAX_RADIUS_KM);
+    if radius_km <= 0.0 {
+        return Ok(Vec::new());
+    }
+    let candidates = database
+        .job_locations_near(
+            filter,
+            home.location,
+            radius_km,
+            MAX_COMMUTE_CANDIDATES as i64,
+        )
+        .await?;
+
+    let locations: Vec<GeoPoint> =
+        candidates.iter().map(|&(_, location)| location).collect();
+    let commutes =
+        estimate_commutes(ROUTING.as_ref(), home.location, &locations, mode)
+            .await;
+    let mut jobs: Vec<(i64, f64)> = candidates
+        .iter()
+        .zip(commutes)
+        .map(|(&(id, _), commute)| (id, commute.minutes))
+        .filter(|&(_, minutes)| {
+            filter.max_commute.is_none_or(|max| minutes <= max)
+        })
+        .collect();
+    // the candidates are nearest first already, which breaks the ties
+    if filter.sort == JobSort::Commute {
+        jobs.sort_by(|a, b| a.1.total_cmp(&b.1));
+    }
+    Ok(jobs.into_iter().map(|(id, _)| id).collect())
+}
+
+/// The jobs of `commute_job_ids` on the page `filter` asks for.
+pub async fn commute_jobs(
+    database: &DataBase,
+    filter: &JobFilter,
+    home: &HomeLocation,
+) -> Result<JobPage, sqlx::Error> {
+    let job_ids = commute_job_ids(database, filter, home).await?;
+    let total = job_ids.len() as i64;
+    let page = filter.size_options.page(job_ids);
+    Ok(JobPage {
+        total,
+        jobs: database.get_job_summaries(&page).await?,
+    })
+}
+
+/// Where `address` is, from the jobs posted at exactly that address or else
+/// from the town it names.
+pub async fn geocode(
+    database: &DataBase,
+    address: &str,
+) -> Result<Option<GeoPoint>, sqlx::Error> {
+    Ok(database
+        .address_location(address)
+        .await?
+        .or_else(|| town_location(address)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COPENHAGEN: GeoPoint = GeoPoint {
+        lat: 55.6761,
+        lon: 12.5683,
+    };
+    const ROSKILDE: GeoPoint = GeoPoint {
+        lat: 55.6415,
+        lon: 12.0803,
+    };
+    const AARHUS: GeoPoint = GeoPoint {
+        lat: 56.1629,
+        lon: 10.2039,
+    };
+
+    struct Offline;
+
+    impl RoutingBackend for Offline {
+        async fn commute_minutes(
+            &self,
+            _: GeoPoint,
+            _: &[GeoPoint],
+            _: CommuteMode,
+        ) -> Result<Vec<Option<f64>>, RoutingError> {
+            Err(RoutingError::Response("NoRoute".to_owned()))
+        }
+    }
+
+    /// Only knows the way to Roskilde.
+    struct RoskildeOnly;
+
+    impl RoutingBackend for RoskildeOnly {
+        async fn commute_minutes(
+            &self,
+            _: GeoPoint,
+            to: &[GeoPoint],
+            _: CommuteMode,
+        ) -> Result<Vec<Option<f64>>, RoutingError> {
+            Ok(to
+                .iter()
+                .map(|&to| (to == ROSKILDE).then_some(25.0))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn osrm_tables_are_asked_for_in_batches() {
+        let mut server = mockito::Server::new_async().await;
+        // the source, then an hour and two to the first destinations
+        let table = server
+            .mock("GET", mockito::Matcher::Regex("^/table/v1/car/".into()))
+            .with_body(r#"{"code":"Ok","durations":[[0,3600,7200]]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let osrm = Osrm::new(Url::parse(&server.url()).unwrap());
+
+        let to = vec![ROSKILDE; Osrm::TABLE_DESTINATIONS + 1];
+        let minutes = osrm
+            .commute_minutes(COPENHAGEN, &to, CommuteMode::Car)
+            .await
+            .unwrap();
+        table.assert_async().await;
+        assert_eq!(minutes.len(), to.len());
+        assert_eq!(minutes[..3], [Some(60.0), Some(120.0), None]);
+        assert_eq!(minutes[Osrm::TABLE_DESTINATIONS], Some(60.0));
+
+        let none = osrm
+            .commute_minutes(COPENHAGEN, &[], CommuteMode::Car)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn straight_line_estimates() {
+        // 30.9 km in a straight line, 40.1 km by road
+        let by_car =
+            StraightLine::minutes(COPENHAGEN, ROSKILDE, CommuteMode::Car);
+        assert!((by_car - 53.1).abs() < 0.1, "{by_car}");
+        let by_bike =
+            StraightLine::minutes(COPENHAGEN, ROSKILDE, CommuteMode::Bike);
+        assert!(by_bike > by_car);
+        assert_eq!(
+            StraightLine::minutes(AARHUS, AARHUS, CommuteMode::Transit),
+            10.0
+        );
+    }
+
+    #[test]
+    fn reach_bounds_the_estimate() {
+        for mode in CommuteMode::ALL {
+            let minutes = StraightLine::minutes(COPENHAGEN, AARHUS, mode);
+            assert!(mode.reach_km(minutes) >= COPENHAGEN.distance_km(AARHUS));
+        }
+        assert_eq!(CommuteMode::Transit.reach_km(5.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn backend_falls_back_to_straight_line() {
+        let to = [ROSKILDE, AARHUS];
+        let offline = estimate_commutes(
+            Some(&Offline),
+            COPENHAGEN,
+            &to,
+            CommuteMode::Car,
+        )
+        .await;
+        let none = estimate_commutes::<Offline>(
+            None,
+            COPENHAGEN,
+            &to,
+            CommuteMode::Car,
+        )
+        .await;
+        assert_eq!(offline, none);
+        assert!(offline.iter().all(|commute| !commute.routed));
+
+        let partly = estimate_commutes(
+            Some(&RoskildeOnly),
+            COPENHAGEN,
+            &to,
+            CommuteMode::Car,
+        )
+        .await;
+        assert_eq!(
+            partly[0],
+            Commute {
+                minutes: 25.0,
+                routed: true
+            }
+        );
+        assert_eq!(partly[1], none[1]);
+    }
+}
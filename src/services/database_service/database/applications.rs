@@ -0,0 +1,562 @@
+use super::query::job_summaries;
+use super::*;
+use crate::services::database_service::types::{
+    ApplicationDocument, ApplicationStatus, CoverLetterTemplate, Cv, DateTime,
+    DocumentKind, JobApplication, JobSummary, Reminder, StatusChange, Utc,
+};
+use std::collections::HashMap;
+
+impl DataBase {
+    /// The applications of `user_id`, the most recently updated first, or
+    /// only application `id`.
+    pub async fn get_applications(
+        &self,
+        user_id: i64,
+        id: Option<i64>,
+    ) -> Result<Vec<JobApplication>, sqlx::Error> {
+        let applications = sqlx::query!(
+            r#"--sql
+        SELECT job_application.id, job_application.user_id,
+            job_application.job_id, job_application.status,
+            job_application.notes, job_application.created_at,
+            job_application.updated_at, job_application.applied_at,
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'from', change.from_status,
+                    'to', change.to_status,
+                    'changed_at', change.changed_at
+                ) ORDER BY change.changed_at, change.id)
+                FROM job_application_status_change AS change
+                WHERE change.application_id = job_application.id
+            ), '[]') AS "history!: Json<Vec<StatusChange>>",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'id', document.id,
+                    'kind', document.kind,
+                    'name', document.name,
+                    'content_type', document.content_type,
+                    'size', octet_length(document.content),
+                    'created_at', document.created_at
+                ) ORDER BY document.created_at, document.id)
+                FROM job_application_document AS document
+                WHERE document.application_id = job_application.id
+            ), '[]') AS "documents!: Json<Vec<ApplicationDocument>>",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'id', reminder.id,
+                    'application_id', reminder.application_id,
+                    'remind_at', reminder.remind_at,
+                    'note', reminder.note,
+                    'done_at', reminder.done_at,
+                    'created_at', reminder.created_at
+                ) ORDER BY reminder.done_at IS NOT NULL, reminder.remind_at, reminder.id)
+                FROM job_application_reminder AS reminder
+                WHERE reminder.application_id = job_application.id
+            ), '[]') AS "reminders!: Json<Vec<Reminder>>"
+        FROM job_application
+        WHERE job_application.user_id = $1
+        AND ($2::bigint IS NULL OR job_application.id = $2)
+        ORDER BY job_application.updated_at DESC, job_application.id DESC
+        "#,
+            user_id,
+            id,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let job_ids = applications
+            .iter()
+            .map(|application| application.job_id)
+            .collect();
+        let mut jobs: HashMap<i64, JobSummary> =
+            job_summaries(&self.database, job_ids)
+                .await?
+                .into_iter()
+                .map(|job| (job.id, job))
+                .collect();
+
+        Ok(applications
+            .into_iter()
+            .filter_map(|application| {
+                Some(JobApplication {
+                    id: application.id,
+                    user_id: application.user_id,
+                    job: jobs.remove(&application.job_id)?,
+                    status: ApplicationStatus::from_name(&application.status)?,
+                    notes: application.notes,
+                    created_at: application.created_at,
+                    updated_at: application.updated_at,
+                    applied_at: application.applied_at,
+                    history: application.history.0,
+                    documents: application.documents.0,
+                    reminders: application.reminders.0,
+                })
+            })
+            .collect())
+    }
+
+    /// Document `document_id` of application `application_id` of
+    /// `user_id`, with its content.
+    pub async fn get_application_document(
+        &self,
+        user_id: i64,
+        application_id: i64,
+        document_id: i64,
+    ) -> Result<Option<(ApplicationDocument, Vec<u8>)>, sqlx::Error> {
+        let document = sqlx::query!(
+            r#"--sql
+        SELECT document.id, document.kind, document.name,
+            document.content_type, document.content, document.created_at
+        FROM job_application_document AS document
+        INNER JOIN job_application ON job_application.id = document.application_id
+        WHERE document.id = $3 AND document.application_id = $2
+        AND job_application.user_id = $1
+        "#,
+            user_id,
+            application_id,
+            document_id,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(document.map(|document| {
+            (
+                ApplicationDocument {
+                    id: document.id,
+                    kind: DocumentKind::from_name(&document.kind)
+                        .unwrap_or(DocumentKind::Other),
+                    name: document.name,
+                    content_type: document.content_type,
+                    size: document.content.len() as i64,
+                    created_at: document.created_at,
+                },
+                document.content,
+            )
+        }))
+    }
+
+    /// The open reminders of `user_id`, soonest first, or only the ones
+    /// due by `due_by`.
+    pub async fn get_reminders(
+        &self,
+        user_id: i64,
+        due_by: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Reminder>, sqlx::Error> {
+        sqlx::query_as!(
+            Reminder,
+            r#"--sql
+        SELECT reminder.id, reminder.application_id, reminder.remind_at,
+            reminder.note, reminder.done_at, reminder.created_at
+        FROM job_application_reminder AS reminder
+        INNER JOIN job_application ON job_application.id = reminder.application_id
+        WHERE job_application.user_id = $1 AND reminder.done_at IS NULL
+        AND ($2::timestamptz IS NULL OR reminder.remind_at <= $2)
+        ORDER BY reminder.remind_at, reminder.id
+        "#,
+            user_id,
+            due_by,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+
+    pub async fn get_cv(
+        &self,
+        user_id: i64,
+    ) -> Result<Option<Cv>, sqlx::Error> {
+        let cv = sqlx::query_scalar!(
+            r#"--sql
+        SELECT content AS "content: Json<Cv>"
+        FROM cv
+        WHERE user_id = $1
+        "#,
+            user_id,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        Ok(cv.map(|Json(cv)| cv))
+    }
+
+    /// The cover letter templates of `user_id` by name, or only the one
+    /// called `name`.
+    pub async fn get_cover_letter_templates(
+        &self,
+        user_id: i64,
+        name: Option<&str>,
+    ) -> Result<Vec<CoverLetterTemplate>, sqlx::Error> {
+        sqlx::query_as!(
+            CoverLetterTemplate,
+            r#"--sql
+        SELECT id, user_id, name, body, updated_at
+        FROM cover_letter_template
+        WHERE user_id = $1 AND ($2::varchar IS NULL OR name = $2)
+        ORDER BY name
+        "#,
+            user_id,
+            name,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+}
+
+impl DataBase {
+    /// Starts tracking an application of `user_id` for job `job_id`.
+    /// `None` when there is no such job.
+    pub async fn insert_application(
+        &self,
+        user_id: i64,
+        job_id: i64,
+        status: ApplicationStatus,
+        notes: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"--sql
+        WITH application AS (
+            INSERT INTO job_application (user_id, job_id, status, notes, applied_at)
+            SELECT $1, job.id, $3, $4, CASE WHEN $5 THEN now() END
+            FROM job
+            WHERE job.id = $2
+            RETURNING id, status
+        )
+        INSERT INTO job_application_status_change (application_id, to_status)
+        SELECT id, status FROM application
+        RETURNING application_id
+        "#,
+            user_id,
+            job_id,
+            status.as_str(),
+            notes,
+            status.has_applied(),
+        )
+        .fetch_optional(&self.database)
+        .await
+    }
+
+    /// Moves application `id` of `user_id` on from `from` to `to`, and
+    /// records the change. False when it is not in `from`, like when it
+    /// was moved meanwhile.
+    pub async fn set_application_status(
+        &self,
+        user_id: i64,
+        id: i64,
+        from: ApplicationStatus,
+        to: ApplicationStatus,
+    ) -> Result<bool, sqlx::Error> {
+        let moved = sqlx::query_scalar!(
+            r#"--sql
+        WITH moved AS (
+            UPDATE job_application
+            SET status = $4, updated_at = now(),
+                applied_at = COALESCE(applied_at, CASE WHEN $5 THEN now() END)
+            WHERE id = $2 AND user_id = $1 AND status = $3
+            RETURNING id
+        )
+        INSERT INTO job_application_status_change (application_id, from_status, to_status)
+        SELECT id, $3, $4 FROM moved
+        RETURNING application_id
+        "#,
+            user_id,
+            id,
+            from.as_str(),
+            to.as_str(),
+            to.has_applied(),
+        )
+        .fetch_optional(&self.database)
+        .await?;
+        Ok(moved.is_some())
+    }
+
+    /// Whether there was an application `id` of `user_id` to write the
+    /// notes of.
+    pub async fn set_application_notes(
+        &self,
+        user_id: i64,
+        id: i64,
+        notes: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let updated = sqlx::query!(
+            r#"--sql
+        UPDATE job_application SET notes = $3, updated_at = now()
+        WHERE id = $2 AND user_id = $1
+        "#,
+            user_id,
+            id,
+            notes,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    /// Whether there was an application `id` of `user_id` to delete. Its
+    /// history, documents and reminders go with it.
+    pub async fn delete_application(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM job_application
+        WHERE id = $2 AND user_id = $1
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Attaches a document to application `application_id` of `user_id`.
+    /// `None` when there is no such application.
+    pub async fn insert_application_document(
+        &self,
+        user_id: i64,
+        application_id: i64,
+        kind: DocumentKind,
+        name: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<Option<ApplicationDocument>, sqlx::Error> {
+        let document = sqlx::query!(
+            r#"--sql
+        INSERT INTO job_application_document (application_id, kind, name, content_type, content)
+        SELECT id, $3, $4, $5, $6
+        FROM job_application
+        WHERE id = $2 AND user_id = $1
+        RETURNING id, created_at
+        "#,
+            user_id,
+            application_id,
+            kind.as_str(),
+            name,
+            content_type,
+            content,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(document.map(|document| ApplicationDocument {
+            id: document.id,
+            kind,
+            name: name.to_owned(),
+            content_type: content_type.to_owned(),
+            size: content.len() as i64,
+            created_at: document.created_at,
+        }))
+    }
+
+    /// Whether there was a document `document_id` on application
+    /// `application_id` of `user_id` to delete.
+    pub async fn delete_application_document(
+        &self,
+        user_id: i64,
+        application_id: i64,
+        document_id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM job_application_document AS document
+        USING job_application
+        WHERE document.id = $3 AND document.application_id = $2
+        AND job_application.id = document.application_id
+        AND job_application.user_id = $1
+        "#,
+            user_id,
+            application_id,
+            document_id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Sets a reminder on application `application_id` of `user_id`.
+    /// `None` when there is no such application.
+    pub async fn insert_reminder(
+        &self,
+        user_id: i64,
+        application_id: i64,
+        remind_at: DateTime<Utc>,
+        note: &str,
+    ) -> Result<Option<Reminder>, sqlx::Error> {
+        sqlx::query_as!(
+            Reminder,
+            r#"--sql
+        INSERT INTO job_application_reminder (application_id, remind_at, note)
+        SELECT id, $3, $4
+        FROM job_application
+        WHERE id = $2 AND user_id = $1
+        RETURNING id, application_id, remind_at, note, done_at, created_at
+        "#,
+            user_id,
+            application_id,
+            remind_at,
+            note,
+        )
+        .fetch_optional(&self.database)
+        .await
+    }
+
+    /// Whether there was an open reminder `id` of `user_id` to mark done.
+    pub async fn complete_reminder(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let completed = sqlx::query!(
+            r#"--sql
+        UPDATE job_application_reminder AS reminder SET done_at = now()
+        FROM job_application
+        WHERE reminder.id = $2 AND reminder.done_at IS NULL
+        AND job_application.id = reminder.application_id
+        AND job_application.user_id = $1
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(completed.rows_affected() > 0)
+    }
+
+    /// Stores the CV of `user_id`, in place of the one it had.
+    pub async fn upsert_cv(
+        &self,
+        user_id: i64,
+        cv: &Cv,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO cv (user_id, content)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET content = EXCLUDED.content, updated_at = now()
+        "#,
+            user_id,
+            Json(cv) as _,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether `user_id` had a CV to delete.
+    pub async fn delete_cv(&self, user_id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM cv
+        WHERE user_id = $1
+        "#,
+            user_id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Stores the template `name` of `user_id`, in place of the one it
+    /// had.
+    pub async fn upsert_cover_letter_template(
+        &self,
+        user_id: i64,
+        name: &str,
+        body: &str,
+    ) -> Result<CoverLetterTemplate, sqlx::Error> {
+        sqlx::query_as!(
+            CoverLetterTemplate,
+            r#"--sql
+        INSERT INTO cover_letter_template (user_id, name, body)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, name) DO UPDATE
+        SET body = EXCLUDED.body, updated_at = now()
+        RETURNING id, user_id, name, body, updated_at
+        "#,
+            user_id,
+            name,
+            body,
+        )
+        .fetch_one(&self.database)
+        .await
+    }
+
+    /// Whether `user_id` had a template `name` to delete.
+    pub async fn delete_cover_letter_template(
+        &self,
+        user_id: i64,
+        name: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM cover_letter_template
+        WHERE user_id = $1 AND name = $2
+        "#,
+            user_id,
+            name,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::{job, user};
+
+    #[sqlx::test]
+    async fn applications_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let job_id = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        let id = database
+            .insert_application(anna, job_id, ApplicationStatus::Interested, "")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(
+            database
+                .get_applications(bo, None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            database
+                .get_applications(bo, Some(id))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !database
+                .set_application_status(
+                    bo,
+                    id,
+                    ApplicationStatus::Interested,
+                    ApplicationStatus::Applied,
+                )
+                .await
+                .unwrap()
+        );
+        assert!(
+            !database
+                .set_application_notes(bo, id, "mine")
+                .await
+                .unwrap()
+        );
+        assert!(!database.delete_application(bo, id).await.unwrap());
+
+        let applications = database.get_applications(anna, None).await.unwrap();
+        assert_eq!(applications.len(), 1);
+        assert_eq!(applications[0].status, ApplicationStatus::Interested);
+        assert_eq!(applications[0].notes, "");
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+use crate::services::database_service::types::{
+    CompanyJob, CompanyRating, PostingMonth,
+};
+use sqlx::PgConnection;
+
+impl DataBase {
+    /// Every company with its open jobs and how many jobs it has posted
+    /// each month, or only the company `company_key` resolves to.
+    pub async fn get_companies(
+        &self,
+        company_key: Option<&str>,
+    ) -> Result<Vec<Company>, sqlx::Error> {
+        let companies = sqlx::query!(
+            r#"--sql
+        SELECT company.id, company.name, company.logo_url, company.profile_url,
+            company.rating_score, company.rating_count,
+            ARRAY(
+                SELECT company_alias.name FROM company_alias
+                WHERE company_alias.company_id = company.id
+                ORDER BY company_alias.name
+            ) AS "aliases!",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'id', job.id,
+                    'title', job.title,
+                    'job_url', job.job_url,
+                    'created_at', job.created_at,
+                    'last_date', job.last_date
+                ) ORDER BY job.created_at DESC)
+                FROM job
+                WHERE job.company_id = company.id
+                AND (job.last_date IS NULL OR job.last_date >= now())
+                AND job.expired_at IS NULL
+            ), '[]') AS "open_jobs!: Json<Vec<CompanyJob>>",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'month', month,
+                    'postings', postings
+                ) ORDER BY month)
+                FROM (
+                    SELECT to_char(job.created_at AT TIME ZONE 'Europe/Copenhagen', 'YYYY-MM') AS month,
+                        count(*) AS postings
+                    FROM job
+                    WHERE job.company_id = company.id AND job.created_at IS NOT NULL
+                    GROUP BY month
+                ) AS history
+            ), '[]') AS "history!: Json<Vec<PostingMonth>>"
+        FROM company
+        WHERE $1::varchar IS NULL OR company.normalised_name = $1
+        ORDER BY company.name
+        "#,
+            company_key,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(companies
+            .into_iter()
+            .map(|company| Company {
+                id: company.id,
+                name: company.name.unwrap_or_default(),
+                aliases: company.aliases,
+                logo_url: company.logo_url,
+                profile_url: company.profile_url,
+                rating: company
+                    .rating_score
+                    .zip(company.rating_count)
+                    .map(|(score, count)| CompanyRating { score, count }),
+                open_jobs: company.open_jobs.0,
+                history: company.history.0,
+            })
+            .collect())
+    }
+}
+
+impl DataBase {
+    pub(super) async fn insert_company_with_executor<'a>(
+        company_info: &'a CompanyInfo,
+        executor: &'a mut PgConnection,
+    ) -> Result<i64, sqlx::Error> {
+        // the first name a company is seen under is the one shown, the
+        // rest of what is known about it is kept up to date.
+        let company_id = sqlx::query!(
+            r#"--sql
+        INSERT INTO company (name, normalised_name, logo_url, profile_url, rating_score, rating_count)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (normalised_name)
+        DO UPDATE SET
+            logo_url = COALESCE(EXCLUDED.logo_url, company.logo_url),
+            profile_url = COALESCE(EXCLUDED.profile_url, company.profile_url),
+            rating_score = COALESCE(EXCLUDED.rating_score, company.rating_score),
+            rating_count = COALESCE(EXCLUDED.rating_count, company.rating_count)
+        RETURNING company.id
+        "#,
+            company_info.name,
+            company_key(&company_info.name),
+            company_info.logo_url,
+            company_info.profile_url,
+            company_info.rating.map(|rating| rating.score),
+            company_info.rating.map(|rating| rating.count),
+        )
+        .fetch_one(&mut *executor)
+        .await?
+        .id;
+
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO company_alias (name, company_id)
+        VALUES ($1, $2)
+        ON CONFLICT (name)
+        DO NOTHING
+        "#,
+            company_info.name,
+            company_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(company_id)
+    }
+}
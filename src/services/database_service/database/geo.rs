@@ -0,0 +1,534 @@
+use super::query::{
+    JOB_SUMMARY_FROM, push_job_filter, push_job_order, push_nearest,
+};
+use super::*;
+use crate::services::database_service::types::{
+    CommuteMode, GeoHit, GeoResults, HomeLocation, Region, RegionKind,
+};
+use crate::services::region_service::region_key;
+use crate::util::geo::{GeoArea, GeoPoint};
+use crate::util::options::{JobFilter, JobSort, SizeOptions};
+use std::collections::HashMap;
+
+#[derive(sqlx::FromRow)]
+struct GeoHitRow {
+    id: i64,
+    title: Option<String>,
+    job_url: Option<String>,
+    company: Option<String>,
+    address: Option<String>,
+    lat: f64,
+    lon: f64,
+    distance: f64,
+}
+
+impl DataBase {
+    /// The jobs with a location in `area`, nearest first, and the expired
+    /// ones only if `expired`.
+    pub async fn jobs_near(
+        &self,
+        area: &GeoArea,
+        expired: bool,
+        size_options: &SizeOptions,
+    ) -> Result<GeoResults, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+
+        let mut count = QueryBuilder::new("");
+        push_nearest(&mut count, area, expired);
+        count.push(" SELECT count(*) FROM nearest");
+        let total: i64 = count.build_query_scalar().fetch_one(&mut *tx).await?;
+
+        let mut query = QueryBuilder::new("");
+        push_nearest(&mut query, area, expired);
+        query.push(
+            r#"--sql
+        SELECT job.id, job.title, job.job_url, company.name AS company,
+            nearest.address, nearest.lat, nearest.lon, nearest.distance
+        FROM nearest
+        INNER JOIN job ON job.id = nearest.job_id
+        LEFT JOIN company ON company.id = job.company_id
+        ORDER BY nearest.distance, job.id
+        LIMIT "#,
+        );
+        let (limit, offset) = size_options.limit_offset();
+        query.push_bind(limit).push(" OFFSET ").push_bind(offset);
+        let hits: Vec<GeoHitRow> =
+            query.build_query_as().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(GeoResults {
+            total,
+            hits: hits
+                .into_iter()
+                .map(|hit| GeoHit {
+                    id: hit.id,
+                    title: hit.title,
+                    job_url: hit.job_url,
+                    company: hit.company,
+                    address: hit.address,
+                    location: GeoPoint {
+                        lat: hit.lat,
+                        lon: hit.lon,
+                    },
+                    distance_km: hit.distance / 1000.0,
+                })
+                .collect(),
+        })
+    }
+
+    /// The jobs `filter` lets through that have a location within
+    /// `radius_km` of `center`, with the one of their locations nearest
+    /// to it. They come in the order of the sort of `filter`, or nearest
+    /// first when it sorts by commute, and at most `limit` of them.
+    pub async fn job_locations_near(
+        &self,
+        filter: &JobFilter,
+        center: GeoPoint,
+        radius_km: f64,
+        limit: i64,
+    ) -> Result<Vec<(i64, GeoPoint)>, sqlx::Error> {
+        #[derive(sqlx::FromRow)]
+        struct NearestRow {
+            id: i64,
+            lat: f64,
+            lon: f64,
+        }
+
+        let radius = radius_km * 1000.0;
+        let mut query =
+            QueryBuilder::new("SELECT job.id, nearest.lat, nearest.lon");
+        query.push(JOB_SUMMARY_FROM);
+        query
+            .push(
+                r#"--sql
+        CROSS JOIN LATERAL (
+            SELECT job_location.x AS lat, job_location.y AS lon,
+                earth_distance(center.point, ll_to_earth(job_location.x, job_location.y)) AS distance
+            FROM (SELECT ll_to_earth("#,
+            )
+            .push_bind(center.lat)
+            .push(", ")
+            .push_bind(center.lon)
+            .push(
+                r#") AS point) AS center,
+                location_for_job
+            INNER JOIN job_location ON job_location.id = location_for_job.location_id
+            WHERE location_for_job.job_id = job.id
+            AND earth_box(center.point, "#,
+            )
+            .push_bind(radius)
+            .push(
+                ") @> ll_to_earth(job_location.x, job_location.y)
+            AND earth_distance(center.point, ll_to_earth(job_location.x, job_location.y)) <= ",
+            )
+            .push_bind(radius)
+            .push(
+                "
+            ORDER BY distance
+            LIMIT 1
+        ) AS nearest",
+            );
+        push_job_filter(&mut query, filter);
+        match filter.sort {
+            JobSort::Commute => {
+                query.push(" ORDER BY nearest.distance, job.id");
+            }
+            _ => push_job_order(&mut query, filter),
+        };
+        query.push(" LIMIT ").push_bind(limit);
+        let jobs: Vec<NearestRow> =
+            query.build_query_as().fetch_all(&self.database).await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|job| {
+                let location = GeoPoint {
+                    lat: job.lat,
+                    lon: job.lon,
+                };
+                (job.id, location)
+            })
+            .collect())
+    }
+
+    /// The home locations of `user_id`, or only the one named `name`.
+    pub async fn get_home_locations(
+        &self,
+        user_id: i64,
+        name: Option<&str>,
+    ) -> Result<Vec<HomeLocation>, sqlx::Error> {
+        let homes = sqlx::query!(
+            r#"--sql
+        SELECT name, address, x, y, commute_mode
+        FROM home_location
+        WHERE user_id = $1 AND ($2::varchar IS NULL OR name = $2)
+        ORDER BY name
+        "#,
+            user_id,
+            name,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(homes
+            .into_iter()
+            .map(|home| HomeLocation {
+                name: home.name,
+                address: home.address,
+                location: GeoPoint {
+                    lat: home.x,
+                    lon: home.y,
+                },
+                commute_mode: CommuteMode::from_name(&home.commute_mode)
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// The middle of the job locations at exactly `address`, like
+    /// "Tangen 4, 8200 Aarhus N", if any job has been posted there. The
+    /// case of the address does not matter.
+    pub async fn address_location(
+        &self,
+        address: &str,
+    ) -> Result<Option<GeoPoint>, sqlx::Error> {
+        let location = sqlx::query!(
+            r#"--sql
+        SELECT avg(x) AS lat, avg(y) AS lon
+        FROM job_location
+        WHERE lower(address) = lower($1) AND (x, y) <> (0, 0)
+        "#,
+            address.trim(),
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(location
+            .lat
+            .zip(location.lon)
+            .map(|(lat, lon)| GeoPoint { lat, lon }))
+    }
+
+    /// The regions in the catalogue whose name has `name` in it, the ones
+    /// named exactly that first and then the largest.
+    pub async fn search_regions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<Region>, sqlx::Error> {
+        let regions = sqlx::query!(
+            r#"--sql
+        SELECT id, name, code, kind, parent_id
+        FROM geoarea
+        WHERE strpos(regexp_replace(lower(name), '[^[:alnum:]]', '', 'g'), $1) > 0
+        ORDER BY regexp_replace(lower(name), '[^[:alnum:]]', '', 'g') = $1 DESC,
+            array_position($2::varchar[], kind), name
+        LIMIT 10
+        "#,
+            region_key(name),
+            &RegionKind::ALL.map(|kind| kind.as_str().to_owned()),
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(regions
+            .into_iter()
+            .map(|row| {
+                region(row.id, row.name, row.code, &row.kind, row.parent_id)
+            })
+            .collect())
+    }
+
+    /// The whole region catalogue.
+    pub async fn get_regions(&self) -> Result<Vec<Region>, sqlx::Error> {
+        let regions = sqlx::query!(
+            r#"--sql
+        SELECT id, name, code, kind, parent_id
+        FROM geoarea
+        ORDER BY id
+        "#,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(regions
+            .into_iter()
+            .map(|row| {
+                region(row.id, row.name, row.code, &row.kind, row.parent_id)
+            })
+            .collect())
+    }
+
+    /// The regions each stored job lies in.
+    pub async fn job_geoareas(&self) -> Result<Vec<Vec<i64>>, sqlx::Error> {
+        let jobs = sqlx::query!(
+            r#"--sql
+        SELECT array_agg(geoarea_id ORDER BY geoarea_id) AS "geoarea_ids!"
+        FROM geoarea_for_job
+        GROUP BY job_id
+        "#,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(jobs.into_iter().map(|job| job.geoarea_ids).collect())
+    }
+
+    /// The parents the catalogue knows of the regions `ids`.
+    pub async fn region_parents(
+        &self,
+        ids: &[i64],
+    ) -> Result<HashMap<i64, i64>, sqlx::Error> {
+        let parents = sqlx::query!(
+            r#"--sql
+        SELECT id, parent_id AS "parent_id!"
+        FROM geoarea
+        WHERE id = ANY($1) AND parent_id IS NOT NULL
+        "#,
+            ids,
+        )
+        .fetch_all(&self.database)
+        .await?;
+        Ok(parents
+            .into_iter()
+            .map(|region| (region.id, region.parent_id))
+            .collect())
+    }
+}
+
+impl DataBase {
+    /// Adds the home location of `user_id`, or replaces their one with
+    /// the same name.
+    pub async fn upsert_home_location(
+        &self,
+        user_id: i64,
+        home: &HomeLocation,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO home_location (user_id, name, address, x, y, commute_mode)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, name)
+        DO UPDATE SET
+            address = EXCLUDED.address,
+            x = EXCLUDED.x,
+            y = EXCLUDED.y,
+            commute_mode = EXCLUDED.commute_mode
+        "#,
+            user_id,
+            home.name,
+            home.address,
+            home.location.lat,
+            home.location.lon,
+            home.commute_mode.as_str(),
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether `user_id` had a home location named `name` to delete.
+    pub async fn delete_home_location(
+        &self,
+        user_id: i64,
+        name: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+            DELETE FROM home_location
+            WHERE user_id = $1 AND name = $2
+            "#,
+            user_id,
+            name,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Adds the regions to the catalogue or updates them, keeping the
+    /// parents that are known. Returns them as stored, in order.
+    pub async fn upsert_regions(
+        &self,
+        regions: &[Region],
+    ) -> Result<Vec<Region>, sqlx::Error> {
+        let ids: Vec<i64> = regions.iter().map(|region| region.id).collect();
+        let names: Vec<&str> =
+            regions.iter().map(|region| region.name.as_str()).collect();
+        let codes: Vec<Option<&str>> = regions
+            .iter()
+            .map(|region| region.code.as_deref())
+            .collect();
+        let kinds: Vec<&str> =
+            regions.iter().map(|region| region.kind.as_str()).collect();
+        let mut stored: HashMap<i64, Region> = sqlx::query!(
+            r#"--sql
+        INSERT INTO geoarea (id, name, code, kind)
+        SELECT DISTINCT ON (id) * FROM UNNEST($1::bigint[], $2::varchar[], $3::varchar[], $4::varchar[])
+            AS region (id, name, code, kind)
+        ON CONFLICT (id)
+        DO UPDATE SET
+            name = EXCLUDED.name,
+            code = COALESCE(EXCLUDED.code, geoarea.code),
+            kind = EXCLUDED.kind
+        RETURNING id, name, code, kind, parent_id
+        "#,
+            &ids,
+            &names as &[&str],
+            &codes as &[Option<&str>],
+            &kinds as &[&str],
+        )
+        .fetch_all(&self.database)
+        .await?
+        .into_iter()
+        .map(|row| {
+            (
+                row.id,
+                region(row.id, row.name, row.code, &row.kind, row.parent_id),
+            )
+        })
+        .collect();
+
+        Ok(ids.iter().filter_map(|id| stored.remove(id)).collect())
+    }
+
+    /// Sets the parent of each region in `parents`, by id.
+    pub async fn set_region_parents(
+        &self,
+        parents: &HashMap<i64, i64>,
+    ) -> Result<(), sqlx::Error> {
+        let (ids, parent_ids): (Vec<i64>, Vec<i64>) = parents.iter().unzip();
+        sqlx::query!(
+            r#"--sql
+        UPDATE geoarea SET parent_id = parents.parent_id
+        FROM UNNEST($1::bigint[], $2::bigint[]) AS parents (id, parent_id)
+        WHERE geoarea.id = parents.id
+        "#,
+            &ids,
+            &parent_ids,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Gives the stored jobs that lie in no region the postcode region
+    /// their address ends with, like "8200 Aarhus N", and the regions
+    /// above it, and returns how many jobs got regions. Jobs stored
+    /// before regions were kept have none, and an address without a
+    /// postcode the catalogue knows still leaves a job without any.
+    pub async fn backfill_job_geoareas(&self) -> Result<i64, sqlx::Error> {
+        let jobs = sqlx::query_scalar!(
+            r#"--sql
+        WITH RECURSIVE matched (job_id, geoarea_id) AS (
+            SELECT DISTINCT location_for_job.job_id, geoarea.id
+            FROM location_for_job
+            INNER JOIN job_location ON job_location.id = location_for_job.location_id
+            INNER JOIN geoarea ON geoarea.kind = $1
+            AND right(lower(job_location.address), length(geoarea.name)) = lower(geoarea.name)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM geoarea_for_job
+                WHERE geoarea_for_job.job_id = location_for_job.job_id
+            )
+            UNION
+            SELECT matched.job_id, geoarea.parent_id
+            FROM matched
+            INNER JOIN geoarea ON geoarea.id = matched.geoarea_id
+            WHERE geoarea.parent_id IS NOT NULL
+        ), inserted AS (
+            INSERT INTO geoarea_for_job (job_id, geoarea_id)
+            SELECT job_id, geoarea_id FROM matched
+            ON CONFLICT DO NOTHING
+            RETURNING job_id
+        )
+        SELECT count(DISTINCT job_id) AS "jobs!" FROM inserted
+        "#,
+            RegionKind::Postcode.as_str(),
+        )
+        .fetch_one(&self.database)
+        .await?;
+        Ok(jobs)
+    }
+}
+
+/// A region as stored in the `geoarea` table.
+fn region(
+    id: i64,
+    name: String,
+    code: Option<String>,
+    kind: &str,
+    parent_id: Option<i64>,
+) -> crate::services::database_service::types::Region {
+    use crate::services::database_service::types::{Region, RegionKind};
+
+    Region {
+        id,
+        name,
+        code,
+        // the catch-all kind, like the areas JobIndex does not type
+        kind: RegionKind::from_name(kind).unwrap_or(RegionKind::Area),
+        parent_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::{job, user};
+
+    fn aarhus() -> GeoPoint {
+        GeoPoint::new(56.15, 10.2).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn jobs_near_leave_the_expired_jobs_out(pool: PgPool) {
+        let database = DataBase { database: pool };
+        database.insert_job(&job("open", "Acme", 10)).await.unwrap();
+        database.insert_job(&job("past", "Acme", -1)).await.unwrap();
+        database.expire_jobs().await.unwrap();
+
+        let area = GeoArea::Radius {
+            center: aarhus(),
+            radius_km: 5.0,
+        };
+        let near =
+            |expired| database.jobs_near(&area, expired, &SizeOptions::All);
+        assert_eq!(near(false).await.unwrap().total, 1);
+        assert_eq!(near(true).await.unwrap().total, 2);
+    }
+
+    #[sqlx::test]
+    async fn home_locations_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let home = HomeLocation {
+            name: "home".to_owned(),
+            address: None,
+            location: aarhus(),
+            commute_mode: CommuteMode::Car,
+        };
+        database.upsert_home_location(anna, &home).await.unwrap();
+
+        assert!(
+            database
+                .get_home_locations(bo, None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            database
+                .get_home_locations(bo, Some("home"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!database.delete_home_location(bo, "home").await.unwrap());
+        assert_eq!(
+            database.get_home_locations(anna, None).await.unwrap().len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,1106 @@
+use super::query::{
+    JOB_SUMMARY_COLUMNS, JOB_SUMMARY_FROM, JobSummaryRow, job_summaries,
+    push_job_filter, push_job_order,
+};
+use super::*;
+use crate::job_fetchers::tag_matcher::TAG_MATCHER;
+use crate::services::database_service::types::{
+    CompanyRating, Currency, Description, EmploymentType, JobClassification,
+    JobInfo, JobPage, JobSummary, Language, PhoneNumber, SalaryPeriod,
+    SearchHit, SearchResults, Seniority, TagHit, Title,
+};
+use crate::util::options::{HiddenMode, JobFilter, SizeOptions};
+use serde::Deserialize;
+use sqlx::PgConnection;
+use std::collections::HashMap;
+
+/// A tag of a stored job, as aggregated by `load_jobs`.
+#[derive(Debug, Deserialize)]
+struct StoredTag {
+    name: String,
+    match_count: u32,
+    in_title: bool,
+    weight: f64,
+    hits: Vec<TagHit>,
+}
+
+/// A location of a stored job, as aggregated by `load_jobs`.
+#[derive(Debug, Deserialize)]
+struct StoredLocation {
+    address: Option<String>,
+    x: Option<f64>,
+    y: Option<f64>,
+}
+
+// impl DataBase {
+//     pub async fn get_job_by_job_url(
+//         &self,
+//         job_url: JobUrl,
+//     ) -> Result<Job, sqlx::Error> {
+//         let mut tx: Transaction<'_, Postgres> =
+//             self.database.begin().await?;
+
+//         let job = sqlx::query!(
+//             "SELECT *
+//         FROM job INNER JOIN company ON
+//         job.company_id = company.id AND job.job_url= $1",
+//             job_url.0
+//         )
+//         .fetch_one(&mut *tx)
+//         .await?;
+//         tx.commit().await?;
+//         Ok(())
+//     }
+// }
+
+impl DataBase {
+    /// Jobs matching a web search style query, e.g. `rust -senior` or
+    /// `"machine learning"`, best match first.
+    ///
+    /// A job is stemmed in its own language, but the query language is
+    /// unknown, so the query is run as Danish, English and unstemmed.
+    ///
+    /// With a `viewer`, the jobs are left out or kept by whether the viewer
+    /// has hidden them. The expired jobs are only searched if `expired`.
+    pub async fn search_jobs(
+        &self,
+        query: &str,
+        viewer: Option<i64>,
+        hidden: HiddenMode,
+        expired: bool,
+        size_options: &SizeOptions,
+    ) -> Result<SearchResults, sqlx::Error> {
+        let hidden = hidden.wanted();
+        let (limit, offset) = size_options.limit_offset();
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+
+        let total = sqlx::query!(
+            r#"--sql
+        SELECT count(*) AS "total!"
+        FROM job
+        WHERE job.search_vector @@ (
+            websearch_to_tsquery('danish', $1)
+            || websearch_to_tsquery('english', $1)
+            || websearch_to_tsquery('simple', $1)
+        )
+        AND ($2::bigint IS NULL OR $3::boolean IS NULL
+            OR job_is_hidden($2, job.id, job.company_id) = $3)
+        AND ($4 OR job.expired_at IS NULL)
+        "#,
+            query,
+            viewer,
+            hidden,
+            expired,
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .total;
+
+        let hits = sqlx::query!(
+            r#"--sql
+        WITH search AS (
+            SELECT websearch_to_tsquery('danish', $1)
+                || websearch_to_tsquery('english', $1)
+                || websearch_to_tsquery('simple', $1) AS query
+        )
+        SELECT job.id, job.job_url, job.language, job.created_at,
+            company.name AS "company?",
+            ts_rank_cd(job.search_vector, search.query) AS "rank!",
+            ts_headline(job_search_config(job.language), coalesce(job.title, ''),
+                search.query, 'HighlightAll=true') AS "title!",
+            ts_headline(job_search_config(job.language), coalesce(job.description, ''),
+                search.query, 'MaxFragments=2, MinWords=8, MaxWords=25') AS "snippet!"
+        FROM job
+        CROSS JOIN search
+        LEFT JOIN company ON company.id = job.company_id
+        WHERE job.search_vector @@ search.query
+        AND ($4::bigint IS NULL OR $5::boolean IS NULL
+            OR job_is_hidden($4, job.id, job.company_id) = $5)
+        AND ($6 OR job.expired_at IS NULL)
+        ORDER BY "rank!" DESC, job.created_at DESC NULLS LAST, job.id
+        LIMIT $2 OFFSET $3
+        "#,
+            query,
+            limit,
+            offset,
+            viewer,
+            hidden,
+            expired,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(SearchResults {
+            total,
+            hits: hits
+                .into_iter()
+                .map(|hit| SearchHit {
+                    id: hit.id,
+                    title: hit.title,
+                    snippet: hit.snippet,
+                    rank: hit.rank,
+                    job_url: hit.job_url,
+                    company: hit.company,
+                    language: hit
+                        .language
+                        .as_deref()
+                        .and_then(Language::from_name),
+                    created_at: hit.created_at,
+                    mark: None,
+                })
+                .collect(),
+        })
+    }
+
+    /// The stored jobs matching `filter`, on the page it asks for.
+    pub async fn filter_jobs(
+        &self,
+        filter: &JobFilter,
+    ) -> Result<JobPage, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+
+        let mut count = QueryBuilder::new("SELECT count(*)");
+        count.push(JOB_SUMMARY_FROM);
+        push_job_filter(&mut count, filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&mut *tx).await?;
+
+        let mut query = QueryBuilder::new(JOB_SUMMARY_COLUMNS);
+        query.push(JOB_SUMMARY_FROM);
+        push_job_filter(&mut query, filter);
+        push_job_order(&mut query, filter);
+
+        // no limit is `LIMIT NULL`, which is every row
+        let (limit, offset) = filter.size_options.limit_offset();
+        query
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let jobs: Vec<JobSummaryRow> =
+            query.build_query_as().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(JobPage {
+            total,
+            jobs: jobs.into_iter().map(JobSummary::from).collect(),
+        })
+    }
+
+    /// The ids of every job `filter` lets through, on every page and in
+    /// the order of its sort, for going through them a few at a time.
+    pub async fn filter_job_ids(
+        &self,
+        filter: &JobFilter,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let mut query = QueryBuilder::new("SELECT job.id");
+        query.push(JOB_SUMMARY_FROM);
+        push_job_filter(&mut query, filter);
+        push_job_order(&mut query, filter);
+        query.build_query_scalar().fetch_all(&self.database).await
+    }
+
+    /// The summaries of the jobs with the ids `job_ids`, in that order.
+    pub async fn get_job_summaries(
+        &self,
+        job_ids: &[i64],
+    ) -> Result<Vec<JobSummary>, sqlx::Error> {
+        let mut summaries: HashMap<i64, JobSummary> =
+            job_summaries(&self.database, job_ids.to_vec())
+                .await?
+                .into_iter()
+                .map(|summary| (summary.id, summary))
+                .collect();
+        Ok(job_ids
+            .iter()
+            .filter_map(|id| summaries.remove(id))
+            .collect())
+    }
+
+    /// Those of `job_urls` a job is stored with.
+    pub async fn get_stored_job_urls(
+        &self,
+        job_urls: &[String],
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"--sql
+        SELECT job_url AS "job_url!"
+        FROM job
+        WHERE job_url = ANY($1)
+        "#,
+            job_urls,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+
+    /// The stored job `id`, like `load_jobs` rebuilds it.
+    pub async fn get_job(&self, id: i64) -> Result<Option<Job>, sqlx::Error> {
+        let mut connection = self.database.acquire().await?;
+        let mut jobs = Self::load_jobs(&mut connection, &[id]).await?;
+        Ok(jobs.pop().map(|(_, job)| job))
+    }
+
+    /// The stored jobs with the ids `job_ids` in the same order, leaving
+    /// out those that are not stored.
+    pub async fn get_jobs_by_id(
+        &self,
+        job_ids: &[i64],
+    ) -> Result<Vec<Job>, sqlx::Error> {
+        let mut connection = self.database.acquire().await?;
+        let mut jobs: HashMap<i64, Job> =
+            Self::load_jobs(&mut connection, job_ids)
+                .await?
+                .into_iter()
+                .collect();
+        Ok(job_ids.iter().filter_map(|id| jobs.remove(id)).collect())
+    }
+
+    /// The stored jobs with the ids `job_ids`, rebuilt as they were
+    /// fetched, by id. Tags that are no longer known are left out.
+    pub(super) async fn load_jobs(
+        executor: &mut PgConnection,
+        job_ids: &[i64],
+    ) -> Result<Vec<(i64, Job)>, sqlx::Error> {
+        let jobs = sqlx::query!(
+            r#"--sql
+        SELECT job.id, job.title, job.description, job.job_url,
+            job.seniority, job.employment_type, job.language, job.remote,
+            job.salary_min, job.salary_max, job.salary_currency,
+            job.salary_period, job.salary_negotiable,
+            job.created_at, job.last_date,
+            job.contact_name, job.contact_phone, job.contact_email,
+            company.name AS "company_name?", company.logo_url AS "logo_url?",
+            company.profile_url AS "profile_url?",
+            company.rating_score AS "rating_score?",
+            company.rating_count AS "rating_count?",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'name', job_tag.tag,
+                    'match_count', tags_for_job.match_count,
+                    'in_title', tags_for_job.in_title,
+                    'weight', tags_for_job.weight,
+                    'hits', tags_for_job.hits
+                ) ORDER BY tags_for_job.weight DESC, job_tag.tag)
+                FROM tags_for_job
+                INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+                WHERE tags_for_job.job_id = job.id
+            ), '[]') AS "tags!: Json<Vec<StoredTag>>",
+            COALESCE((
+                SELECT json_agg(json_build_object(
+                    'address', job_location.address,
+                    'x', job_location.x,
+                    'y', job_location.y
+                ) ORDER BY job_location.address)
+                FROM location_for_job
+                INNER JOIN job_location ON job_location.id = location_for_job.location_id
+                WHERE location_for_job.job_id = job.id
+            ), '[]') AS "locations!: Json<Vec<StoredLocation>>",
+            ARRAY(
+                SELECT geoarea_for_job.geoarea_id FROM geoarea_for_job
+                LEFT JOIN geoarea ON geoarea.id = geoarea_for_job.geoarea_id
+                WHERE geoarea_for_job.job_id = job.id
+                ORDER BY array_position(
+                    ARRAY['country', 'region', 'area', 'municipality', 'postcode']::varchar[],
+                    geoarea.kind
+                ), geoarea_for_job.geoarea_id
+            ) AS "geoarea_ids!"
+        FROM job
+        LEFT JOIN company ON company.id = job.company_id
+        WHERE job.id = ANY($1)
+        ORDER BY job.id
+        "#,
+            job_ids,
+        )
+        .fetch_all(&mut *executor)
+        .await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|job| {
+                let salary = (job.salary_min.is_some()
+                    || job.salary_max.is_some()
+                    || job.salary_negotiable)
+                    .then(|| Salary {
+                        min: job.salary_min,
+                        max: job.salary_max,
+                        currency: job
+                            .salary_currency
+                            .as_deref()
+                            .and_then(Currency::from_name),
+                        period: job
+                            .salary_period
+                            .as_deref()
+                            .and_then(SalaryPeriod::from_name),
+                        negotiable: job.salary_negotiable,
+                    });
+                let job_tags = job
+                    .tags
+                    .0
+                    .into_iter()
+                    .filter_map(|tag| {
+                        Some(JobTag {
+                            name: TAG_MATCHER.tag_name(&tag.name)?,
+                            match_count: tag.match_count,
+                            in_title: tag.in_title,
+                            weight: tag.weight,
+                            hits: tag.hits,
+                        })
+                    })
+                    .collect();
+                let locations = job
+                    .locations
+                    .0
+                    .into_iter()
+                    .map(|location| Location {
+                        address: location.address.unwrap_or_default(),
+                        geo_location: (
+                            location.x.unwrap_or_default(),
+                            location.y.unwrap_or_default(),
+                        ),
+                    })
+                    .collect();
+
+                let stored = Job {
+                    job_info: JobInfo {
+                        job_url: JobUrl(job.job_url.unwrap_or_default()),
+                        title: Title(job.title.unwrap_or_default()),
+                        description: Description(
+                            job.description.unwrap_or_default(),
+                        ),
+                        job_tags,
+                        classification: JobClassification {
+                            seniority: job
+                                .seniority
+                                .as_deref()
+                                .and_then(Seniority::from_name),
+                            employment_type: job
+                                .employment_type
+                                .as_deref()
+                                .and_then(EmploymentType::from_name),
+                            language: job
+                                .language
+                                .as_deref()
+                                .and_then(Language::from_name),
+                            remote: job.remote,
+                        },
+                        salary,
+                    },
+                    created_at: job.created_at.unwrap_or_default(),
+                    last_date: job.last_date,
+                    company_info: CompanyInfo {
+                        name: job.company_name.unwrap_or_default(),
+                        logo_url: job.logo_url.unwrap_or_default(),
+                        profile_url: job.profile_url,
+                        rating: job.rating_score.zip(job.rating_count).map(
+                            |(score, count)| CompanyRating { score, count },
+                        ),
+                    },
+                    locations,
+                    geoarea_ids: job.geoarea_ids,
+                    contact_info: job.contact_name.map(|name| ContactInfo {
+                        name,
+                        phone_number: PhoneNumber(
+                            job.contact_phone.unwrap_or_default(),
+                        ),
+                        email: job.contact_email.unwrap_or_default(),
+                    }),
+                };
+                (job.id, stored)
+            })
+            .collect())
+    }
+}
+
+impl DataBase {
+    /// Adds the job, or updates the stored job with the same url, and
+    /// queues the `JobEvent::Created` or `JobEvent::Updated` for it. A
+    /// job fetched again without changes is no event.
+    pub async fn insert_job(&self, job: &Job) -> Result<i64, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+        // if ANY OF THESE FAIL, WE ROLL BACK :)
+
+        //COMPANY
+        let company_id =
+            Self::insert_company_with_executor(&job.company_info, &mut tx)
+                .await?;
+        //JOB
+        let fingerprint = JobFingerprint::new(job);
+        let stored_id = sqlx::query_scalar!(
+            r#"--sql
+        SELECT id FROM job
+        WHERE job_url = $1
+        "#,
+            job.job_info.job_url.0,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (job_id, event) = match stored_id {
+            Some(job_id) => {
+                let changed = Self::update_job_with_executor(
+                    job,
+                    &fingerprint,
+                    company_id,
+                    job_id,
+                    &mut tx,
+                )
+                .await?;
+                if !changed {
+                    tx.commit().await?;
+                    return Ok(job_id);
+                }
+                Self::delete_job_relations_with_executor(job_id, &mut tx)
+                    .await?;
+                (job_id, JobEvent::Updated)
+            }
+            None => {
+                let job_id = Self::insert_job_with_executor(
+                    job,
+                    &fingerprint,
+                    company_id,
+                    &mut tx,
+                )
+                .await?;
+                (job_id, JobEvent::Created)
+            }
+        };
+        //JOB TAGS
+        Self::insert_job_tags_with_executor(&job.job_info.job_tags, &mut *tx)
+            .await?;
+
+        Self::insert_job_tag_relations_with_executor(
+            &job.job_info.job_tags,
+            job_id,
+            &mut *tx,
+        )
+        .await?;
+
+        // JOB LOCATIONS
+        let location_ids =
+            Self::insert_job_locations(&job.locations, &mut *tx).await?;
+
+        Self::insert_job_location_relations_with_executor(
+            location_ids,
+            job_id,
+            &mut *tx,
+        )
+        .await?;
+
+        // REGIONS
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO geoarea_for_job (job_id, geoarea_id)
+        SELECT $1, UNNEST($2::bigint[])
+        ON CONFLICT DO NOTHING
+        "#,
+            job_id,
+            &job.geoarea_ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // DUPLICATES
+        if event == JobEvent::Created {
+            Self::insert_job_cluster_with_executor(
+                &fingerprint,
+                job_id,
+                company_id,
+                &mut tx,
+            )
+            .await?;
+        }
+
+        // EVENTS
+        Self::enqueue_job_events(&mut tx, event, None, &[(job_id, job)])
+            .await?;
+
+        tx.commit().await?;
+        Ok(job_id)
+    }
+
+    /// Marks the jobs whose deadline has passed as expired, and queues
+    /// the `JobEvent::Expired` of each. Returns how many expired.
+    pub async fn expire_jobs(&self) -> Result<usize, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+        let job_ids = sqlx::query_scalar!(
+            r#"--sql
+        UPDATE job SET expired_at = now()
+        WHERE last_date < now() AND expired_at IS NULL
+        RETURNING id
+        "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        Self::enqueue_stored_job_events(
+            &mut tx,
+            JobEvent::Expired,
+            None,
+            &job_ids,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(job_ids.len())
+    }
+
+    /// Matches the tags of every stored job again, for when the tag
+    /// table changed since the jobs were stored. Returns how many jobs
+    /// were retagged.
+    pub async fn retag_jobs(&self) -> Result<usize, sqlx::Error> {
+        const BATCH: i64 = 500;
+        let mut retagged = 0;
+        let mut after = 0;
+        loop {
+            let jobs = sqlx::query!(
+                r#"--sql
+        SELECT id, title, description FROM job
+        WHERE id > $1
+        ORDER BY id
+        LIMIT $2
+        "#,
+                after,
+                BATCH,
+            )
+            .fetch_all(&self.database)
+            .await?;
+            let Some(last) = jobs.last() else {
+                return Ok(retagged);
+            };
+            after = last.id;
+
+            let mut tx: Transaction<'_, Postgres> =
+                self.database.begin().await?;
+            for job in &jobs {
+                let job_tags = TAG_MATCHER.job_tags(
+                    job.title.as_deref().unwrap_or_default(),
+                    job.description.as_deref().unwrap_or_default(),
+                );
+                Self::insert_job_tags_with_executor(&job_tags, &mut tx).await?;
+                sqlx::query!(
+                    r#"--sql
+        DELETE FROM tags_for_job
+        WHERE job_id = $1
+        "#,
+                    job.id,
+                )
+                .execute(&mut *tx)
+                .await?;
+                Self::insert_job_tag_relations_with_executor(
+                    &job_tags, job.id, &mut tx,
+                )
+                .await?;
+            }
+            tx.commit().await?;
+            retagged += jobs.len();
+        }
+    }
+}
+
+impl DataBase {
+    async fn insert_job_with_executor<'a>(
+        job: &'a Job,
+        fingerprint: &'a JobFingerprint,
+        company_id: i64,
+        executor: &'a mut PgConnection,
+    ) -> Result<i64, sqlx::Error> {
+        let job_info = &job.job_info;
+        let classification = &job_info.classification;
+        let salary = job_info.salary.as_ref();
+        let monthly = salary.and_then(Salary::monthly_dkk);
+        let contact = job.contact_info.as_ref();
+        let job_id = sqlx::query!(
+            r#"--sql
+        INSERT INTO job (title, description,job_url,company_id,seniority,employment_type,language,
+            salary_min,salary_max,salary_currency,salary_period,salary_negotiable,salary_monthly_min,salary_monthly_max,
+            dedup_title,dedup_locations,simhash,created_at,last_date,remote,
+            contact_name,contact_phone,contact_email)
+        VALUES ($1, $2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22,$23)
+        RETURNING id
+        "#,
+            job_info.title.0,
+            job_info.description.0,
+            job_info.job_url.0,
+            company_id,
+            classification.seniority.map(|seniority| seniority.as_str()),
+            classification
+                .employment_type
+                .map(|employment_type| employment_type.as_str()),
+            classification.language.map(|language| language.as_str()),
+            salary.and_then(|salary| salary.min),
+            salary.and_then(|salary| salary.max),
+            salary
+                .and_then(|salary| salary.currency)
+                .map(|currency| currency.as_str()),
+            salary
+                .and_then(|salary| salary.period)
+                .map(|period| period.as_str()),
+            salary.is_some_and(|salary| salary.negotiable),
+            monthly.map(|(min, _)| min),
+            monthly.map(|(_, max)| max),
+            fingerprint.title,
+            &fingerprint.locations,
+            // stored bit for bit, it is only ever compared
+            fingerprint.simhash as i64,
+            job.created_at,
+            job.last_date,
+            classification.remote,
+            contact.map(|contact| contact.name.as_str()),
+            contact.map(|contact| contact.phone_number.0.as_str()),
+            contact.map(|contact| contact.email.as_str()),
+        )
+        .fetch_one(&mut *executor)
+        .await?
+        .id;
+
+        Self::update_search_vector_with_executor(job_id, executor).await?;
+        Ok(job_id)
+    }
+
+    /// Updates the stored job `job_id` to `job`, where anything shown
+    /// of it changed. Returns whether it did.
+    async fn update_job_with_executor<'a>(
+        job: &'a Job,
+        fingerprint: &'a JobFingerprint,
+        company_id: i64,
+        job_id: i64,
+        executor: &'a mut PgConnection,
+    ) -> Result<bool, sqlx::Error> {
+        let job_info = &job.job_info;
+        let classification = &job_info.classification;
+        let salary = job_info.salary.as_ref();
+        let monthly = salary.and_then(Salary::monthly_dkk);
+        let contact = job.contact_info.as_ref();
+        // a job that got a new deadline can expire again
+        let updated = sqlx::query!(
+            r#"--sql
+        UPDATE job SET title = $2, description = $3, company_id = $4,
+            seniority = $5, employment_type = $6, language = $7,
+            salary_min = $8, salary_max = $9, salary_currency = $10,
+            salary_period = $11, salary_negotiable = $12,
+            salary_monthly_min = $13, salary_monthly_max = $14,
+            dedup_title = $15, dedup_locations = $16, simhash = $17,
+            last_date = $18, remote = $19, contact_name = $20,
+            contact_phone = $21, contact_email = $22,
+            expired_at = CASE WHEN last_date IS NOT DISTINCT FROM $18 THEN expired_at END
+        WHERE id = $1
+        AND (title, description, company_id, seniority, employment_type,
+            language, salary_min, salary_max, salary_currency,
+            salary_period, salary_negotiable, dedup_locations, last_date,
+            remote, contact_name, contact_phone, contact_email)
+        IS DISTINCT FROM ($2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12,
+            $16, $18, $19, $20, $21, $22)
+        "#,
+            job_id,
+            job_info.title.0,
+            job_info.description.0,
+            company_id,
+            classification.seniority.map(|seniority| seniority.as_str()),
+            classification
+                .employment_type
+                .map(|employment_type| employment_type.as_str()),
+            classification.language.map(|language| language.as_str()),
+            salary.and_then(|salary| salary.min),
+            salary.and_then(|salary| salary.max),
+            salary
+                .and_then(|salary| salary.currency)
+                .map(|currency| currency.as_str()),
+            salary
+                .and_then(|salary| salary.period)
+                .map(|period| period.as_str()),
+            salary.is_some_and(|salary| salary.negotiable),
+            monthly.map(|(min, _)| min),
+            monthly.map(|(_, max)| max),
+            fingerprint.title,
+            &fingerprint.locations,
+            fingerprint.simhash as i64,
+            job.last_date,
+            classification.remote,
+            contact.map(|contact| contact.name.as_str()),
+            contact.map(|contact| contact.phone_number.0.as_str()),
+            contact.map(|contact| contact.email.as_str()),
+        )
+        .execute(&mut *executor)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::update_search_vector_with_executor(job_id, executor).await?;
+        Ok(true)
+    }
+
+    /// Indexes the job in the language it was detected to be in.
+    async fn update_search_vector_with_executor(
+        job_id: i64,
+        executor: &mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        UPDATE job SET search_vector = job_search_vector(
+            job.language, job.title, job.description, company.name
+        )
+        FROM company
+        WHERE job.id = $1 AND company.id = job.company_id
+        "#,
+            job_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the tags, locations and regions of a job that is about to
+    /// get them again.
+    async fn delete_job_relations_with_executor(
+        job_id: i64,
+        executor: &mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        DELETE FROM tags_for_job
+        WHERE job_id = $1
+        "#,
+            job_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        sqlx::query!(
+            r#"--sql
+        DELETE FROM location_for_job
+        WHERE job_id = $1
+        "#,
+            job_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        sqlx::query!(
+            r#"--sql
+        DELETE FROM geoarea_for_job
+        WHERE job_id = $1
+        "#,
+            job_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Puts the job in the cluster of the first earlier job of the same
+    /// company it is a near duplicate of, or in a new cluster. The newest
+    /// job of the cluster is its canonical job, which need not be this
+    /// one when an old posting is stored late.
+    async fn insert_job_cluster_with_executor<'a>(
+        fingerprint: &'a JobFingerprint,
+        job_id: i64,
+        company_id: i64,
+        executor: &'a mut PgConnection,
+    ) -> Result<i64, sqlx::Error> {
+        let candidates = sqlx::query!(
+            r#"--sql
+        SELECT cluster_id as "cluster_id!", dedup_title, dedup_locations, simhash
+        FROM job
+        WHERE company_id = $1 AND id <> $2 AND cluster_id IS NOT NULL
+        ORDER BY id
+        "#,
+            company_id,
+            job_id,
+        )
+        .fetch_all(&mut *executor)
+        .await?;
+
+        let cluster_id = candidates
+            .into_iter()
+            .find(|candidate| {
+                fingerprint.is_near_duplicate(&JobFingerprint {
+                    title: candidate.dedup_title.clone().unwrap_or_default(),
+                    locations: candidate
+                        .dedup_locations
+                        .clone()
+                        .unwrap_or_default(),
+                    simhash: candidate.simhash.unwrap_or_default() as u64,
+                })
+            })
+            .map(|candidate| candidate.cluster_id);
+
+        let cluster_id = match cluster_id {
+            Some(cluster_id) => cluster_id,
+            None => {
+                sqlx::query!(
+                    r#"--sql
+                INSERT INTO job_cluster (canonical_job_id)
+                VALUES ($1)
+                RETURNING id
+                "#,
+                    job_id,
+                )
+                .fetch_one(&mut *executor)
+                .await?
+                .id
+            }
+        };
+
+        sqlx::query!(
+            r#"--sql
+        UPDATE job SET cluster_id = $1
+        WHERE id = $2
+        "#,
+            cluster_id,
+            job_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        sqlx::query!(
+            r#"--sql
+        UPDATE job_cluster SET canonical_job_id = (
+            SELECT job.id FROM job
+            WHERE job.cluster_id = $1
+            ORDER BY job.created_at DESC NULLS LAST, job.id DESC
+            LIMIT 1
+        )
+        WHERE id = $1
+        "#,
+            cluster_id,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(cluster_id)
+    }
+
+    async fn insert_job_tags_with_executor<'a>(
+        job_tags: &'a [JobTag],
+        executor: &'a mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        let job_tag_names = job_tags
+            .iter()
+            .map(|job| job.name.to_owned())
+            .collect::<Vec<String>>();
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO job_tag (tag)
+        SELECT  UNNEST($1::varchar(255)[])
+        ON CONFLICT (tag)
+        DO NOTHING
+        "#,
+            &job_tag_names
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_job_tag_relations_with_executor<'a>(
+        job_tags: &'a [JobTag],
+        job_id: i64,
+        executor: &'a mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        // the tags are matched to their ids by name, with the evidence
+        // of each tag passed along as one json array.
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO tags_for_job (job_id,job_tag_id,match_count,in_title,weight,hits)
+        SELECT $1, job_tag.id, tag.match_count, tag.in_title, tag.weight, tag.hits
+        FROM jsonb_to_recordset($2::jsonb) AS tag(
+            name varchar(255),
+            match_count integer,
+            in_title boolean,
+            weight double precision,
+            hits jsonb
+        )
+        INNER JOIN job_tag ON job_tag.tag = tag.name
+        ON CONFLICT (job_id,job_tag_id)
+        DO UPDATE SET
+            match_count = EXCLUDED.match_count,
+            in_title = EXCLUDED.in_title,
+            weight = EXCLUDED.weight,
+            hits = EXCLUDED.hits
+        "#,
+            job_id,
+            Json(job_tags) as _,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_job_locations<'a>(
+        locations: &'a [Location],
+        executor: &'a mut PgConnection,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let (job_location_addresses, job_location_geo): (Vec<_>, Vec<_>) =
+            locations
+                .iter()
+                .map(|loc| (loc.address.to_owned(), loc.geo_location))
+                .unzip();
+
+        let (x, y): (Vec<_>, Vec<_>) = job_location_geo
+            .into_iter()
+            .map(|loc| (loc.0, loc.1))
+            .collect();
+
+        let locations = sqlx::query!(
+        r#"--sql
+        INSERT INTO job_location (address,x,y)
+        SELECT * FROM UNNEST($1::varchar(255)[], $2::double precision[], $3::double precision[])            
+        ON CONFLICT (address,x,y)
+        DO UPDATE SET address = EXCLUDED.address
+        RETURNING id
+        "#,
+        &job_location_addresses,
+        &x,
+        &y
+    )
+    .fetch_all(&mut* executor)
+    .await?;
+
+        let location_ids = locations
+            .into_iter()
+            .map(|loc| loc.id)
+            .collect::<Vec<i64>>();
+
+        Ok(location_ids)
+    }
+
+    async fn insert_job_location_relations_with_executor<'a>(
+        location_ids: Vec<i64>,
+        job_id: i64,
+        executor: &'a mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO location_for_job (job_id,location_id)
+        SELECT $1, UNNEST($2::bigint[])         
+        ON CONFLICT (job_id,location_id)
+        DO NOTHING
+        "#,
+            job_id,
+            &location_ids,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::{job, user};
+
+    fn filter(query: &str, viewer: Option<i64>) -> JobFilter {
+        let mut filter: JobFilter = serde_html_form::from_str(query).unwrap();
+        filter.viewer = viewer;
+        filter
+    }
+
+    async fn listed(database: &DataBase, filter: &JobFilter) -> Vec<i64> {
+        let page = database.filter_jobs(filter).await.unwrap();
+        let mut ids: Vec<i64> = page.jobs.iter().map(|job| job.id).collect();
+        ids.sort();
+        ids
+    }
+
+    async fn company_id(database: &DataBase, job_id: i64) -> i64 {
+        sqlx::query_scalar("SELECT company_id FROM job WHERE id = $1")
+            .bind(job_id)
+            .fetch_one(&database.database)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn expired_jobs_are_left_out_unless_asked_for(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let open = database.insert_job(&job("open", "Acme", 10)).await.unwrap();
+        let past = database.insert_job(&job("past", "Acme", -1)).await.unwrap();
+
+        assert_eq!(database.expire_jobs().await.unwrap(), 1);
+        assert_eq!(database.expire_jobs().await.unwrap(), 0);
+
+        assert_eq!(listed(&database, &filter("", None)).await, [open]);
+        assert_eq!(
+            listed(&database, &filter("expired=true", None)).await,
+            [open, past]
+        );
+
+        let search = |expired| {
+            database.search_jobs(
+                "rust",
+                None,
+                HiddenMode::Exclude,
+                expired,
+                &SizeOptions::All,
+            )
+        };
+        assert_eq!(search(false).await.unwrap().total, 1);
+        assert_eq!(search(true).await.unwrap().total, 2);
+    }
+
+    #[sqlx::test]
+    async fn hidden_jobs_are_only_left_out_for_their_viewer(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let hidden = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        let shown = database.insert_job(&job("b", "Acme", 10)).await.unwrap();
+        database
+            .update_job_mark(anna, hidden, None, Some(true), None)
+            .await
+            .unwrap();
+
+        assert_eq!(listed(&database, &filter("", Some(anna))).await, [shown]);
+        assert_eq!(
+            listed(&database, &filter("hidden=only", Some(anna))).await,
+            [hidden]
+        );
+        assert_eq!(
+            listed(&database, &filter("hidden=include", Some(anna))).await,
+            [hidden, shown]
+        );
+        assert_eq!(
+            listed(&database, &filter("", Some(bo))).await,
+            [hidden, shown]
+        );
+        assert_eq!(listed(&database, &filter("", None)).await, [hidden, shown]);
+    }
+
+    #[sqlx::test]
+    async fn hidden_companies_are_only_left_out_for_their_viewer(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let acme = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        let other = database.insert_job(&job("b", "Other", 10)).await.unwrap();
+        let company = company_id(&database, acme).await;
+        assert!(database.hide_company(anna, company).await.unwrap());
+
+        assert_eq!(listed(&database, &filter("", Some(anna))).await, [other]);
+        assert_eq!(
+            listed(&database, &filter("", Some(bo))).await,
+            [acme, other]
+        );
+    }
+
+    #[sqlx::test]
+    async fn starred_jobs_are_of_the_viewer(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let starred = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        database.insert_job(&job("b", "Acme", 10)).await.unwrap();
+        database
+            .update_job_mark(anna, starred, Some(true), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            listed(&database, &filter("starred=true", Some(anna))).await,
+            [starred]
+        );
+        assert!(
+            listed(&database, &filter("starred=true", Some(bo)))
+                .await
+                .is_empty()
+        );
+    }
+}
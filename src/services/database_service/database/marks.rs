@@ -0,0 +1,210 @@
+use super::*;
+use crate::services::database_service::types::{HiddenCompany, JobMark};
+
+impl DataBase {
+    /// The marks of `user_id` on the jobs with the ids `job_ids`, of the
+    /// jobs that are marked.
+    pub async fn get_job_marks(
+        &self,
+        user_id: i64,
+        job_ids: &[i64],
+    ) -> Result<Vec<JobMark>, sqlx::Error> {
+        sqlx::query_as!(
+            JobMark,
+            r#"--sql
+        SELECT job_id, starred, hidden, note, updated_at AS "updated_at?"
+        FROM job_mark
+        WHERE user_id = $1 AND job_id = ANY($2)
+        "#,
+            user_id,
+            job_ids,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+
+    pub async fn get_hidden_companies(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<HiddenCompany>, sqlx::Error> {
+        sqlx::query_as!(
+            HiddenCompany,
+            r#"--sql
+        SELECT hidden_company.company_id, company.name, hidden_company.hidden_at
+        FROM hidden_company
+        INNER JOIN company ON company.id = hidden_company.company_id
+        WHERE hidden_company.user_id = $1
+        ORDER BY company.name, hidden_company.company_id
+        "#,
+            user_id,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+}
+
+impl DataBase {
+    /// Sets what is given of the mark of `user_id` on the job, leaving the
+    /// rest. An empty note removes the note, and a mark that marks
+    /// nothing is removed. `None` when there is no such job.
+    pub async fn update_job_mark(
+        &self,
+        user_id: i64,
+        job_id: i64,
+        starred: Option<bool>,
+        hidden: Option<bool>,
+        note: Option<&str>,
+    ) -> Result<Option<JobMark>, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+        let mark = sqlx::query_as!(
+            JobMark,
+            r#"--sql
+        INSERT INTO job_mark (user_id, job_id, starred, hidden, note)
+        SELECT $1, job.id, coalesce($3, false), coalesce($4, false), nullif($5, '')
+        FROM job
+        WHERE job.id = $2
+        ON CONFLICT (user_id, job_id) DO UPDATE
+        SET starred = coalesce($3, job_mark.starred),
+            hidden = coalesce($4, job_mark.hidden),
+            note = CASE WHEN $5::text IS NULL THEN job_mark.note
+                ELSE nullif($5, '') END,
+            updated_at = now()
+        RETURNING job_id, starred, hidden, note, updated_at AS "updated_at?"
+        "#,
+            user_id,
+            job_id,
+            starred,
+            hidden,
+            note,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let mark = match mark {
+            Some(mark)
+                if !mark.starred && !mark.hidden && mark.note.is_none() =>
+            {
+                sqlx::query!(
+                    r#"--sql
+        DELETE FROM job_mark
+        WHERE user_id = $1 AND job_id = $2
+        "#,
+                    user_id,
+                    job_id,
+                )
+                .execute(&mut *tx)
+                .await?;
+                Some(JobMark {
+                    updated_at: None,
+                    ..mark
+                })
+            }
+            mark => mark,
+        };
+        tx.commit().await?;
+        Ok(mark)
+    }
+
+    /// Hides every job of the company from `user_id`. `false` when there
+    /// is no such company.
+    pub async fn hide_company(
+        &self,
+        user_id: i64,
+        company_id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let hidden = sqlx::query!(
+            r#"--sql
+        INSERT INTO hidden_company (user_id, company_id)
+        SELECT $1, company.id
+        FROM company
+        WHERE company.id = $2
+        ON CONFLICT (user_id, company_id) DO NOTHING
+        "#,
+            user_id,
+            company_id,
+        )
+        .execute(&self.database)
+        .await?;
+        if hidden.rows_affected() > 0 {
+            return Ok(true);
+        }
+        // hidden already, or no such company
+        sqlx::query_scalar!(
+            r#"--sql
+        SELECT EXISTS (SELECT 1 FROM company WHERE id = $1) AS "exists!"
+        "#,
+            company_id,
+        )
+        .fetch_one(&self.database)
+        .await
+    }
+
+    /// Whether `user_id` had hidden the company.
+    pub async fn unhide_company(
+        &self,
+        user_id: i64,
+        company_id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM hidden_company
+        WHERE user_id = $1 AND company_id = $2
+        "#,
+            user_id,
+            company_id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::{job, user};
+
+    #[sqlx::test]
+    async fn marks_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let job_id = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        database
+            .update_job_mark(anna, job_id, Some(true), None, Some("apply"))
+            .await
+            .unwrap();
+
+        assert!(
+            database
+                .get_job_marks(bo, &[job_id])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let marks = database.get_job_marks(anna, &[job_id]).await.unwrap();
+        assert!(marks[0].starred);
+        assert_eq!(marks[0].note.as_deref(), Some("apply"));
+    }
+
+    #[sqlx::test]
+    async fn hidden_companies_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let job_id = database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        let company_id: i64 =
+            sqlx::query_scalar("SELECT company_id FROM job WHERE id = $1")
+                .bind(job_id)
+                .fetch_one(&database.database)
+                .await
+                .unwrap();
+        assert!(database.hide_company(anna, company_id).await.unwrap());
+
+        assert!(database.get_hidden_companies(bo).await.unwrap().is_empty());
+        assert!(!database.unhide_company(bo, company_id).await.unwrap());
+        assert_eq!(database.get_hidden_companies(anna).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+mod applications;
+mod companies;
+mod geo;
+mod jobs;
+mod marks;
+mod query;
+mod searches;
+mod users;
+mod webhooks;
+
+use crate::job_fetchers::company::company_key;
+use crate::job_fetchers::dedup::JobFingerprint;
+use crate::services::database_service::DbDelete;
+use crate::services::database_service::types::{
+    Company, CompanyInfo, ContactInfo, Job, JobEvent, JobTag, JobUrl, Location,
+    Salary,
+};
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder, Transaction, query_builder::Separated};
+
+#[derive(Debug, Clone)]
+pub struct DataBase {
+    database: sqlx::PgPool,
+}
+
+impl DataBase {
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        Ok(DataBase {
+            database: sqlx::PgPool::connect(database_url).await?,
+        })
+    }
+    /// Applies the migrations in `migrations` the database has not had yet.
+    pub async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        sqlx::migrate!().run(&self.database).await
+    }
+    pub async fn get_newest_job(&self) -> Result<Job, sqlx::Error> {
+        todo!()
+    }
+
+    pub async fn get_jobs<T>(
+        &self,
+        job_url: JobUrl,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+
+        let job = sqlx::query!(
+            "SELECT job.id, job.title, job.description, job.job_url, job.company_id,
+            company.name, company.logo_url
+            FROM job INNER JOIN company ON 
+            job.company_id = company.id AND job.job_url= $1",
+            job_url.0
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        todo!()
+    }
+    /// The ids of the jobs that were deleted, which webhooks are sent a
+    /// `JobEvent::Deleted` for. Jobs someone has an application for are
+    /// kept, as the application would be lost with them.
+    pub async fn delete_jobs(
+        &self,
+        jobs: &[JobUrl],
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+        let job_urls: Vec<String> =
+            jobs.iter().map(|job| job.0.to_owned()).collect();
+        let job_ids = sqlx::query_scalar!(
+            r#"--sql
+            SELECT id FROM job
+            WHERE job_url = ANY($1)
+            AND NOT EXISTS (
+                SELECT 1 FROM job_application
+                WHERE job_application.job_id = job.id
+            )
+            "#,
+            &job_urls,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        // the event carries the job, so it is queued before the job is gone
+        Self::enqueue_stored_job_events(
+            &mut tx,
+            JobEvent::Deleted,
+            None,
+            &job_ids,
+        )
+        .await?;
+        let deleted = Job::delete(&mut *tx, jobs).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    pub async fn insert_jobs<'a, T>(
+        &self,
+        jobs: &[Job],
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let mut job_ids: Vec<i64> = Vec::with_capacity(jobs.len());
+        // these are assumed to be in the correct order. Ie if any one fails that must mean the next ones will also fail.
+
+        for job in jobs {
+            if let Ok(id) = self.insert_job(job).await {
+                job_ids.push(id);
+            } else {
+                break;
+            }
+        }
+        Ok(job_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::services::database_service::types::{
+        Description, JobClassification, JobInfo, Title, User,
+    };
+
+    /// A job at `company` in Aarhus, whose deadline is `deadline_days` from
+    /// now, in the past if negative. The description is made of `url`, so
+    /// no two jobs are taken for reposts of each other.
+    pub(super) fn job(url: &str, company: &str, deadline_days: i64) -> Job {
+        Job {
+            job_info: JobInfo {
+                job_url: JobUrl(format!("https://example.com/jobs/{url}")),
+                title: Title(format!("Rust developer {url}")),
+                description: Description([url; 8].join(" ")),
+                job_tags: Vec::new(),
+                classification: JobClassification::default(),
+                salary: None,
+            },
+            created_at: Utc::now(),
+            last_date: Some(Utc::now() + Duration::days(deadline_days)),
+            company_info: CompanyInfo {
+                name: company.to_owned(),
+                logo_url: String::new(),
+                profile_url: None,
+                rating: None,
+            },
+            locations: vec![Location {
+                address: "Aarhus".to_owned(),
+                geo_location: (56.15, 10.2),
+            }],
+            geoarea_ids: Vec::new(),
+            contact_info: None,
+        }
+    }
+
+    pub(super) async fn user(database: &DataBase, username: &str) -> User {
+        database.insert_user(username, "hash").await.unwrap()
+    }
+}
@@ -0,0 +1,563 @@
+use super::*;
+use crate::services::database_service::types::{
+    DateTime, EmploymentType, JobSummary, Language, Seniority, Utc,
+};
+use crate::util::geo::GeoArea;
+use crate::util::options::{JobFilter, JobSort, TagMode};
+
+/// The columns of a `JobSummaryRow`, from `job` joined with `company`.
+pub(super) const JOB_SUMMARY_COLUMNS: &str = r#"--sql
+        SELECT job.id, job.title, job.job_url, company.name AS company,
+            job.seniority, job.employment_type, job.language,
+            job.salary_monthly_min, job.salary_monthly_max, job.remote,
+            ARRAY(
+                SELECT job_tag.tag FROM tags_for_job
+                INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+                WHERE tags_for_job.job_id = job.id
+                ORDER BY tags_for_job.weight DESC, job_tag.tag
+            ) AS tags,
+            ARRAY(
+                SELECT job_location.address FROM location_for_job
+                INNER JOIN job_location ON job_location.id = location_for_job.location_id
+                WHERE location_for_job.job_id = job.id
+                ORDER BY job_location.address
+            ) AS locations,
+            ARRAY(
+                SELECT duplicate.job_url FROM job AS duplicate
+                WHERE duplicate.cluster_id = job.cluster_id
+                AND duplicate.id <> job.id AND duplicate.job_url IS NOT NULL
+                ORDER BY duplicate.created_at DESC NULLS LAST
+            ) AS duplicates,
+            job.created_at, job.last_date"#;
+pub(super) const JOB_SUMMARY_FROM: &str = "
+        FROM job
+        LEFT JOIN company ON company.id = job.company_id";
+
+#[derive(sqlx::FromRow)]
+pub(super) struct JobSummaryRow {
+    id: i64,
+    title: Option<String>,
+    job_url: Option<String>,
+    company: Option<String>,
+    seniority: Option<String>,
+    employment_type: Option<String>,
+    language: Option<String>,
+    salary_monthly_min: Option<i32>,
+    salary_monthly_max: Option<i32>,
+    remote: bool,
+    tags: Vec<String>,
+    locations: Vec<String>,
+    duplicates: Vec<String>,
+    created_at: Option<DateTime<Utc>>,
+    last_date: Option<DateTime<Utc>>,
+}
+
+impl From<JobSummaryRow> for JobSummary {
+    fn from(row: JobSummaryRow) -> Self {
+        JobSummary {
+            id: row.id,
+            title: row.title,
+            job_url: row.job_url,
+            company: row.company,
+            seniority: row.seniority.as_deref().and_then(Seniority::from_name),
+            employment_type: row
+                .employment_type
+                .as_deref()
+                .and_then(EmploymentType::from_name),
+            language: row.language.as_deref().and_then(Language::from_name),
+            salary_monthly_min: row.salary_monthly_min,
+            salary_monthly_max: row.salary_monthly_max,
+            remote: row.remote,
+            tags: row.tags,
+            locations: row.locations,
+            duplicates: row.duplicates,
+            created_at: row.created_at,
+            last_date: row.last_date,
+            mark: None,
+        }
+    }
+}
+
+/// The summaries of the jobs with the ids `job_ids`, newest first.
+pub(super) async fn job_summaries<
+    'e,
+    E: sqlx::Executor<'e, Database = Postgres>,
+>(
+    executor: E,
+    job_ids: Vec<i64>,
+) -> Result<Vec<JobSummary>, sqlx::Error> {
+    let mut query = QueryBuilder::new(JOB_SUMMARY_COLUMNS);
+    query
+        .push(JOB_SUMMARY_FROM)
+        .push(" WHERE job.id = ANY(")
+        .push_bind(job_ids)
+        .push(")")
+        .push(JobSort::Newest.order_by());
+    let jobs: Vec<JobSummaryRow> =
+        query.build_query_as().fetch_all(executor).await?;
+    Ok(jobs.into_iter().map(JobSummary::from).collect())
+}
+
+/// Escapes the wildcards of `ILIKE` in `text` and matches it anywhere.
+fn contains_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Adds the `WHERE` clause of `filter` to a query over `job` joined with
+/// `company`.
+pub(super) fn push_job_filter(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    filter: &JobFilter,
+) {
+    builder.push(" WHERE ");
+    let mut conditions: Separated<'_, '_, Postgres, &str> =
+        builder.separated(" AND ");
+    // the other postings of a cluster are listed with its canonical job
+    conditions.push(
+        "NOT EXISTS (SELECT 1 FROM job_cluster
+        WHERE job_cluster.id = job.cluster_id
+        AND job_cluster.canonical_job_id <> job.id)",
+    );
+    if !filter.expired {
+        conditions.push("job.expired_at IS NULL");
+    }
+
+    if let Some(job_name) = &filter.job_name {
+        conditions
+            .push("job.title ILIKE ")
+            .push_bind_unseparated(contains_pattern(job_name));
+    }
+    if !filter.job_regions.is_empty() {
+        let patterns: Vec<String> = filter
+            .job_regions
+            .iter()
+            .map(|region| contains_pattern(region))
+            .collect();
+        conditions
+            .push(
+                "EXISTS (SELECT 1 FROM location_for_job
+                INNER JOIN job_location ON job_location.id = location_for_job.location_id
+                WHERE location_for_job.job_id = job.id
+                AND job_location.address ILIKE ANY(",
+            )
+            .push_bind_unseparated(patterns)
+            .push_unseparated("))");
+    }
+    if !filter.regions.is_empty() {
+        // the jobs list the regions above their own, but the catalogue
+        // may know sub-regions the jobs do not list the parents of
+        conditions
+            .push(
+                "EXISTS (SELECT 1 FROM geoarea_for_job
+                WHERE geoarea_for_job.job_id = job.id
+                AND geoarea_for_job.geoarea_id IN (
+                    WITH RECURSIVE sub_region (id) AS (
+                        SELECT UNNEST(",
+            )
+            .push_bind_unseparated(filter.regions.clone())
+            .push_unseparated(
+                "::bigint[])
+                        UNION
+                        SELECT geoarea.id FROM geoarea
+                        INNER JOIN sub_region ON geoarea.parent_id = sub_region.id
+                    )
+                    SELECT id FROM sub_region
+                ))",
+            );
+    }
+    if !filter.job_tags.is_empty() {
+        let mut tags: Vec<String> = filter
+            .job_tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        let tag_count = tags.len() as i64;
+
+        conditions
+            .push(
+                "(SELECT count(DISTINCT lower(job_tag.tag)) FROM tags_for_job
+                INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+                WHERE tags_for_job.job_id = job.id
+                AND lower(job_tag.tag) = ANY(",
+            )
+            .push_bind_unseparated(tags)
+            .push_unseparated(")) ");
+        match filter.tag_mode {
+            TagMode::All => conditions
+                .push_unseparated("= ")
+                .push_bind_unseparated(tag_count),
+            TagMode::Any => conditions.push_unseparated("> 0"),
+        };
+    }
+    if let Some(company) = &filter.company {
+        conditions
+            .push("company.normalised_name = ")
+            .push_bind_unseparated(company_key(company));
+    }
+    if let Some(posted_after) = filter.posted_after {
+        conditions
+            .push("job.created_at >= ")
+            .push_bind_unseparated(posted_after);
+    }
+    if let Some(deadline_before) = filter.deadline_before {
+        conditions
+            .push("job.last_date < ")
+            .push_bind_unseparated(deadline_before);
+    }
+    if let Some(remote) = filter.remote {
+        conditions
+            .push("job.remote = ")
+            .push_bind_unseparated(remote);
+    }
+    if let Some(viewer) = filter.viewer {
+        if filter.starred {
+            conditions
+                .push(
+                    "EXISTS (SELECT 1 FROM job_mark
+                    WHERE job_mark.job_id = job.id AND job_mark.starred
+                    AND job_mark.user_id = ",
+                )
+                .push_bind_unseparated(viewer)
+                .push_unseparated(")");
+        }
+        if let Some(hidden) = filter.hidden.wanted() {
+            conditions
+                .push("job_is_hidden(")
+                .push_bind_unseparated(viewer)
+                .push_unseparated(", job.id, job.company_id) = ")
+                .push_bind_unseparated(hidden);
+        }
+    }
+
+    let options = &filter.filter_options;
+    if !options.seniority.is_empty() {
+        conditions
+            .push("job.seniority = ANY(")
+            .push_bind_unseparated(
+                options
+                    .seniority
+                    .iter()
+                    .map(|seniority| seniority.as_str())
+                    .collect::<Vec<_>>(),
+            );
+        conditions.push_unseparated(")");
+    }
+    if !options.employment_types.is_empty() {
+        conditions
+            .push("job.employment_type = ANY(")
+            .push_bind_unseparated(
+                options
+                    .employment_types
+                    .iter()
+                    .map(|employment_type| employment_type.as_str())
+                    .collect::<Vec<_>>(),
+            );
+        conditions.push_unseparated(")");
+    }
+    if !options.languages.is_empty() {
+        conditions
+            .push("job.language = ANY(")
+            .push_bind_unseparated(
+                options
+                    .languages
+                    .iter()
+                    .map(|language| language.as_str())
+                    .collect::<Vec<_>>(),
+            );
+        conditions.push_unseparated(")");
+    }
+    // the salary ranges overlap, like in `FilterOptions::matches`
+    if let Some(salary_min) = options.salary_min {
+        conditions
+            .push("job.salary_monthly_max >= ")
+            .push_bind_unseparated(salary_min);
+    }
+    if let Some(salary_max) = options.salary_max {
+        conditions
+            .push("job.salary_monthly_min <= ")
+            .push_bind_unseparated(salary_max);
+    }
+}
+
+/// Adds the `ORDER BY` of the sort of `filter` to a query filtered by
+/// `push_job_filter`.
+pub(super) fn push_job_order(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    filter: &JobFilter,
+) {
+    if filter.sort != JobSort::TagStrength {
+        builder.push(filter.sort.order_by());
+        return;
+    }
+    let tags: Vec<String> = filter
+        .job_tags
+        .iter()
+        .map(|tag| tag.to_lowercase())
+        .collect();
+    builder
+        .push(
+            " ORDER BY (SELECT COALESCE(sum(tags_for_job.weight), 0)
+            FROM tags_for_job
+            INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+            WHERE tags_for_job.job_id = job.id
+            AND lower(job_tag.tag) = ANY(",
+        )
+        .push_bind(tags)
+        .push(")) DESC, job.created_at DESC NULLS LAST, job.id DESC");
+}
+
+/// Adds the `nearest` common table expression, the location of each job
+/// in `area` that is nearest to its center, with its distance in meters.
+/// The expired jobs are left out unless `expired`.
+///
+/// Both areas are looked up through the cube of `earth_box` around the
+/// center, so the gist index on the locations is used, and then narrowed
+/// down to the radius or the latitudes and longitudes of the box.
+pub(super) fn push_nearest(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    area: &GeoArea,
+    expired: bool,
+) {
+    let center = area.center();
+    let push_center = |builder: &mut QueryBuilder<'_, Postgres>| {
+        builder
+            .push("ll_to_earth(")
+            .push_bind(center.lat)
+            .push(", ")
+            .push_bind(center.lon)
+            .push(")");
+    };
+
+    builder.push(
+        r#"--sql
+        WITH nearest AS (
+            SELECT DISTINCT ON (location_for_job.job_id)
+                location_for_job.job_id, job_location.address,
+                job_location.x AS lat, job_location.y AS lon,
+                earth_distance("#,
+    );
+    push_center(builder);
+    builder.push(
+        ", ll_to_earth(job_location.x, job_location.y)) AS distance
+            FROM job_location
+            INNER JOIN location_for_job ON location_for_job.location_id = job_location.id
+            WHERE ",
+    );
+    match area {
+        GeoArea::Radius { radius_km, .. } => {
+            let radius = radius_km * 1000.0;
+            builder.push("earth_box(");
+            push_center(builder);
+            builder
+                .push(", ")
+                .push_bind(radius)
+                .push(") @> ll_to_earth(job_location.x, job_location.y) AND earth_distance(");
+            push_center(builder);
+            builder
+                .push(", ll_to_earth(job_location.x, job_location.y)) <= ")
+                .push_bind(radius);
+        }
+        GeoArea::Box(bounding_box) => {
+            builder.push("earth_box(");
+            push_center(builder);
+            builder
+                .push(", ")
+                .push_bind(bounding_box.radius_km() * 1000.0)
+                .push(") @> ll_to_earth(job_location.x, job_location.y) AND job_location.x BETWEEN ")
+                .push_bind(bounding_box.south_west.lat)
+                .push(" AND ")
+                .push_bind(bounding_box.north_east.lat)
+                .push(" AND job_location.y BETWEEN ")
+                .push_bind(bounding_box.south_west.lon)
+                .push(" AND ")
+                .push_bind(bounding_box.north_east.lon);
+        }
+    }
+    if !expired {
+        builder.push(
+            " AND NOT EXISTS (SELECT 1 FROM job
+            WHERE job.id = location_for_job.job_id
+            AND job.expired_at IS NOT NULL)",
+        );
+    }
+    builder.push(
+        "
+            ORDER BY location_for_job.job_id, distance
+        )",
+    );
+}
+
+impl JobSort {
+    fn order_by(&self) -> &'static str {
+        match self {
+            JobSort::Newest => {
+                " ORDER BY job.created_at DESC NULLS LAST, job.id DESC"
+            }
+            JobSort::Oldest => {
+                " ORDER BY job.created_at ASC NULLS LAST, job.id"
+            }
+            JobSort::Deadline => {
+                " ORDER BY job.last_date ASC NULLS LAST, job.id DESC"
+            }
+            JobSort::Salary => {
+                " ORDER BY job.salary_monthly_max DESC NULLS LAST, job.id DESC"
+            }
+            // ranked outside SQL, see `match_service::rank_jobs` and
+            // `commute_service::commute_jobs`, or by the tags of the
+            // filter, see `push_job_order`
+            JobSort::Match | JobSort::Commute | JobSort::TagStrength => {
+                " ORDER BY job.created_at DESC NULLS LAST, job.id DESC"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::geo::{BoundingBox, GeoPoint};
+
+    /// The `WHERE` clause of the filter of the query parameters `query`,
+    /// and how many values it binds.
+    fn where_clause(query: &str, viewer: Option<i64>) -> (String, usize) {
+        let mut filter: JobFilter = serde_html_form::from_str(query).unwrap();
+        filter.viewer = viewer;
+        let mut builder = QueryBuilder::new("SELECT job.id FROM job");
+        push_job_filter(&mut builder, &filter);
+        let sql = builder.sql().to_owned();
+        let binds = sql.matches('$').count();
+        (sql, binds)
+    }
+
+    #[test]
+    fn an_empty_filter_lists_the_canonical_jobs() {
+        let (sql, binds) = where_clause("", None);
+        assert!(sql.starts_with("SELECT job.id FROM job WHERE NOT EXISTS"));
+        assert!(sql.ends_with(
+            "job_cluster.canonical_job_id <> job.id) \
+             AND job.expired_at IS NULL"
+        ));
+        assert_eq!(binds, 0);
+
+        let (sql, _) = where_clause("expired=true", None);
+        assert!(sql.ends_with("job_cluster.canonical_job_id <> job.id)"));
+    }
+
+    #[test]
+    fn binds_every_value_in_order() {
+        let (sql, binds) = where_clause(
+            "job_name=rust&job_tags=Rust&job_tags=SQL&company=Acme\
+            &remote=true&language=en&salary_min=40000",
+            None,
+        );
+        assert_eq!(binds, 7);
+        let order = [
+            "job.title ILIKE $1",
+            "= ANY($2)) = $3",
+            "company.normalised_name = $4",
+            "job.remote = $5",
+            "job.language = ANY($6)",
+            "job.salary_monthly_max >= $7",
+        ];
+        let positions: Vec<usize> = order
+            .iter()
+            .map(|part| sql.find(part).unwrap_or_else(|| panic!("{part}")))
+            .collect();
+        assert!(positions.is_sorted());
+    }
+
+    #[test]
+    fn any_tag_only_needs_one_of_them() {
+        let (sql, binds) =
+            where_clause("job_tags=Rust&job_tags=SQL&tag_mode=any", None);
+        assert!(sql.ends_with("= ANY($1)) > 0"));
+        assert_eq!(binds, 1);
+    }
+
+    #[test]
+    fn marks_are_of_the_viewer() {
+        let (sql, _) = where_clause("starred=true", None);
+        assert!(!sql.contains("job_mark"));
+        assert!(!sql.contains("job_is_hidden"));
+
+        let (sql, binds) = where_clause("starred=true", Some(1));
+        assert!(sql.contains("job_mark.user_id = $1"));
+        assert!(sql.contains("job_is_hidden($2, job.id, job.company_id) = $3"));
+        assert_eq!(binds, 3);
+
+        let (sql, binds) = where_clause("hidden=include", Some(1));
+        assert!(!sql.contains("job_is_hidden"));
+        assert_eq!(binds, 0);
+    }
+
+    #[test]
+    fn tag_strength_sums_the_weight_of_the_filter_tags() {
+        let mut filter: JobFilter =
+            serde_html_form::from_str("job_tags=Rust&sort=tag_strength")
+                .unwrap();
+        filter.expired = true;
+        let mut builder = QueryBuilder::new("SELECT job.id FROM job");
+        push_job_filter(&mut builder, &filter);
+        push_job_order(&mut builder, &filter);
+        let sql = builder.sql();
+        assert!(sql.contains("sum(tags_for_job.weight)"));
+        assert!(sql.contains("lower(job_tag.tag) = ANY($3))"));
+        assert!(
+            sql.ends_with(
+                ") DESC, job.created_at DESC NULLS LAST, job.id DESC"
+            )
+        );
+    }
+
+    fn nearest(area: GeoArea) -> String {
+        let mut builder = QueryBuilder::new("");
+        push_nearest(&mut builder, &area, true);
+        builder.sql().to_owned()
+    }
+
+    #[test]
+    fn nearest_leaves_the_expired_jobs_out() {
+        let area = GeoArea::Radius {
+            center: GeoPoint::new(55.68, 12.57).unwrap(),
+            radius_km: 25.0,
+        };
+        assert!(!nearest(area).contains("expired_at"));
+        let mut builder = QueryBuilder::new("");
+        push_nearest(&mut builder, &area, false);
+        assert!(builder.sql().contains("job.expired_at IS NOT NULL"));
+    }
+
+    #[test]
+    fn a_radius_is_looked_up_in_an_earth_box() {
+        let sql = nearest(GeoArea::Radius {
+            center: GeoPoint::new(55.68, 12.57).unwrap(),
+            radius_km: 25.0,
+        });
+        assert!(sql.contains(
+            "earth_box(ll_to_earth($3, $4), $5) \
+             @> ll_to_earth(job_location.x, job_location.y)"
+        ));
+        assert!(sql.contains(
+            "earth_distance(ll_to_earth($6, $7), \
+             ll_to_earth(job_location.x, job_location.y)) <= $8"
+        ));
+    }
+
+    #[test]
+    fn a_box_is_looked_up_in_an_earth_box() {
+        let bounding_box = BoundingBox::new(55.4, 9.9, 57.0, 10.4).unwrap();
+        let sql = nearest(GeoArea::Box(bounding_box));
+        assert!(sql.contains(
+            "earth_box(ll_to_earth($3, $4), $5) \
+             @> ll_to_earth(job_location.x, job_location.y) \
+             AND job_location.x BETWEEN $6 AND $7 \
+             AND job_location.y BETWEEN $8 AND $9"
+        ));
+    }
+}
@@ -0,0 +1,420 @@
+use super::query::{JOB_SUMMARY_FROM, job_summaries, push_job_filter};
+use super::*;
+use crate::services::database_service::types::{
+    FeedItem, JobSummary, MatchCandidate, MatchProfile, NotifyTarget,
+    SavedSearch, Seniority,
+};
+use crate::util::geo::GeoPoint;
+use crate::util::options::{FilterOptions, JobFilter, QueryOptions};
+
+impl DataBase {
+    /// The ids of at most `limit` jobs `filter` lets through, the ones
+    /// with the largest share of their tag weight in `skills` first and
+    /// the newest first among equals. Jobs without tags come last.
+    pub async fn match_candidate_ids(
+        &self,
+        filter: &JobFilter,
+        skills: &[String],
+        limit: i64,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let skills: Vec<String> =
+            skills.iter().map(|skill| skill.to_lowercase()).collect();
+        let mut query = QueryBuilder::new("SELECT job.id");
+        query.push(JOB_SUMMARY_FROM);
+        push_job_filter(&mut query, filter);
+        query
+            .push(
+                r#"--sql
+        ORDER BY (
+            SELECT COALESCE(sum(tags_for_job.weight) FILTER (
+                WHERE lower(job_tag.tag) = ANY("#,
+            )
+            .push_bind(skills)
+            .push(
+                r#")
+            ), 0) / NULLIF(sum(tags_for_job.weight), 0)
+            FROM tags_for_job
+            INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+            WHERE tags_for_job.job_id = job.id
+        ) DESC NULLS LAST, job.created_at DESC NULLS LAST, job.id DESC
+        LIMIT "#,
+            )
+            .push_bind(limit);
+        query.build_query_scalar().fetch_all(&self.database).await
+    }
+
+    /// What the jobs with the ids `job_ids` are matched on.
+    pub async fn get_match_candidates(
+        &self,
+        job_ids: &[i64],
+    ) -> Result<Vec<MatchCandidate>, sqlx::Error> {
+        let jobs = sqlx::query!(
+            r#"--sql
+        SELECT job.id, job.seniority, job.remote,
+            COALESCE((
+                SELECT json_agg(json_build_array(job_tag.tag, tags_for_job.weight)
+                    ORDER BY tags_for_job.weight DESC, job_tag.tag)
+                FROM tags_for_job
+                INNER JOIN job_tag ON job_tag.id = tags_for_job.job_tag_id
+                WHERE tags_for_job.job_id = job.id
+            ), '[]') AS "tags!: Json<Vec<(String, f64)>>",
+            COALESCE((
+                SELECT json_agg(json_build_array(job_location.x, job_location.y))
+                FROM location_for_job
+                INNER JOIN job_location ON job_location.id = location_for_job.location_id
+                WHERE location_for_job.job_id = job.id
+                AND job_location.x IS NOT NULL AND job_location.y IS NOT NULL
+            ), '[]') AS "locations!: Json<Vec<(f64, f64)>>"
+        FROM job
+        WHERE job.id = ANY($1)
+        ORDER BY job.id
+        "#,
+            job_ids,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|job| MatchCandidate {
+                id: job.id,
+                tags: job.tags.0,
+                seniority: job
+                    .seniority
+                    .as_deref()
+                    .and_then(Seniority::from_name),
+                remote: job.remote,
+                locations: job
+                    .locations
+                    .0
+                    .into_iter()
+                    .map(|(lat, lon)| GeoPoint { lat, lon })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    pub async fn get_match_profile(
+        &self,
+        user_id: i64,
+    ) -> Result<Option<MatchProfile>, sqlx::Error> {
+        let profile = sqlx::query!(
+            r#"--sql
+        SELECT user_id, skills, seniority, home, updated_at
+        FROM match_profile
+        WHERE user_id = $1
+        "#,
+            user_id,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(profile.map(|profile| MatchProfile {
+            user_id: profile.user_id,
+            skills: profile.skills,
+            seniority: profile
+                .seniority
+                .as_deref()
+                .and_then(Seniority::from_name),
+            home: profile.home,
+            updated_at: profile.updated_at,
+        }))
+    }
+
+    /// Records the jobs `filter` matches that saved search `search_id`
+    /// has not matched before, and returns them, newest first. Where
+    /// `announce`, webhooks are sent a `JobEvent::Matched` for each.
+    pub async fn record_new_matches(
+        &self,
+        search_id: i64,
+        filter: &JobFilter,
+        announce: bool,
+    ) -> Result<Vec<JobSummary>, sqlx::Error> {
+        let mut tx: Transaction<'_, Postgres> = self.database.begin().await?;
+
+        let mut insert = QueryBuilder::new(
+            "INSERT INTO saved_search_match (saved_search_id, job_id) SELECT ",
+        );
+        insert.push_bind(search_id).push(", job.id");
+        insert.push(JOB_SUMMARY_FROM);
+        push_job_filter(&mut insert, filter);
+        insert.push(" ON CONFLICT DO NOTHING RETURNING job_id");
+        let job_ids: Vec<i64> =
+            insert.build_query_scalar().fetch_all(&mut *tx).await?;
+        if announce {
+            Self::enqueue_stored_job_events(
+                &mut tx,
+                JobEvent::Matched,
+                Some(search_id),
+                &job_ids,
+            )
+            .await?;
+        }
+
+        let jobs = job_summaries(&mut *tx, job_ids).await?;
+        tx.commit().await?;
+        Ok(jobs)
+    }
+
+    /// Every saved search, or only the ones of `user_id`.
+    pub async fn get_saved_searches(
+        &self,
+        user_id: Option<i64>,
+    ) -> Result<Vec<SavedSearch>, sqlx::Error> {
+        let searches = sqlx::query!(
+            r#"--sql
+        SELECT id, user_id, name,
+            query_options AS "query_options: Json<QueryOptions>",
+            filter_options AS "filter_options: Json<FilterOptions>",
+            notify AS "notify: Json<Vec<NotifyTarget>>",
+            created_at
+        FROM saved_search
+        WHERE $1::bigint IS NULL OR user_id = $1
+        ORDER BY user_id, name
+        "#,
+            user_id,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(searches
+            .into_iter()
+            .map(|search| SavedSearch {
+                id: search.id,
+                user_id: search.user_id,
+                name: search.name,
+                query_options: search.query_options.0,
+                filter_options: search.filter_options.0,
+                notify: search.notify.0,
+                created_at: search.created_at,
+            })
+            .collect())
+    }
+
+    /// The in-app feed of `user_id`, newest first.
+    pub async fn get_feed(
+        &self,
+        user_id: i64,
+        unread_only: bool,
+    ) -> Result<Vec<FeedItem>, sqlx::Error> {
+        sqlx::query_as!(
+            FeedItem,
+            r#"--sql
+        SELECT notification.id, notification.saved_search_id,
+            saved_search.name AS saved_search, notification.job_id,
+            job.title, job.job_url, notification.created_at,
+            notification.read_at
+        FROM notification
+        INNER JOIN saved_search ON saved_search.id = notification.saved_search_id
+        INNER JOIN job ON job.id = notification.job_id
+        WHERE notification.user_id = $1
+        AND (NOT $2 OR notification.read_at IS NULL)
+        ORDER BY notification.created_at DESC, notification.id DESC
+        "#,
+            user_id,
+            unread_only,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+}
+
+impl DataBase {
+    pub async fn insert_saved_search(
+        &self,
+        user_id: i64,
+        name: &str,
+        query_options: &QueryOptions,
+        filter_options: &FilterOptions,
+        notify: &[NotifyTarget],
+    ) -> Result<SavedSearch, sqlx::Error> {
+        let search = sqlx::query!(
+            r#"--sql
+        INSERT INTO saved_search (user_id, name, query_options, filter_options, notify)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, created_at
+        "#,
+            user_id,
+            name,
+            Json(query_options) as _,
+            Json(filter_options) as _,
+            Json(notify) as _,
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(SavedSearch {
+            id: search.id,
+            user_id,
+            name: name.to_owned(),
+            query_options: query_options.clone(),
+            filter_options: filter_options.clone(),
+            notify: notify.to_vec(),
+            created_at: search.created_at,
+        })
+    }
+
+    /// Whether `user_id` had a saved search `id` to delete.
+    pub async fn delete_saved_search(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM saved_search
+        WHERE user_id = $1 AND id = $2
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Stores the profile of `user_id`, in place of the one it had.
+    pub async fn upsert_match_profile(
+        &self,
+        user_id: i64,
+        skills: &[String],
+        seniority: Option<Seniority>,
+        home: Option<&str>,
+    ) -> Result<MatchProfile, sqlx::Error> {
+        let updated_at = sqlx::query_scalar!(
+            r#"--sql
+        INSERT INTO match_profile (user_id, skills, seniority, home)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE
+        SET skills = EXCLUDED.skills, seniority = EXCLUDED.seniority,
+            home = EXCLUDED.home, updated_at = now()
+        RETURNING updated_at
+        "#,
+            user_id,
+            skills,
+            seniority.map(|seniority| seniority.as_str()),
+            home,
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(MatchProfile {
+            user_id,
+            skills: skills.to_vec(),
+            seniority,
+            home: home.map(str::to_owned),
+            updated_at,
+        })
+    }
+
+    /// Whether `user_id` had a profile to delete.
+    pub async fn delete_match_profile(
+        &self,
+        user_id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM match_profile
+        WHERE user_id = $1
+        "#,
+            user_id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Puts the jobs in the in-app feed of `user_id`.
+    pub async fn insert_feed_items(
+        &self,
+        user_id: i64,
+        search_id: i64,
+        job_ids: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO notification (user_id, saved_search_id, job_id)
+        SELECT $1, $2, UNNEST($3::bigint[])
+        "#,
+            user_id,
+            search_id,
+            job_ids,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether `user_id` had an unread feed item `id` to mark read.
+    pub async fn mark_feed_item_read(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let marked = sqlx::query!(
+            r#"--sql
+        UPDATE notification SET read_at = now()
+        WHERE user_id = $1 AND id = $2 AND read_at IS NULL
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(marked.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::user;
+
+    #[sqlx::test]
+    async fn saved_searches_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let search = database
+            .insert_saved_search(
+                anna,
+                "rust",
+                &QueryOptions::All,
+                &FilterOptions::default(),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        assert!(
+            database
+                .get_saved_searches(Some(bo))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!database.delete_saved_search(bo, search.id).await.unwrap());
+        let searches = database.get_saved_searches(Some(anna)).await.unwrap();
+        assert_eq!(searches.len(), 1);
+        // the notifier goes through the searches of every user
+        assert_eq!(database.get_saved_searches(None).await.unwrap().len(), 1);
+        assert!(database.delete_saved_search(anna, search.id).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn match_profiles_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        database
+            .upsert_match_profile(anna, &["Rust".to_owned()], None, None)
+            .await
+            .unwrap();
+
+        assert!(database.get_match_profile(bo).await.unwrap().is_none());
+        assert!(!database.delete_match_profile(bo).await.unwrap());
+        assert!(database.get_match_profile(anna).await.unwrap().is_some());
+    }
+}
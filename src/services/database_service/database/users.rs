@@ -0,0 +1,170 @@
+use super::*;
+use crate::services::database_service::types::{ApiToken, User};
+
+impl DataBase {
+    pub async fn get_user(&self, id: i64) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as!(
+            User,
+            r#"--sql
+        SELECT id, username, admin, created_at
+        FROM app_user
+        WHERE id = $1
+        "#,
+            id,
+        )
+        .fetch_optional(&self.database)
+        .await
+    }
+
+    /// The user called `username` and the hash its password is checked
+    /// against.
+    pub async fn get_login(
+        &self,
+        username: &str,
+    ) -> Result<Option<(User, String)>, sqlx::Error> {
+        let login = sqlx::query!(
+            r#"--sql
+        SELECT id, username, admin, password_hash, created_at
+        FROM app_user
+        WHERE username = $1
+        "#,
+            username,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(login.map(|login| {
+            let user = User {
+                id: login.id,
+                username: login.username,
+                admin: login.admin,
+                created_at: login.created_at,
+            };
+            (user, login.password_hash)
+        }))
+    }
+
+    pub async fn get_api_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<ApiToken>, sqlx::Error> {
+        sqlx::query_as!(
+            ApiToken,
+            r#"--sql
+        SELECT id, name, created_at, last_used_at
+        FROM api_token
+        WHERE user_id = $1
+        ORDER BY created_at, id
+        "#,
+            user_id,
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+}
+
+impl DataBase {
+    pub async fn insert_user(
+        &self,
+        username: &str,
+        password_hash: &str,
+    ) -> Result<User, sqlx::Error> {
+        sqlx::query_as!(
+            User,
+            r#"--sql
+        INSERT INTO app_user (username, password_hash)
+        VALUES ($1, $2)
+        RETURNING id, username, admin, created_at
+        "#,
+            username,
+            password_hash,
+        )
+        .fetch_one(&self.database)
+        .await
+    }
+
+    /// Makes the user called `username` an admin, or no longer one.
+    /// `false` when there is no such user.
+    pub async fn set_admin(
+        &self,
+        username: &str,
+        admin: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let updated = sqlx::query!(
+            r#"--sql
+        UPDATE app_user SET admin = $2
+        WHERE username = $1
+        "#,
+            username,
+            admin,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    pub async fn insert_api_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        token_hash: &str,
+    ) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as!(
+            ApiToken,
+            r#"--sql
+        INSERT INTO api_token (user_id, name, token_hash)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, created_at, last_used_at
+        "#,
+            user_id,
+            name,
+            token_hash,
+        )
+        .fetch_one(&self.database)
+        .await
+    }
+
+    /// The user of the API token with the hash `token_hash`, noting that
+    /// the token is used.
+    pub async fn use_api_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as!(
+            User,
+            r#"--sql
+        WITH used AS (
+            UPDATE api_token SET last_used_at = now()
+            WHERE token_hash = $1
+            RETURNING user_id
+        )
+        SELECT app_user.id, app_user.username, app_user.admin,
+            app_user.created_at
+        FROM used
+        INNER JOIN app_user ON app_user.id = used.user_id
+        "#,
+            token_hash,
+        )
+        .fetch_optional(&self.database)
+        .await
+    }
+
+    /// Whether the user had the token to delete.
+    pub async fn delete_api_token(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM api_token
+        WHERE user_id = $1 AND id = $2
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
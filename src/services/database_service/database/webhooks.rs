@@ -0,0 +1,432 @@
+use super::*;
+use crate::services::database_service::types::{
+    DateTime, DeliveryStatus, DueDelivery, JobEventPayload, Utc,
+    WebhookDelivery, WebhookSubscription,
+};
+use sqlx::PgConnection;
+
+impl DataBase {
+    /// The webhooks `user_id` registered.
+    pub async fn get_webhooks(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<WebhookSubscription>, sqlx::Error> {
+        let webhooks = sqlx::query!(
+            r#"--sql
+        SELECT id, url, events, active, created_at
+        FROM webhook
+        WHERE user_id = $1
+        ORDER BY id
+        "#,
+            user_id,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(webhooks
+            .into_iter()
+            .map(|webhook| WebhookSubscription {
+                id: webhook.id,
+                url: webhook.url,
+                events: webhook
+                    .events
+                    .iter()
+                    .filter_map(|event| JobEvent::from_name(event))
+                    .collect(),
+                active: webhook.active,
+                created_at: webhook.created_at,
+            })
+            .collect())
+    }
+
+    /// The delivery log of the webhooks of `user_id`, newest first: of
+    /// webhook `webhook_id` or of every one of them, and with status
+    /// `status` or any status.
+    pub async fn get_webhook_deliveries(
+        &self,
+        user_id: i64,
+        webhook_id: Option<i64>,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let deliveries = sqlx::query!(
+            r#"--sql
+        SELECT webhook_delivery.id, webhook_delivery.webhook_id,
+            webhook_delivery.event, webhook_delivery.job_id,
+            webhook_delivery.status, webhook_delivery.attempts,
+            webhook_delivery.next_attempt_at, webhook_delivery.last_status,
+            webhook_delivery.last_error, webhook_delivery.created_at,
+            webhook_delivery.delivered_at
+        FROM webhook_delivery
+        INNER JOIN webhook ON webhook.id = webhook_delivery.webhook_id
+        WHERE webhook.user_id = $1
+        AND ($2::bigint IS NULL OR webhook_delivery.webhook_id = $2)
+        AND ($3::varchar IS NULL OR webhook_delivery.status = $3)
+        ORDER BY webhook_delivery.created_at DESC, webhook_delivery.id DESC
+        LIMIT $4
+        "#,
+            user_id,
+            webhook_id,
+            status.map(|status| status.as_str()),
+            limit,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(deliveries
+            .into_iter()
+            .filter_map(|delivery| {
+                Some(WebhookDelivery {
+                    id: delivery.id,
+                    webhook_id: delivery.webhook_id,
+                    event: JobEvent::from_name(&delivery.event)?,
+                    job_id: delivery.job_id,
+                    status: DeliveryStatus::from_name(&delivery.status)?,
+                    attempts: delivery.attempts,
+                    next_attempt_at: delivery.next_attempt_at,
+                    last_status: delivery.last_status,
+                    last_error: delivery.last_error,
+                    created_at: delivery.created_at,
+                    delivered_at: delivery.delivered_at,
+                })
+            })
+            .collect())
+    }
+}
+
+impl DataBase {
+    /// Registers a webhook of `user_id` that is sent `events`, or every
+    /// event where there are none.
+    pub async fn insert_webhook(
+        &self,
+        user_id: i64,
+        url: &str,
+        secret: &str,
+        events: &[JobEvent],
+    ) -> Result<WebhookSubscription, sqlx::Error> {
+        let event_names: Vec<String> = events
+            .iter()
+            .map(|event| event.as_str().to_owned())
+            .collect();
+        let webhook = sqlx::query!(
+            r#"--sql
+        INSERT INTO webhook (user_id, url, secret, events)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, active, created_at
+        "#,
+            user_id,
+            url,
+            secret,
+            &event_names as &[String],
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(WebhookSubscription {
+            id: webhook.id,
+            url: url.to_owned(),
+            events: events.to_vec(),
+            active: webhook.active,
+            created_at: webhook.created_at,
+        })
+    }
+
+    /// Whether `user_id` had a webhook `id` to delete. Its delivery log
+    /// goes with it.
+    pub async fn delete_webhook(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query!(
+            r#"--sql
+        DELETE FROM webhook
+        WHERE user_id = $1 AND id = $2
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Takes up to `limit` deliveries that are due, oldest first. Each
+    /// is leased for `lease_secs`, so other workers pass it over while
+    /// it is sent, and it is retried should the sender die.
+    pub async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: f64,
+    ) -> Result<Vec<DueDelivery>, sqlx::Error> {
+        let deliveries = sqlx::query!(
+            r#"--sql
+        WITH due AS (
+            SELECT webhook_delivery.id FROM webhook_delivery
+            INNER JOIN webhook ON webhook.id = webhook_delivery.webhook_id
+            WHERE webhook_delivery.status = 'pending'
+            AND webhook_delivery.next_attempt_at <= now()
+            AND webhook.active
+            ORDER BY webhook_delivery.next_attempt_at, webhook_delivery.id
+            LIMIT $1
+            FOR UPDATE OF webhook_delivery SKIP LOCKED
+        )
+        UPDATE webhook_delivery
+        SET next_attempt_at = now() + make_interval(secs => $2)
+        FROM due, webhook
+        WHERE webhook_delivery.id = due.id
+        AND webhook.id = webhook_delivery.webhook_id
+        RETURNING webhook_delivery.id, webhook.url, webhook.secret,
+            webhook_delivery.event, webhook_delivery.payload,
+            webhook_delivery.attempts
+        "#,
+            limit,
+            lease_secs,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(deliveries
+            .into_iter()
+            .filter_map(|delivery| {
+                Some(DueDelivery {
+                    id: delivery.id,
+                    url: delivery.url,
+                    secret: delivery.secret,
+                    event: JobEvent::from_name(&delivery.event)?,
+                    payload: delivery.payload,
+                    attempts: delivery.attempts,
+                })
+            })
+            .collect())
+    }
+
+    /// Records an attempt at delivery `id` that left it `status`. A
+    /// pending delivery is tried again at `next_attempt_at`.
+    pub async fn record_delivery_attempt(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        next_attempt_at: Option<DateTime<Utc>>,
+        last_status: Option<i32>,
+        last_error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"--sql
+        UPDATE webhook_delivery
+        SET status = $2::varchar,
+            attempts = attempts + 1,
+            next_attempt_at = COALESCE($3, next_attempt_at),
+            last_status = $4,
+            last_error = $5,
+            delivered_at = CASE WHEN $2 = 'delivered' THEN now() END
+        WHERE id = $1
+        "#,
+            id,
+            status.as_str(),
+            next_attempt_at,
+            last_status,
+            last_error,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether a webhook of `user_id` had a dead delivery `id` to take
+    /// out of the dead-letter queue. It is sent again right away, with
+    /// all its attempts.
+    pub async fn retry_dead_delivery(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let retried = sqlx::query!(
+            r#"--sql
+        UPDATE webhook_delivery
+        SET status = 'pending', attempts = 0, next_attempt_at = now()
+        FROM webhook
+        WHERE webhook.id = webhook_delivery.webhook_id
+        AND webhook.user_id = $1
+        AND webhook_delivery.id = $2 AND webhook_delivery.status = 'dead'
+        "#,
+            user_id,
+            id,
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(retried.rows_affected() > 0)
+    }
+}
+
+impl DataBase {
+    /// The active webhooks that are sent `event`. The events of saved
+    /// search `saved_search_id` only go to the webhooks of its user.
+    async fn subscribed_webhooks(
+        executor: &mut PgConnection,
+        event: JobEvent,
+        saved_search_id: Option<i64>,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"--sql
+        SELECT id FROM webhook
+        WHERE active AND (cardinality(events) = 0 OR $1 = ANY(events))
+        AND ($2::bigint IS NULL OR user_id = (
+            SELECT user_id FROM saved_search WHERE id = $2
+        ))
+        ORDER BY id
+        "#,
+            event.as_str(),
+            saved_search_id,
+        )
+        .fetch_all(&mut *executor)
+        .await
+    }
+
+    /// Queues `event` about each of `jobs` for every webhook that is
+    /// sent it, in the transaction of the change the event is about, so
+    /// an event is queued exactly when its change is stored.
+    pub(super) async fn enqueue_job_events(
+        executor: &mut PgConnection,
+        event: JobEvent,
+        saved_search_id: Option<i64>,
+        jobs: &[(i64, &Job)],
+    ) -> Result<(), sqlx::Error> {
+        if jobs.is_empty() {
+            return Ok(());
+        }
+        let webhook_ids =
+            Self::subscribed_webhooks(&mut *executor, event, saved_search_id)
+                .await?;
+        if webhook_ids.is_empty() {
+            return Ok(());
+        }
+
+        let occurred_at = Utc::now();
+        let (job_ids, payloads): (Vec<i64>, Vec<String>) = jobs
+            .iter()
+            .map(|&(job_id, job)| {
+                let payload = JobEventPayload {
+                    event,
+                    occurred_at,
+                    job_id,
+                    saved_search_id,
+                    job,
+                };
+                serde_json::to_string(&payload)
+                    .map(|payload| (job_id, payload))
+                    .map_err(|error| sqlx::Error::Encode(Box::new(error)))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        sqlx::query!(
+            r#"--sql
+        INSERT INTO webhook_delivery (webhook_id, event, job_id, payload)
+        SELECT webhook.id, $2, payload.job_id, payload.body
+        FROM UNNEST($1::bigint[]) AS webhook (id)
+        CROSS JOIN UNNEST($3::bigint[], $4::text[]) AS payload (job_id, body)
+        "#,
+            &webhook_ids,
+            event.as_str(),
+            &job_ids,
+            &payloads,
+        )
+        .execute(&mut *executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Like `enqueue_job_events` for stored jobs, which are only loaded
+    /// when a webhook is sent `event`.
+    pub(super) async fn enqueue_stored_job_events(
+        executor: &mut PgConnection,
+        event: JobEvent,
+        saved_search_id: Option<i64>,
+        job_ids: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        if job_ids.is_empty()
+            || Self::subscribed_webhooks(&mut *executor, event, saved_search_id)
+                .await?
+                .is_empty()
+        {
+            return Ok(());
+        }
+        let jobs = Self::load_jobs(&mut *executor, job_ids).await?;
+        let jobs: Vec<(i64, &Job)> =
+            jobs.iter().map(|(job_id, job)| (*job_id, job)).collect();
+        Self::enqueue_job_events(executor, event, saved_search_id, &jobs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::services::database_service::database::tests::{job, user};
+    use crate::util::options::{FilterOptions, JobFilter, QueryOptions};
+
+    async fn events(database: &DataBase, user_id: i64) -> Vec<JobEvent> {
+        database
+            .get_webhook_deliveries(user_id, None, None, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|delivery| delivery.event)
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn webhooks_are_of_their_user(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        let webhook = database
+            .insert_webhook(anna, "https://example.com/hook", "secret", &[])
+            .await
+            .unwrap();
+
+        assert!(database.get_webhooks(bo).await.unwrap().is_empty());
+        assert!(!database.delete_webhook(bo, webhook.id).await.unwrap());
+        assert_eq!(database.get_webhooks(anna).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn matches_only_go_to_the_webhooks_of_the_search_owner(pool: PgPool) {
+        let database = DataBase { database: pool };
+        let anna = user(&database, "anna").await.id;
+        let bo = user(&database, "bo").await.id;
+        for user_id in [anna, bo] {
+            database
+                .insert_webhook(
+                    user_id,
+                    "https://example.com/hook",
+                    "secret",
+                    &[],
+                )
+                .await
+                .unwrap();
+        }
+        database.insert_job(&job("a", "Acme", 10)).await.unwrap();
+        let search = database
+            .insert_saved_search(
+                anna,
+                "everything",
+                &QueryOptions::All,
+                &FilterOptions::default(),
+                &[],
+            )
+            .await
+            .unwrap();
+        let filter: JobFilter = serde_html_form::from_str("").unwrap();
+        database
+            .record_new_matches(search.id, &filter, true)
+            .await
+            .unwrap();
+
+        assert!(events(&database, anna).await.contains(&JobEvent::Matched));
+        assert_eq!(events(&database, bo).await, [JobEvent::Created]);
+    }
+}
@@ -7,25 +7,25 @@ trait DbType {
 }
 pub trait DbGet: Sized {
     type GetType<'a>;
-    async fn get<'a, E: Executor<'a, Database = Postgres>>(
+    fn get<'a, E: Executor<'a, Database = Postgres>>(
         executor: E,
         get: Self::GetType<'a>,
-    ) -> Result<Self, sqlx::Error>;
+    ) -> impl Future<Output = Result<Self, sqlx::Error>> + Send;
 }
 
 pub trait DbInsert: Sized {
     type InsertType<'a>;
 
-    async fn insert<'a, E: Executor<'a, Database = Postgres>>(
+    fn insert<'a, E: Executor<'a, Database = Postgres>>(
         executor: E,
         value: Self::InsertType<'a>,
-    ) -> Result<Self, sqlx::Error>;
+    ) -> impl Future<Output = Result<Self, sqlx::Error>> + Send;
 }
 pub trait DbDelete: Sized {
     type DeleteType<'a>;
     type RetType;
-    async fn delete<'a, E: Executor<'a, Database = Postgres>>(
+    fn delete<'a, E: Executor<'a, Database = Postgres>>(
         executor: E,
         delete: Self::DeleteType<'a>,
-    ) -> Result<Self::RetType, sqlx::Error>;
+    ) -> impl Future<Output = Result<Self::RetType, sqlx::Error>> + Send;
 }
@@ -1,18 +1,335 @@
+use chrono::NaiveDate;
 pub use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::Postgres;
 
 use crate::services::database_service::DbDelete;
+use crate::util::geo::GeoPoint;
+use crate::util::options::{FilterOptions, QueryOptions};
 
+/// The applications of an owner as a Kanban board.
+#[derive(Debug, Serialize)]
 pub struct JobApplications {
+    /// A column per status in the order of the workflow, also when empty.
+    pub columns: Vec<ApplicationColumn>,
+}
+#[derive(Debug, Serialize)]
+pub struct ApplicationColumn {
+    pub status: ApplicationStatus,
+    /// The most recently updated first.
     pub applications: Vec<JobApplication>,
 }
-
+/// An application for a stored job, tracked from the first interest on.
+#[derive(Debug, Serialize)]
 pub struct JobApplication {
-    pub job: Job,
+    pub id: i64,
+    pub user_id: i64,
+    pub job: JobSummary,
+    pub status: ApplicationStatus,
+    pub notes: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When it was sent, also when it was rejected later.
+    pub applied_at: Option<DateTime<Utc>>,
+    /// Oldest first, starting with the status it was created in.
+    pub history: Vec<StatusChange>,
+    pub documents: Vec<ApplicationDocument>,
+    /// The open ones first, soonest first.
+    pub reminders: Vec<Reminder>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationStatus {
+    Interested,
+    Applied,
+    Interview,
+    Offer,
+    Rejected,
+}
+// the names below are what is stored in the job_application table.
+impl ApplicationStatus {
+    /// In the order of the workflow.
+    pub const ALL: [ApplicationStatus; 5] = [
+        ApplicationStatus::Interested,
+        ApplicationStatus::Applied,
+        ApplicationStatus::Interview,
+        ApplicationStatus::Offer,
+        ApplicationStatus::Rejected,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApplicationStatus::Interested => "interested",
+            ApplicationStatus::Applied => "applied",
+            ApplicationStatus::Interview => "interview",
+            ApplicationStatus::Offer => "offer",
+            ApplicationStatus::Rejected => "rejected",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == name)
+    }
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusChange {
+    /// `None` for the status the application was created in.
+    pub from: Option<ApplicationStatus>,
+    pub to: ApplicationStatus,
+    pub changed_at: DateTime<Utc>,
+}
+/// A document attached to an application. The content is fetched on its
+/// own.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApplicationDocument {
+    pub id: i64,
+    pub kind: DocumentKind,
+    pub name: String,
+    pub content_type: String,
+    /// In bytes.
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    Cv,
+    CoverLetter,
+    Other,
+}
+// the names below are what is stored in the job_application_document table.
+impl DocumentKind {
+    pub const ALL: [DocumentKind; 3] = [
+        DocumentKind::Cv,
+        DocumentKind::CoverLetter,
+        DocumentKind::Other,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::Cv => "cv",
+            DocumentKind::CoverLetter => "cover_letter",
+            DocumentKind::Other => "other",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+/// A reminder to follow up on an application.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub application_id: i64,
+    pub remind_at: DateTime<Utc>,
+    pub note: String,
+    pub done_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+/// A CV kept as data rather than as a document, so it can be rendered in
+/// any format and tailored to the job it is sent to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cv {
+    pub name: String,
+    /// A line under the name, e.g. "Backend developer".
+    #[serde(default)]
+    pub headline: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub summary: String,
+    /// Newest first.
+    #[serde(default)]
+    pub experience: Vec<Experience>,
+    /// Strongest first, until tailored to a job.
+    #[serde(default)]
+    pub skills: Vec<Skill>,
+    #[serde(default)]
+    pub education: Vec<Education>,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Experience {
+    pub title: String,
+    pub employer: String,
+    pub location: Option<String>,
+    pub start: NaiveDate,
+    /// `None` while it lasts.
+    pub end: Option<NaiveDate>,
+    #[serde(default)]
+    pub description: String,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Skill {
+    pub name: String,
+    /// e.g. "5 years" or "fluent".
+    pub level: Option<String>,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Education {
+    pub degree: String,
+    pub institution: String,
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+    #[serde(default)]
+    pub description: String,
+}
+/// A cover letter in the words of its owner, with placeholders for what
+/// comes from the job.
+#[derive(Debug, Serialize)]
+pub struct CoverLetterTemplate {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub body: String,
+    pub updated_at: DateTime<Utc>,
+}
+/// What a generated document is written as.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    Html,
+    Pdf,
+}
+/// What a list of jobs is exported as, see `export_service`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    /// JSON Lines, a `JobSummary` on each line.
+    Ndjson,
+    /// An iCalendar feed of the application deadlines.
+    Ics,
+}
+/// What a file of jobs is imported from, see `import_service`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Json,
+    /// JSON Lines, a job on each line.
+    Ndjson,
+    Csv,
+}
+/// What importing a file did, or would do on a dry run.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// The jobs found in the file, valid or not.
+    pub records: usize,
+    /// Valid jobs not stored before.
+    pub new: usize,
+    /// Valid jobs stored before, which are updated.
+    pub existing: usize,
+    pub issues: Vec<ImportIssue>,
+}
+/// Why a job of a file was not imported.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportIssue {
+    /// The line of the job in NDJSON and CSV, its place in the list in JSON,
+    /// counting from 1. 0 is the file as a whole.
+    pub record: usize,
+    pub message: String,
+}
+/// What the jobs are matched against for a member of the team.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchProfile {
+    pub user_id: i64,
+    /// Names of tags, like `JobTag::name`, the strongest first.
+    pub skills: Vec<String>,
+    pub seniority: Option<Seniority>,
+    /// The name of the home location distances are measured from.
+    pub home: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+/// What a job is scored on by `match_service::score`.
+#[derive(Debug)]
+pub struct MatchCandidate {
+    pub id: i64,
+    /// Names and weights, heaviest first.
+    pub tags: Vec<(String, f64)>,
+    pub seniority: Option<Seniority>,
+    pub remote: bool,
+    pub locations: Vec<GeoPoint>,
+}
+/// How well a job fits a `MatchProfile`, and why. Each fit is from 0 to 1,
+/// and `None` where there is nothing to compare.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobMatch {
+    pub job_id: i64,
+    /// The weighted mean of the fits there are, from 0 to 100.
+    pub score: f64,
+    /// The tags of the job in the profile, heaviest first.
+    pub matched_tags: Vec<String>,
+    /// The tags of the job not in the profile, heaviest first.
+    pub missing_tags: Vec<String>,
+    /// The share of the weight of the job tags that is matched.
+    pub tag_fit: Option<f64>,
+    pub seniority: Option<Seniority>,
+    pub seniority_fit: Option<f64>,
+    /// From the home of the profile to the nearest location of the job.
+    pub distance_km: Option<f64>,
+    /// A remote job fits wherever the home is.
+    pub distance_fit: Option<f64>,
+}
+/// An account of a member of the team. The personal data of the user is
+/// stored under `id`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    /// Whether the user runs what is done for everyone, like syncing the
+    /// regions.
+    pub admin: bool,
+    pub created_at: DateTime<Utc>,
+}
+/// A signed in session, which is sent as a bearer token until it expires.
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+/// A token scripts authenticate with in place of a session. The token
+/// itself is only shown when it is created, see `NewApiToken`.
+#[derive(Debug, Serialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+#[derive(Debug, Serialize)]
+pub struct NewApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    /// The bearer token, which cannot be shown again.
+    pub secret: String,
+}
+/// What a user has starred, hidden or noted of a job. Each user only sees
+/// their own.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobMark {
+    pub job_id: i64,
+    pub starred: bool,
+    pub hidden: bool,
+    pub note: Option<String>,
+    /// `None` while the job is not marked at all.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+/// A company a user hides every job of.
+#[derive(Debug, Serialize)]
+pub struct HiddenCompany {
+    pub company_id: i64,
+    pub name: Option<String>,
+    pub hidden_at: DateTime<Utc>,
 }
 #[allow(unused)]
 pub struct JobId(u64);
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Job {
     pub job_info: JobInfo,
     pub created_at: DateTime<Utc>,
@@ -20,102 +337,748 @@ pub struct Job {
 
     pub company_info: CompanyInfo,
     pub locations: Vec<Location>,
+    /// The ids of every `Region` the job lies in, from the largest down.
+    pub geoarea_ids: Vec<i64>,
 
     pub contact_info: Option<ContactInfo>,
 }
+/// Postings that look to be the same position, like reposts of an ad or the
+/// same ad on several job boards.
+#[derive(Debug, Serialize)]
+pub struct JobCluster {
+    /// The newest posting, which has the latest text and deadline.
+    pub canonical: Job,
+    pub duplicates: Vec<JobPosting>,
+}
+/// Where else a clustered job has been posted.
+#[derive(Debug, Serialize)]
+pub struct JobPosting {
+    pub job_url: JobUrl,
+    pub title: Title,
+    pub created_at: DateTime<Utc>,
+    pub last_date: Option<DateTime<Utc>>,
+}
+impl From<Job> for JobPosting {
+    fn from(job: Job) -> Self {
+        JobPosting {
+            job_url: job.job_info.job_url,
+            title: job.job_info.title,
+            created_at: job.created_at,
+            last_date: job.last_date,
+        }
+    }
+}
 impl DbDelete for Job {
     type DeleteType<'a> = &'a [JobUrl];
-    type RetType = ();
+    /// The ids of the deleted jobs.
+    type RetType = Vec<i64>;
     async fn delete<'a, E: sqlx::Executor<'a, Database = Postgres>>(
         executor: E,
         jobs: Self::DeleteType<'a>,
     ) -> Result<Self::RetType, sqlx::Error> {
         let job_urls: Vec<String> =
-            jobs.into_iter().map(|job| job.0.to_owned()).collect();
-        let jobs = sqlx::query!(
+            jobs.iter().map(|job| job.0.to_owned()).collect();
+        // one statement, as the executor is only good for one. A cluster
+        // that loses its canonical job gets its newest remaining job, and
+        // the jobs that have applications are left alone.
+        let jobs = sqlx::query_scalar!(
             r#"--sql
-            SELECT id FROM job
-            WHERE job_url IN (
-                SELECT UNNEST($1::text[])
+            WITH doomed AS (
+                SELECT id FROM job
+                WHERE job_url = ANY($1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM job_application
+                    WHERE job_application.job_id = job.id
+                )
+            ), tags AS (
+                DELETE FROM tags_for_job
+                WHERE job_id IN (SELECT id FROM doomed)
+            ), locations AS (
+                DELETE FROM location_for_job
+                WHERE job_id IN (SELECT id FROM doomed)
+            ), clusters AS (
+                UPDATE job_cluster SET canonical_job_id = (
+                    SELECT job.id FROM job
+                    WHERE job.cluster_id = job_cluster.id
+                    AND job.id NOT IN (SELECT id FROM doomed)
+                    ORDER BY job.created_at DESC NULLS LAST, job.id DESC
+                    LIMIT 1
+                )
+                WHERE canonical_job_id IN (SELECT id FROM doomed)
             )
+            DELETE FROM job
+            WHERE id IN (SELECT id FROM doomed)
+            RETURNING id
         "#,
             &job_urls
         )
         .fetch_all(executor)
         .await?;
-
-        // let job_ids: Vec<i64> = jobs.into_iter().map(|job| job.id).collect();
-        // let tags = sqlx::query!(
-        //     r#"--sql
-        //   DELETE FROM tags_for_job
-        //   WHERE job_id in (
-        //     SELECT UNNEST($1::bigint[])
-        //   )
-        //   RETURNING job_tag_id
-        // "#,
-        //     &job_ids
-        // )
-        // .fetch_all(executor)
-        // .await?;
-
-        // let locations = sqlx::query!(
-        //     r#"--sql
-        //   DELETE FROM location_for_job
-        //   WHERE job_id in (
-        //     SELECT UNNEST($1::bigint[])
-        //   )
-        // "#,
-        //     &job_ids
-        // )
-        // .execute(executor)
-        // .await?;
-        // Ok(())
-        Ok(())
+        Ok(jobs)
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct JobTag {
     pub name: &'static str,
+    /// Number of times the tag matched, in title and body together. Only the
+    /// first few of these are kept in `hits`.
+    pub match_count: u32,
+    pub in_title: bool,
+    pub weight: f64,
+    pub hits: Vec<TagHit>,
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagHit {
+    pub source: TagSource,
+    /// byte offsets of the match in the title or description.
+    pub start: usize,
+    pub end: usize,
+    pub snippet: String,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagSource {
+    Title,
+    Body,
+}
+#[derive(Debug, Serialize)]
 
 pub struct JobInfo {
     pub job_url: JobUrl,
     pub title: Title,
     pub description: Description,
     pub job_tags: Vec<JobTag>,
+    pub classification: JobClassification,
+    pub salary: Option<Salary>,
 }
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct JobClassification {
+    pub seniority: Option<Seniority>,
+    pub employment_type: Option<EmploymentType>,
+    pub language: Option<Language>,
+    /// Whether the job can be done from home, fully or in part.
+    pub remote: bool,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seniority {
+    Junior,
+    Senior,
+    Lead,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmploymentType {
+    FullTime,
+    PartTime,
+    Student,
+    Internship,
+    Temporary,
+    Freelance,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[serde(rename = "da")]
+    Danish,
+    #[serde(rename = "en")]
+    English,
+}
+
+// the names below are what is stored in the job table.
+impl Seniority {
+    pub const ALL: [Seniority; 3] =
+        [Seniority::Junior, Seniority::Senior, Seniority::Lead];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Seniority::Junior => "junior",
+            Seniority::Senior => "senior",
+            Seniority::Lead => "lead",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|seniority| seniority.as_str() == name)
+    }
+}
+impl EmploymentType {
+    pub const ALL: [EmploymentType; 6] = [
+        EmploymentType::FullTime,
+        EmploymentType::PartTime,
+        EmploymentType::Student,
+        EmploymentType::Internship,
+        EmploymentType::Temporary,
+        EmploymentType::Freelance,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmploymentType::FullTime => "full_time",
+            EmploymentType::PartTime => "part_time",
+            EmploymentType::Student => "student",
+            EmploymentType::Internship => "internship",
+            EmploymentType::Temporary => "temporary",
+            EmploymentType::Freelance => "freelance",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Danish, Language::English];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Danish => "da",
+            Language::English => "en",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.as_str() == name)
+    }
+}
+/// A salary as written in the job ad. `min` and `max` are in whole units of
+/// `currency` per `period`, and are both missing when the ad only says the
+/// salary is negotiable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Salary {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    pub currency: Option<Currency>,
+    pub period: Option<SalaryPeriod>,
+    pub negotiable: bool,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Dkk,
+    Eur,
+    Sek,
+    Nok,
+    Usd,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SalaryPeriod {
+    Hour,
+    Month,
+    Year,
+}
+
+/// Full-time hours in a month, 37 hours a week.
+const HOURS_PER_MONTH: f64 = 37.0 * 52.0 / 12.0;
+
+impl Salary {
+    /// The salary range in DKK per month, which is what salary filters
+    /// compare against. `None` without an amount or a known exchange rate.
+    pub fn monthly_dkk(&self) -> Option<(i32, i32)> {
+        let min = self.min?;
+        let max = self.max.unwrap_or(min);
+        let factor = self.currency?.dkk_rate()? * self.period?.months_factor();
+        let monthly = |amount: i32| (f64::from(amount) * factor).round() as i32;
+        Some((monthly(min), monthly(max)))
+    }
+}
+impl Currency {
+    pub const ALL: [Currency; 5] = [
+        Currency::Dkk,
+        Currency::Eur,
+        Currency::Sek,
+        Currency::Nok,
+        Currency::Usd,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Currency::Dkk => "DKK",
+            Currency::Eur => "EUR",
+            Currency::Sek => "SEK",
+            Currency::Nok => "NOK",
+            Currency::Usd => "USD",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|currency| currency.as_str() == name)
+    }
+    /// Only the krone and the pegged euro have a fixed rate, the others are
+    /// left out of normalisation.
+    fn dkk_rate(&self) -> Option<f64> {
+        match self {
+            Currency::Dkk => Some(1.0),
+            Currency::Eur => Some(7.46),
+            Currency::Sek | Currency::Nok | Currency::Usd => None,
+        }
+    }
+}
+impl SalaryPeriod {
+    pub const ALL: [SalaryPeriod; 3] =
+        [SalaryPeriod::Hour, SalaryPeriod::Month, SalaryPeriod::Year];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SalaryPeriod::Hour => "hour",
+            SalaryPeriod::Month => "month",
+            SalaryPeriod::Year => "year",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|period| period.as_str() == name)
+    }
+    fn months_factor(&self) -> f64 {
+        match self {
+            SalaryPeriod::Hour => HOURS_PER_MONTH,
+            SalaryPeriod::Month => 1.0,
+            SalaryPeriod::Year => 1.0 / 12.0,
+        }
+    }
+}
+#[derive(Debug, Serialize)]
 
 pub struct JobUrl(pub String);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct Title(pub String);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct Description(pub String);
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct CompanyInfo {
     pub name: String,
     pub logo_url: String,
+    pub profile_url: Option<String>,
+    pub rating: Option<CompanyRating>,
+}
+/// One page of full-text search results.
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    /// Matching jobs on all pages.
+    pub total: i64,
+    pub hits: Vec<SearchHit>,
+}
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub id: i64,
+    /// The title and a few fragments of the description, with the matched
+    /// words in `<b>` tags.
+    pub title: String,
+    pub snippet: String,
+    pub rank: f32,
+    pub job_url: Option<String>,
+    pub company: Option<String>,
+    pub language: Option<Language>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// What the signed in user has marked of the job, if anything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark: Option<JobMark>,
+}
+/// One page of stored jobs matching a `JobFilter`.
+#[derive(Debug, Serialize)]
+pub struct JobPage {
+    /// Matching jobs on all pages.
+    pub total: i64,
+    pub jobs: Vec<JobSummary>,
+}
+#[derive(Debug, Serialize)]
+pub struct JobSummary {
+    pub id: i64,
+    pub title: Option<String>,
+    pub job_url: Option<String>,
+    pub company: Option<String>,
+    pub seniority: Option<Seniority>,
+    pub employment_type: Option<EmploymentType>,
+    pub language: Option<Language>,
+    /// The salary range in DKK per month, see `Salary::monthly_dkk`.
+    pub salary_monthly_min: Option<i32>,
+    pub salary_monthly_max: Option<i32>,
+    pub remote: bool,
+    pub tags: Vec<String>,
+    pub locations: Vec<String>,
+    /// Where else the job has been posted, newest first. A list of jobs
+    /// shows these postings only here, with the canonical job of their
+    /// `JobCluster`.
+    pub duplicates: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_date: Option<DateTime<Utc>>,
+    /// What the signed in user has marked of the job, if anything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark: Option<JobMark>,
+}
+#[cfg(test)]
+impl JobSummary {
+    /// A job with only an id and a title, for tests to fill in the rest of.
+    pub fn for_test(id: i64, title: &str) -> Self {
+        JobSummary {
+            id,
+            title: Some(title.to_owned()),
+            job_url: None,
+            company: None,
+            seniority: None,
+            employment_type: None,
+            language: None,
+            salary_monthly_min: None,
+            salary_monthly_max: None,
+            remote: false,
+            tags: Vec::new(),
+            locations: Vec::new(),
+            duplicates: Vec::new(),
+            created_at: None,
+            last_date: None,
+            mark: None,
+        }
+    }
+}
+/// One page of jobs in a `GeoArea`, nearest to its center first.
+#[derive(Debug, Serialize)]
+pub struct GeoResults {
+    /// Matching jobs on all pages.
+    pub total: i64,
+    pub hits: Vec<GeoHit>,
+}
+#[derive(Debug, Serialize)]
+pub struct GeoHit {
+    pub id: i64,
+    pub title: Option<String>,
+    pub job_url: Option<String>,
+    pub company: Option<String>,
+    /// The location of the job in the area that is nearest to its center.
+    pub address: Option<String>,
+    pub location: GeoPoint,
+    pub distance_km: f64,
+}
+/// A place jobs are commuted to from, like where someone lives.
+#[derive(Debug, Serialize)]
+pub struct HomeLocation {
+    pub name: String,
+    /// What the location was geocoded from, if not given as coordinates.
+    pub address: Option<String>,
+    pub location: GeoPoint,
+    pub commute_mode: CommuteMode,
+}
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CommuteMode {
+    #[default]
+    Car,
+    Transit,
+    Bike,
+    Walk,
+}
+// the names below are what is stored in the home_location table.
+impl CommuteMode {
+    pub const ALL: [CommuteMode; 4] = [
+        CommuteMode::Car,
+        CommuteMode::Transit,
+        CommuteMode::Bike,
+        CommuteMode::Walk,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommuteMode::Car => "car",
+            CommuteMode::Transit => "transit",
+            CommuteMode::Bike => "bike",
+            CommuteMode::Walk => "walk",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == name)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Commute {
+    pub minutes: f64,
+    /// Whether the time is from a routing backend rather than estimated from
+    /// the straight-line distance.
+    pub routed: bool,
+}
+/// One page of jobs within a commute of a `HomeLocation`.
+#[derive(Debug, Serialize)]
+pub struct CommuteResults {
+    /// Matching jobs on all pages.
+    pub total: usize,
+    pub hits: Vec<CommuteHit>,
+}
+#[derive(Debug, Serialize)]
+pub struct CommuteHit {
+    #[serde(flatten)]
+    pub job: GeoHit,
+    pub commute: Commute,
+}
+/// An area jobs can be searched in, as JobIndex knows it by its geoareaid.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Region {
+    pub id: i64,
+    pub name: String,
+    /// The short name JobIndex uses in its search URLs, like "storkbh".
+    pub code: Option<String>,
+    pub kind: RegionKind,
+    pub parent_id: Option<i64>,
 }
+/// The kinds of `Region`, from the largest down. Areas like "Storkøbenhavn"
+/// span several municipalities but lie within a region.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionKind {
+    Country,
+    Region,
+    Area,
+    Municipality,
+    Postcode,
+}
+// the names below are what is stored in the geoarea table.
+impl RegionKind {
+    pub const ALL: [RegionKind; 5] = [
+        RegionKind::Country,
+        RegionKind::Region,
+        RegionKind::Area,
+        RegionKind::Municipality,
+        RegionKind::Postcode,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegionKind::Country => "country",
+            RegionKind::Region => "region",
+            RegionKind::Area => "area",
+            RegionKind::Municipality => "municipality",
+            RegionKind::Postcode => "postcode",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+/// The regions a name could mean, and the one it was resolved to if it is
+/// not ambiguous.
+#[derive(Debug, Serialize)]
+pub struct RegionMatch {
+    pub resolved: Option<Region>,
+    pub candidates: Vec<Region>,
+}
+/// What a sync of the region catalogue left in it.
+#[derive(Debug, Serialize)]
+pub struct RegionSync {
+    pub regions: usize,
+    /// Regions whose parent is known from the stored jobs.
+    pub with_parent: usize,
+    /// Stored jobs that lay in no region and were given the regions of
+    /// their postcode, see `DataBase::backfill_job_geoareas`.
+    pub backfilled_jobs: i64,
+}
+/// A search that is run again after each crawl, to tell its owner about the
+/// jobs it has not matched before.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub query_options: QueryOptions,
+    pub filter_options: FilterOptions,
+    pub notify: Vec<NotifyTarget>,
+    pub created_at: DateTime<Utc>,
+}
+/// Where the new matches of a `SavedSearch` are sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotifyTarget {
+    Email {
+        to: String,
+    },
+    /// Signed like the webhooks of job events, with `secret`.
+    Webhook {
+        url: String,
+        secret: String,
+    },
+    /// The in-app feed of the owner.
+    Feed,
+}
+/// A job in the in-app feed of new matches.
+#[derive(Debug, Serialize)]
+pub struct FeedItem {
+    pub id: i64,
+    pub saved_search_id: i64,
+    pub saved_search: String,
+    pub job_id: i64,
+    pub title: Option<String>,
+    pub job_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+/// What running the saved searches after a crawl did.
+#[derive(Debug, Default, Serialize)]
+pub struct NotifyReport {
+    pub searches: usize,
+    /// Jobs that were new to a search, counted once per search.
+    pub new_matches: usize,
+    pub failures: Vec<DeliveryFailure>,
+}
+#[derive(Debug, Serialize)]
+pub struct DeliveryFailure {
+    pub saved_search_id: i64,
+    pub target: NotifyTarget,
+    pub error: String,
+}
+/// What happened to a job, as told to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEvent {
+    Created,
+    Updated,
+    /// The deadline of the job passed.
+    Expired,
+    /// A saved search matched the job for the first time.
+    Matched,
+    Deleted,
+}
+// the names below are what is stored in the webhook and webhook_delivery
+// tables.
+impl JobEvent {
+    pub const ALL: [JobEvent; 5] = [
+        JobEvent::Created,
+        JobEvent::Updated,
+        JobEvent::Expired,
+        JobEvent::Matched,
+        JobEvent::Deleted,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobEvent::Created => "created",
+            JobEvent::Updated => "updated",
+            JobEvent::Expired => "expired",
+            JobEvent::Matched => "matched",
+            JobEvent::Deleted => "deleted",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+}
+/// The body of a webhook.
+#[derive(Debug, Serialize)]
+pub struct JobEventPayload<'a> {
+    pub event: JobEvent,
+    pub occurred_at: DateTime<Utc>,
+    pub job_id: i64,
+    /// The saved search of a `JobEvent::Matched`.
+    pub saved_search_id: Option<i64>,
+    pub job: &'a Job,
+}
+/// A registered webhook. The secret is only shown when it is registered.
+#[derive(Debug, Serialize)]
+pub struct WebhookSubscription {
+    pub id: i64,
+    pub url: String,
+    /// Every event when empty.
+    pub events: Vec<JobEvent>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Out of attempts, in the dead-letter queue until it is retried.
+    Dead,
+}
+// the names below are what is stored in the webhook_delivery table.
+impl DeliveryStatus {
+    pub const ALL: [DeliveryStatus; 3] = [
+        DeliveryStatus::Pending,
+        DeliveryStatus::Delivered,
+        DeliveryStatus::Dead,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == name)
+    }
+}
+/// One event sent to one webhook, as listed in the delivery log.
+#[derive(Debug, Serialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: JobEvent,
+    pub job_id: i64,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// The HTTP status of the last attempt, if it got that far.
+    pub last_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+/// A delivery that is due, with what is needed to send it.
 #[derive(Debug)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub event: JobEvent,
+    pub payload: String,
+    pub attempts: i32,
+}
+/// A company as listed by the companies API.
+#[derive(Debug, Serialize)]
+pub struct Company {
+    pub id: i64,
+    pub name: String,
+    /// Every name the company has been posted under.
+    pub aliases: Vec<String>,
+    pub logo_url: Option<String>,
+    pub profile_url: Option<String>,
+    pub rating: Option<CompanyRating>,
+    /// Jobs whose deadline has not passed, newest first.
+    pub open_jobs: Vec<CompanyJob>,
+    pub history: Vec<PostingMonth>,
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompanyJob {
+    pub id: i64,
+    pub title: Option<String>,
+    pub job_url: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_date: Option<DateTime<Utc>>,
+}
+/// How many jobs a company posted in a month, e.g. "2025-08", in Danish time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostingMonth {
+    pub month: String,
+    pub postings: i64,
+}
+/// The employee rating of a company on the job board.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CompanyRating {
+    pub score: f64,
+    pub count: i32,
+}
+#[derive(Debug, Serialize)]
 
 pub struct Location {
     pub address: String,
     pub geo_location: (f64, f64),
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct ContactInfo {
     pub name: String,
     pub phone_number: PhoneNumber,
     pub email: String,
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 
 pub struct PhoneNumber(pub String);
@@ -1,5 +1,760 @@
-#![allow(unused)]
-struct DocService {}
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use minijinja::{Environment, UndefinedBehavior, context};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::Serialize;
+
+use crate::job_fetchers::tag_matcher::TAG_MATCHER;
+use crate::services::database_service::types::{Cv, DocFormat, Job, Skill};
+
+pub static DOC_SERVICE: LazyLock<DocService> = LazyLock::new(DocService::new);
+
+/// The cover letter template of an owner who has not written one.
+pub const DEFAULT_COVER_LETTER: &str =
+    include_str!("templates/cover_letter.md");
+
+/// Renders the documents that go with an application.
+pub struct DocService {
+    templates: Environment<'static>,
+}
+
+impl Default for DocService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DocService {
-    pub fn cv() {}
+    pub fn new() -> Self {
+        let mut templates = Environment::new();
+        // a block tag on a line of its own leaves no empty line behind
+        templates.set_trim_blocks(true);
+        templates.set_lstrip_blocks(true);
+        // a misspelt placeholder fails rather than leaving a gap in a letter
+        templates.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        templates.add_filter("month", month);
+        for (name, source) in [
+            ("cv.html", include_str!("templates/cv.html")),
+            ("cv.txt", include_str!("templates/cv.txt")),
+            (
+                "cover_letter.html",
+                include_str!("templates/cover_letter.html"),
+            ),
+        ] {
+            templates
+                .add_template(name, source)
+                .expect("the built-in templates should parse");
+        }
+        DocService { templates }
+    }
+
+    /// The CV as an HTML page. With a `job`, the skills it asks for come
+    /// first.
+    pub fn cv_html(
+        &self,
+        cv: &Cv,
+        job: Option<&Job>,
+    ) -> Result<String, minijinja::Error> {
+        self.render_cv("cv.html", cv, job)
+    }
+
+    /// The CV as an A4 PDF, set in the base fonts every reader has, so
+    /// nothing has to be embedded or fetched.
+    pub fn cv_pdf(
+        &self,
+        cv: &Cv,
+        job: Option<&Job>,
+    ) -> Result<Vec<u8>, minijinja::Error> {
+        let text = self.render_cv("cv.txt", cv, job)?;
+        Ok(typeset(&cv.name, &text))
+    }
+
+    /// Checks that `source` is a cover letter template that fills in.
+    pub fn check_cover_letter(
+        &self,
+        source: &str,
+    ) -> Result<(), minijinja::Error> {
+        self.templates
+            .render_str(source, LetterFields::default())
+            .map(|_| ())
+    }
+
+    /// A cover letter from the Markdown template `source`, filled in with
+    /// `fields`.
+    pub fn cover_letter(
+        &self,
+        source: &str,
+        fields: &LetterFields,
+        format: DocFormat,
+    ) -> Result<Vec<u8>, minijinja::Error> {
+        let markdown = self.templates.render_str(source, fields)?;
+        Ok(match format {
+            DocFormat::Markdown => markdown.into_bytes(),
+            DocFormat::Html => self
+                .templates
+                .get_template("cover_letter.html")?
+                .render(context! {
+                    title => fields.title,
+                    body => markdown_html(&markdown),
+                })?
+                .into_bytes(),
+            DocFormat::Pdf => typeset(&fields.title, &markup(&markdown)),
+        })
+    }
+
+    fn render_cv(
+        &self,
+        template: &str,
+        cv: &Cv,
+        job: Option<&Job>,
+    ) -> Result<String, minijinja::Error> {
+        let mut cv = cv.clone();
+        if let Some(job) = job {
+            tailor_skills(&mut cv.skills, job);
+        }
+        self.templates
+            .get_template(template)?
+            .render(context! { cv })
+    }
+}
+
+/// Orders `skills` by the weight of the tag of `job` each one is, heaviest
+/// first. A skill is a tag when it is named like it or like one of its
+/// needles, so "golang" is the "Go" tag. The other skills keep their order
+/// after those.
+pub fn tailor_skills(skills: &mut [Skill], job: &Job) {
+    // the sort is stable, so equal skills stay in the order they were given
+    skills.sort_by(|a, b| {
+        let unmatched = f64::NEG_INFINITY;
+        skill_weight(b, job)
+            .unwrap_or(unmatched)
+            .total_cmp(&skill_weight(a, job).unwrap_or(unmatched))
+    });
+}
+
+/// The weight of the heaviest tag of `job` that `skill` is, if any.
+fn skill_weight(skill: &Skill, job: &Job) -> Option<f64> {
+    let matched: Vec<&str> = TAG_MATCHER
+        .find_matches(&skill.name)
+        .into_iter()
+        .map(|tag_match| tag_match.tag)
+        .collect();
+    job.job_info
+        .job_tags
+        .iter()
+        .filter(|job_tag| {
+            job_tag.name.eq_ignore_ascii_case(&skill.name)
+                || matched.contains(&job_tag.name)
+        })
+        .map(|job_tag| job_tag.weight)
+        .reduce(f64::max)
+}
+
+/// What a cover letter template can fill in. What is not known is `none`,
+/// so a template can leave it out with e.g. `{% if contact %}`.
+#[derive(Debug, Default, Serialize)]
+pub struct LetterFields {
+    /// The applicant, from their CV.
+    pub name: Option<String>,
+    /// The day it is written, e.g. "19 October 2026".
+    pub date: String,
+    pub company: String,
+    pub title: String,
+    /// The skills of the CV the job asks for, most wanted first. Without a
+    /// CV, the tags of the job.
+    pub tags: Vec<String>,
+    pub location: Option<String>,
+    /// The contact person of the job.
+    pub contact: Option<String>,
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    pub job_url: String,
+}
+
+impl LetterFields {
+    pub fn new(job: &Job, cv: Option<&Cv>, date: NaiveDate) -> Self {
+        let tags = match cv {
+            Some(cv) => {
+                let mut skills = cv.skills.clone();
+                tailor_skills(&mut skills, job);
+                skills
+                    .into_iter()
+                    .filter(|skill| skill_weight(skill, job).is_some())
+                    .map(|skill| skill.name)
+                    .collect()
+            }
+            None => job
+                .job_info
+                .job_tags
+                .iter()
+                .map(|job_tag| job_tag.name.to_owned())
+                .collect(),
+        };
+        let contact = job.contact_info.as_ref();
+        let known = |value: &str| {
+            Some(value.trim().to_owned()).filter(|value| !value.is_empty())
+        };
+        LetterFields {
+            name: cv.map(|cv| cv.name.clone()),
+            date: date.format("%-d %B %Y").to_string(),
+            company: job.company_info.name.clone(),
+            title: job.job_info.title.0.clone(),
+            tags,
+            location: job
+                .locations
+                .first()
+                .map(|location| location.address.clone()),
+            contact: contact.and_then(|contact| known(&contact.name)),
+            contact_email: contact.and_then(|contact| known(&contact.email)),
+            contact_phone: contact
+                .and_then(|contact| known(&contact.phone_number.0)),
+            job_url: job.job_info.job_url.0.clone(),
+        }
+    }
+}
+
+impl DocFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "text/markdown; charset=utf-8",
+            DocFormat::Html => "text/html; charset=utf-8",
+            DocFormat::Pdf => "application/pdf",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+            DocFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// `markdown` as HTML. Raw HTML in it is shown as text, as it may come
+/// from a job ad.
+fn markdown_html(markdown: &str) -> String {
+    let events = Parser::new(markdown).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// `markdown` in the markup of `typeset`: headings, list items and
+/// paragraphs become blocks, and inline formatting is dropped.
+fn markup(markdown: &str) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut block = String::new();
+    let mut prefix = "";
+    let mut end_block = |block: &mut String, prefix: &mut &str| {
+        let text = block.trim();
+        if !text.is_empty() {
+            blocks.push(format!("{prefix}{text}"));
+        }
+        block.clear();
+        *prefix = "";
+    };
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                prefix = match level {
+                    HeadingLevel::H1 => "# ",
+                    HeadingLevel::H2 => "## ",
+                    _ => "### ",
+                }
+            }
+            Event::Start(Tag::Item) => prefix = "- ",
+            Event::End(
+                TagEnd::Heading(_) | TagEnd::Paragraph | TagEnd::Item,
+            )
+            | Event::HardBreak => end_block(&mut block, &mut prefix),
+            Event::Text(text)
+            | Event::Code(text)
+            | Event::Html(text)
+            | Event::InlineHtml(text) => block.push_str(&text),
+            Event::SoftBreak => block.push(' '),
+            _ => {}
+        }
+    }
+    end_block(&mut block, &mut prefix);
+    blocks.join("\n")
+}
+
+/// Formats a date like "2024-03-01" as "Mar 2024". Anything else is left
+/// as it is.
+fn month(value: String) -> String {
+    match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        Ok(date) => date.format("%b %Y").to_string(),
+        Err(_) => value,
+    }
+}
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// How a line of the text markup is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Style {
+    bold: bool,
+    size: f32,
+    /// Extra space above the line.
+    space_before: f32,
+    indent: f32,
+}
+
+impl Style {
+    const TITLE: Style = Style::new(true, 20.0, 0.0, 0.0);
+    const SECTION: Style = Style::new(true, 13.0, 12.0, 0.0);
+    const ENTRY: Style = Style::new(true, 11.0, 6.0, 0.0);
+    const BODY: Style = Style::new(false, 10.0, 4.0, 0.0);
+    const BULLET: Style = Style::new(false, 10.0, 0.0, 12.0);
+
+    const fn new(
+        bold: bool,
+        size: f32,
+        space_before: f32,
+        indent: f32,
+    ) -> Self {
+        Style {
+            bold,
+            size,
+            space_before,
+            indent,
+        }
+    }
+
+    /// The style a line of markup asks for, and its text.
+    fn of(line: &str) -> (Style, &str) {
+        [
+            ("# ", Style::TITLE),
+            ("## ", Style::SECTION),
+            ("### ", Style::ENTRY),
+            ("- ", Style::BULLET),
+        ]
+        .into_iter()
+        .find_map(|(prefix, style)| {
+            line.strip_prefix(prefix).map(|text| (style, text))
+        })
+        .unwrap_or((Style::BODY, line))
+    }
+
+    fn leading(&self) -> f32 {
+        self.size * 1.35
+    }
+}
+
+/// Sets `text` on A4 pages. Each line is a block in the style its prefix
+/// asks for: "# " for the title, "## " for a section, "### " for an entry
+/// and "- " for a bullet, or else a paragraph. Blocks are wrapped to the
+/// page and run on to new pages.
+fn typeset(title: &str, text: &str) -> Vec<u8> {
+    let mut pages: Vec<Content> = Vec::new();
+    let mut content = Content::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            continue;
+        }
+        let (style, text) = Style::of(line);
+        let font = if style.bold { BOLD } else { REGULAR };
+        let width = PAGE_WIDTH - 2.0 * MARGIN - style.indent;
+        y -= style.space_before;
+        for (i, row) in wrap(text, style, width).into_iter().enumerate() {
+            y -= style.leading();
+            if y < MARGIN {
+                pages.push(std::mem::replace(&mut content, Content::new()));
+                y = PAGE_HEIGHT - MARGIN - style.leading();
+            }
+            let x = MARGIN + style.indent;
+            content.begin_text().set_font(font, style.size);
+            if style == Style::BULLET && i == 0 {
+                content
+                    .next_line(x - style.indent, y)
+                    .show(Str(&win_ansi("•")))
+                    .next_line(style.indent, 0.0);
+            } else {
+                content.next_line(x, y);
+            }
+            content.show(Str(&win_ansi(&row))).end_text();
+        }
+    }
+    pages.push(content);
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let info_id = Ref::new(5);
+    let first_page = 6;
+    let page_ids: Vec<Ref> = (0..pages.len() as i32)
+        .map(|i| Ref::new(first_page + 2 * i))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    for (&page_id, content) in page_ids.iter().zip(pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .parent(page_tree_id)
+            .contents(content_id);
+        page.resources()
+            .fonts()
+            .pair(REGULAR, regular_id)
+            .pair(BOLD, bold_id);
+        page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+    for (id, font) in [(regular_id, "Helvetica"), (bold_id, "Helvetica-Bold")] {
+        pdf.type1_font(id)
+            .base_font(Name(font.as_bytes()))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+    pdf.document_info(info_id).title(TextStr(title));
+    pdf.finish()
+}
+
+/// Breaks `text` into rows that fit in `width` points, between words. A
+/// word longer than a row gets a row of its own.
+fn wrap(text: &str, style: Style, width: f32) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if row.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{row} {word}")
+        };
+        if !row.is_empty() && text_width(&candidate, style) > width {
+            rows.push(std::mem::replace(&mut row, word.to_owned()));
+        } else {
+            row = candidate;
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Advance widths of Helvetica and Helvetica-Bold from ' ' to '~', in
+/// thousandths of the font size.
+#[rustfmt::skip]
+const WIDTHS: [[u16; 95]; 2] = [
+    [
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333,
+        278, 278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278,
+        584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278,
+        500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944,
+        667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556,
+        278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500,
+        278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    ],
+    [
+        278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333,
+        278, 278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333,
+        584, 584, 584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278,
+        556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944,
+        667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556,
+        333, 611, 611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556,
+        333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+    ],
+];
+
+/// The width of `text` in points. Letters outside ASCII are taken to be as
+/// wide as a capital, so a row rather ends short than runs into the margin.
+fn text_width(text: &str, style: Style) -> f32 {
+    let widths = &WIDTHS[usize::from(style.bold)];
+    let thousandths: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => u32::from(widths[c as usize - ' ' as usize]),
+            _ => 667,
+        })
+        .sum();
+    thousandths as f32 * style.size / 1000.0
+}
+
+/// `text` in the WinAnsi encoding of the base fonts, which covers Latin-1
+/// and a little punctuation. Other characters become '?'.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::database_service::types::{
+        CompanyInfo, ContactInfo, Description, Education, Experience,
+        JobClassification, JobInfo, JobTag, JobUrl, Location, PhoneNumber,
+        Title, Utc,
+    };
+    use chrono::DateTime;
+
+    fn cv() -> Cv {
+        let date = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        let skill = |name: &str| Skill {
+            name: name.to_owned(),
+            level: None,
+        };
+        Cv {
+            name: "Anna Hansen".to_owned(),
+            headline: "Backend developer".to_owned(),
+            email: Some("anna@example.com".to_owned()),
+            phone: None,
+            summary: "Builds <fast> services.".to_owned(),
+            experience: vec![Experience {
+                title: "Developer".to_owned(),
+                employer: "Jensen & Søn".to_owned(),
+                location: Some("Aarhus".to_owned()),
+                start: date(2021, 3),
+                end: None,
+                description: "Payments in Rust.\n- Ran the on-call rota"
+                    .to_owned(),
+            }],
+            skills: vec![
+                skill("Python"),
+                skill("Docker"),
+                skill("golang"),
+                skill("Rust"),
+            ],
+            education: vec![Education {
+                degree: "MSc Computer Science".to_owned(),
+                institution: "Aarhus University".to_owned(),
+                start: date(2016, 9),
+                end: Some(date(2021, 1)),
+                description: String::new(),
+            }],
+        }
+    }
+
+    fn job(tags: &[(&'static str, f64)]) -> Job {
+        Job {
+            job_info: JobInfo {
+                job_url: JobUrl("https://example.com/job".to_owned()),
+                title: Title("Rust developer".to_owned()),
+                description: Description(String::new()),
+                job_tags: tags
+                    .iter()
+                    .map(|&(name, weight)| JobTag {
+                        name,
+                        match_count: 1,
+                        in_title: false,
+                        weight,
+                        hits: Vec::new(),
+                    })
+                    .collect(),
+                classification: JobClassification::default(),
+                salary: None,
+            },
+            created_at: DateTime::<Utc>::from_timestamp(0, 0).unwrap(),
+            last_date: None,
+            company_info: CompanyInfo {
+                name: "Jensen & Søn".to_owned(),
+                logo_url: String::new(),
+                profile_url: None,
+                rating: None,
+            },
+            locations: Vec::new(),
+            geoarea_ids: Vec::new(),
+            contact_info: None,
+        }
+    }
+
+    fn names(skills: &[Skill]) -> Vec<&str> {
+        skills.iter().map(|skill| skill.name.as_str()).collect()
+    }
+
+    #[test]
+    fn skills_are_ordered_by_the_job_tags() {
+        let mut skills = cv().skills;
+        tailor_skills(&mut skills, &job(&[("Rust", 3.0), ("Go", 1.5)]));
+        assert_eq!(names(&skills), ["Rust", "golang", "Python", "Docker"]);
+
+        let mut skills = cv().skills;
+        tailor_skills(&mut skills, &job(&[]));
+        assert_eq!(names(&skills), ["Python", "Docker", "golang", "Rust"]);
+    }
+
+    #[test]
+    fn renders_the_cv_as_html() {
+        let service = DocService::new();
+        let html = service
+            .cv_html(&cv(), Some(&job(&[("Docker", 1.0)])))
+            .unwrap();
+        assert!(html.contains("<h1>Anna Hansen</h1>"));
+        assert!(html.contains("Builds &lt;fast&gt; services."));
+        assert!(html.contains("Developer, Jensen &amp; Søn"));
+        assert!(html.contains("Mar 2021 – present, Aarhus"));
+        assert!(html.contains("Sep 2016 – Jan 2021"));
+        let docker = html.find("<li>Docker</li>").unwrap();
+        assert!(docker < html.find("<li>Python</li>").unwrap());
+    }
+
+    #[test]
+    fn the_text_layout_uses_the_markup() {
+        let service = DocService::new();
+        let text = service.render_cv("cv.txt", &cv(), None).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# Anna Hansen",
+                "Backend developer",
+                "anna@example.com",
+                "Builds <fast> services.",
+                "## Experience",
+                "### Developer, Jensen & Søn",
+                "Mar 2021 – present, Aarhus",
+                "Payments in Rust.",
+                "- Ran the on-call rota",
+                "## Skills",
+                "- Python",
+                "- Docker",
+                "- golang",
+                "- Rust",
+                "## Education",
+                "### MSc Computer Science, Aarhus University",
+                "Sep 2016 – Jan 2021",
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_the_cv_as_pdf() {
+        let pdf = DocService::new().cv_pdf(&cv(), None).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/BaseFont /Helvetica-Bold"));
+        assert!(pdf.contains("/Count 1"));
+    }
+
+    #[test]
+    fn long_text_wraps_and_runs_on_to_new_pages() {
+        let rows = wrap(&"word ".repeat(100), Style::BODY, 200.0);
+        assert!(rows.len() > 1);
+        assert!(rows.iter().all(|row| text_width(row, Style::BODY) <= 200.0));
+        assert_eq!(rows.join(" "), "word ".repeat(100).trim_end());
+
+        let text = "long paragraph of text\n".repeat(60);
+        let pdf = typeset("Long", &text);
+        assert!(String::from_utf8_lossy(&pdf).contains("/Count 2"));
+    }
+
+    #[test]
+    fn text_is_encoded_for_the_base_fonts() {
+        assert_eq!(win_ansi("Søn – 5€"), b"S\xf8n \x96 5\x80");
+        assert_eq!(win_ansi("日本"), b"??");
+    }
+
+    fn letter_fields() -> LetterFields {
+        let mut job = job(&[("Rust", 3.0), ("Sql", 1.0), ("Docker", 2.0)]);
+        job.locations.push(Location {
+            address: "Aarhus C".to_owned(),
+            geo_location: (56.1567, 10.2108),
+        });
+        job.contact_info = Some(ContactInfo {
+            name: "Mette Jensen".to_owned(),
+            phone_number: PhoneNumber(String::new()),
+            email: "mette@example.com".to_owned(),
+        });
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        LetterFields::new(&job, Some(&cv()), date)
+    }
+
+    #[test]
+    fn letter_fields_come_from_the_job_and_cv() {
+        let fields = letter_fields();
+        assert_eq!(fields.name.as_deref(), Some("Anna Hansen"));
+        assert_eq!(fields.date, "19 October 2026");
+        assert_eq!(fields.company, "Jensen & Søn");
+        assert_eq!(fields.title, "Rust developer");
+        // only the skills the job asks for, and Sql is not one of them
+        assert_eq!(fields.tags, ["Rust", "Docker"]);
+        assert_eq!(fields.location.as_deref(), Some("Aarhus C"));
+        assert_eq!(fields.contact.as_deref(), Some("Mette Jensen"));
+        assert_eq!(fields.contact_email.as_deref(), Some("mette@example.com"));
+        assert_eq!(fields.contact_phone, None);
+
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let fields = LetterFields::new(&job(&[("Go", 1.0)]), None, date);
+        assert_eq!(fields.name, None);
+        assert_eq!(fields.tags, ["Go"]);
+    }
+
+    #[test]
+    fn drafts_a_cover_letter() {
+        let service = DocService::new();
+        let fields = letter_fields();
+        let letter = service
+            .cover_letter(DEFAULT_COVER_LETTER, &fields, DocFormat::Markdown)
+            .unwrap();
+        let letter = String::from_utf8(letter).unwrap();
+        assert!(letter.starts_with("Anna Hansen\n\n19 October 2026\n"));
+        assert!(letter.contains("Jensen & Søn, Aarhus C\n"));
+        assert!(letter.contains("# Rust developer\n"));
+        assert!(letter.contains("Dear Mette Jensen,"));
+        assert!(letter.contains("I have worked with Rust, Docker, which"));
+
+        let html = service
+            .cover_letter(DEFAULT_COVER_LETTER, &fields, DocFormat::Html)
+            .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<title>Rust developer</title>"));
+        assert!(html.contains("<h1>Rust developer</h1>"));
+
+        let pdf = service
+            .cover_letter(DEFAULT_COVER_LETTER, &fields, DocFormat::Pdf)
+            .unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+    }
+
+    #[test]
+    fn templates_are_checked() {
+        let service = DocService::new();
+        assert!(service.check_cover_letter(DEFAULT_COVER_LETTER).is_ok());
+        assert!(service.check_cover_letter("Dear {{ contact }}").is_ok());
+        assert!(service.check_cover_letter("Dear {{ contact ").is_err());
+        // misspelt
+        assert!(service.check_cover_letter("Dear {{ contcat }}").is_err());
+    }
+
+    #[test]
+    fn markdown_is_converted() {
+        let markdown =
+            "# To *you*\n\nA line\nwrapped.\n\n- one\n- `two`\n\n<b>hi</b>\n";
+        assert_eq!(
+            markup(markdown),
+            "# To you\nA line wrapped.\n- one\n- two\n<b>hi</b>"
+        );
+        let html = markdown_html(markdown);
+        assert!(html.contains("<h1>To <em>you</em></h1>"));
+        assert!(html.contains("&lt;b&gt;hi&lt;/b&gt;"));
+    }
 }
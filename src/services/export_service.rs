@@ -0,0 +1,500 @@
+use std::fmt;
+
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt, TryStreamExt};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+use crate::services::commute_service::commute_job_ids;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    ExportFormat, HomeLocation, JobSummary, MatchProfile,
+};
+use crate::services::match_service::ranked_job_ids;
+use crate::util::options::{JobFilter, JobSort};
+
+/// How many jobs are fetched at a time while exporting.
+const BATCH_SIZE: usize = 500;
+
+/// The columns of the CSV and XLSX exports.
+const COLUMNS: [&str; 14] = [
+    "id",
+    "title",
+    "company",
+    "url",
+    "locations",
+    "tags",
+    "seniority",
+    "employment_type",
+    "language",
+    "salary_monthly_min",
+    "salary_monthly_max",
+    "remote",
+    "posted",
+    "deadline",
+];
+
+/// What tags and locations are joined with in a single field. Addresses
+/// have commas in them, so a comma would not do.
+const LIST_SEPARATOR: &str = "; ";
+
+/// An export, written a few jobs at a time.
+pub type ExportStream = BoxStream<'static, Result<Bytes, ExportError>>;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Database(sqlx::Error),
+    Xlsx(XlsxError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Database(error) => write!(f, "export failed: {error}"),
+            ExportError::Xlsx(error) => write!(f, "export failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(error: sqlx::Error) -> Self {
+        ExportError::Database(error)
+    }
+}
+
+impl From<XlsxError> for ExportError {
+    fn from(error: XlsxError) -> Self {
+        ExportError::Xlsx(error)
+    }
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Ics => "text/calendar; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Ics => "ics",
+        }
+    }
+}
+
+/// The jobs `filter` lets through, on its page and in the order of its sort,
+/// as `format`. Sorting by match needs the `profile` to rank the jobs for,
+/// and a filter with a `home` needs that home location.
+///
+/// Only the ids of the jobs are held while the export is written; the rest
+/// is fetched `BATCH_SIZE` jobs at a time as the stream is read.
+pub async fn export_jobs(
+    database: &DataBase,
+    filter: &JobFilter,
+    profile: Option<&MatchProfile>,
+    home: Option<&HomeLocation>,
+    format: ExportFormat,
+) -> Result<ExportStream, sqlx::Error> {
+    let job_ids = match (home, profile) {
+        (Some(home), _) => commute_job_ids(database, filter, home).await?,
+        (None, Some(profile)) if filter.sort == JobSort::Match => {
+            ranked_job_ids(database, filter, profile).await?
+        }
+        _ => database.filter_job_ids(filter).await?,
+    };
+    let job_ids = filter.size_options.page(job_ids);
+    Ok(encode(
+        job_summaries(database.clone(), job_ids),
+        format,
+        Utc::now(),
+    ))
+}
+
+/// The summaries of the jobs with the ids `job_ids`, in that order.
+fn job_summaries(
+    database: DataBase,
+    job_ids: Vec<i64>,
+) -> impl Stream<Item = Result<JobSummary, sqlx::Error>> + Send + 'static {
+    let batches: Vec<Vec<i64>> =
+        job_ids.chunks(BATCH_SIZE).map(<[i64]>::to_vec).collect();
+    stream::iter(batches)
+        .then(move |batch| {
+            let database = database.clone();
+            async move { database.get_job_summaries(&batch).await }
+        })
+        .map_ok(|jobs| stream::iter(jobs).map(Ok))
+        .try_flatten()
+}
+
+/// `jobs` written as `format`, stamped with `now` where the format wants it.
+fn encode<S>(jobs: S, format: ExportFormat, now: DateTime<Utc>) -> ExportStream
+where
+    S: Stream<Item = Result<JobSummary, sqlx::Error>> + Send + 'static,
+{
+    let (head, tail) = match format {
+        // a zip archive, which cannot be sent before it is done
+        ExportFormat::Xlsx => return stream::once(xlsx(jobs)).boxed(),
+        ExportFormat::Csv => (csv_record(COLUMNS), Vec::new()),
+        ExportFormat::Ndjson => (Vec::new(), Vec::new()),
+        ExportFormat::Ics => (
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//jobhunter//application deadlines//EN",
+                "CALSCALE:GREGORIAN",
+                "X-WR-CALNAME:Application deadlines",
+            ]
+            .map(|line| format!("{line}\r\n"))
+            .concat()
+            .into_bytes(),
+            b"END:VCALENDAR\r\n".to_vec(),
+        ),
+    };
+    let rows = jobs
+        .map_err(ExportError::from)
+        .map_ok(move |job| match format {
+            ExportFormat::Csv => csv_record(csv_fields(&job)),
+            ExportFormat::Ndjson => ndjson_line(&job),
+            ExportFormat::Ics => ics_event(&job, now).unwrap_or_default(),
+            ExportFormat::Xlsx => unreachable!("XLSX is written whole"),
+        })
+        .try_filter(|row| std::future::ready(!row.is_empty()));
+
+    stream::once(std::future::ready(Ok(head)))
+        .chain(rows)
+        .chain(stream::once(std::future::ready(Ok(tail))))
+        .try_filter(|chunk| std::future::ready(!chunk.is_empty()))
+        .map_ok(Bytes::from)
+        .boxed()
+}
+
+/// The day of `date` in Denmark, where the deadlines are.
+fn local_day(date: DateTime<Utc>) -> NaiveDate {
+    date.with_timezone(&chrono_tz::Europe::Copenhagen)
+        .date_naive()
+}
+
+fn csv_fields(job: &JobSummary) -> [String; 14] {
+    let text = |text: &Option<String>| text.clone().unwrap_or_default();
+    let number = |number: Option<i32>| {
+        number.map(|number| number.to_string()).unwrap_or_default()
+    };
+    let day = |date: Option<DateTime<Utc>>| {
+        date.map(|date| local_day(date).to_string())
+            .unwrap_or_default()
+    };
+    [
+        job.id.to_string(),
+        text(&job.title),
+        text(&job.company),
+        text(&job.job_url),
+        job.locations.join(LIST_SEPARATOR),
+        job.tags.join(LIST_SEPARATOR),
+        job.seniority
+            .map(|seniority| seniority.as_str())
+            .unwrap_or("")
+            .to_owned(),
+        job.employment_type
+            .map(|employment_type| employment_type.as_str())
+            .unwrap_or("")
+            .to_owned(),
+        job.language
+            .map(|language| language.as_str())
+            .unwrap_or("")
+            .to_owned(),
+        number(job.salary_monthly_min),
+        number(job.salary_monthly_max),
+        job.remote.to_string(),
+        day(job.created_at),
+        day(job.last_date),
+    ]
+}
+
+/// `fields` as a line of CSV, quoted where they need to be.
+fn csv_record<I>(fields: I) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .expect("writing to memory should not fail");
+    writer
+        .into_inner()
+        .expect("writing to memory should not fail")
+}
+
+fn ndjson_line(job: &JobSummary) -> Vec<u8> {
+    let mut line =
+        serde_json::to_vec(job).expect("a job summary should serialise");
+    line.push(b'\n');
+    line
+}
+
+/// `text` escaped for an iCalendar text value.
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// `line` folded into lines of at most 75 bytes, each after the first
+/// starting with a space, as iCalendar wants.
+fn ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut width = 0;
+    for char in line.chars() {
+        if width + char.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(char);
+        width += char.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// An all-day event on the deadline of `job`, or `None` if it has none.
+fn ics_event(job: &JobSummary, now: DateTime<Utc>) -> Option<Vec<u8>> {
+    let deadline = local_day(job.last_date?);
+    let title = job.title.as_deref().unwrap_or("Untitled job");
+    let summary = match &job.company {
+        Some(company) => format!("Deadline: {title} at {company}"),
+        None => format!("Deadline: {title}"),
+    };
+    let mut description = Vec::new();
+    if !job.locations.is_empty() {
+        description.push(job.locations.join(LIST_SEPARATOR));
+    }
+    if !job.tags.is_empty() {
+        description.push(job.tags.join(", "));
+    }
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_owned(),
+        format!("UID:job-{}@jobhunter", job.id),
+        format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+        format!("DTSTART;VALUE=DATE:{}", deadline.format("%Y%m%d")),
+        format!(
+            "DTEND;VALUE=DATE:{}",
+            (deadline + TimeDelta::days(1)).format("%Y%m%d")
+        ),
+        format!("SUMMARY:{}", ics_text(&summary)),
+    ];
+    if let Some(job_url) = &job.job_url {
+        lines.push(format!("URL:{job_url}"));
+    }
+    if !description.is_empty() {
+        lines
+            .push(format!("DESCRIPTION:{}", ics_text(&description.join("\n"))));
+    }
+    lines.push("END:VEVENT".to_owned());
+    Some(
+        lines
+            .iter()
+            .map(|line| ics_line(line))
+            .collect::<String>()
+            .into_bytes(),
+    )
+}
+
+/// `jobs` as a workbook with a sheet of a row per job. The rows go to a
+/// temporary file as they are written rather than staying in memory.
+async fn xlsx<S>(jobs: S) -> Result<Bytes, ExportError>
+where
+    S: Stream<Item = Result<JobSummary, sqlx::Error>> + Send,
+{
+    let mut workbook = Workbook::new();
+    let header = Format::new().set_bold();
+    let day = Format::new().set_num_format("yyyy-mm-dd");
+    let sheet = workbook.add_worksheet_with_constant_memory();
+    sheet.set_name("Jobs")?;
+    // with constant memory, columns are laid out before the rows
+    for (column, width) in [(1, 40), (2, 24), (3, 40), (4, 30), (5, 30)] {
+        sheet.set_column_width(column, width)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.write_row_with_format(0, 0, COLUMNS, &header)?;
+
+    let mut jobs = std::pin::pin!(jobs);
+    let mut row = 0;
+    while let Some(job) = jobs.try_next().await? {
+        row += 1;
+        let text = |text: &Option<String>| text.clone().unwrap_or_default();
+        sheet.write_number(row, 0, job.id as f64)?;
+        sheet.write_string(row, 1, text(&job.title))?;
+        sheet.write_string(row, 2, text(&job.company))?;
+        sheet.write_string(row, 3, text(&job.job_url))?;
+        sheet.write_string(row, 4, job.locations.join(LIST_SEPARATOR))?;
+        sheet.write_string(row, 5, job.tags.join(LIST_SEPARATOR))?;
+        if let Some(seniority) = job.seniority {
+            sheet.write_string(row, 6, seniority.as_str())?;
+        }
+        if let Some(employment_type) = job.employment_type {
+            sheet.write_string(row, 7, employment_type.as_str())?;
+        }
+        if let Some(language) = job.language {
+            sheet.write_string(row, 8, language.as_str())?;
+        }
+        if let Some(salary) = job.salary_monthly_min {
+            sheet.write_number(row, 9, salary)?;
+        }
+        if let Some(salary) = job.salary_monthly_max {
+            sheet.write_number(row, 10, salary)?;
+        }
+        sheet.write_boolean(row, 11, job.remote)?;
+        if let Some(created_at) = job.created_at {
+            sheet.write_datetime_with_format(
+                row,
+                12,
+                local_day(created_at),
+                &day,
+            )?;
+        }
+        if let Some(last_date) = job.last_date {
+            sheet.write_datetime_with_format(
+                row,
+                13,
+                local_day(last_date),
+                &day,
+            )?;
+        }
+    }
+    sheet.autofilter(0, 0, row, COLUMNS.len() as u16 - 1)?;
+
+    let buffer = tokio::task::spawn_blocking(move || workbook.save_to_buffer())
+        .await
+        .expect("saving the workbook should not panic")?;
+    Ok(Bytes::from(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::database_service::types::Seniority;
+
+    fn job(id: i64, last_date: Option<DateTime<Utc>>) -> JobSummary {
+        JobSummary {
+            job_url: Some("https://example.com/jobs/1".to_owned()),
+            company: Some("Acme, Inc.".to_owned()),
+            seniority: Some(Seniority::Senior),
+            salary_monthly_min: Some(50_000),
+            remote: true,
+            tags: vec!["Rust".to_owned(), "PostgreSQL".to_owned()],
+            locations: vec![
+                "Vesterbrogade 1, København".to_owned(),
+                "Aarhus".to_owned(),
+            ],
+            last_date,
+            ..JobSummary::for_test(id, "Rust developer")
+        }
+    }
+
+    fn deadline() -> DateTime<Utc> {
+        // late in the evening in UTC, the next day in Denmark
+        "2026-11-30T23:30:00Z".parse().unwrap()
+    }
+
+    async fn export(jobs: Vec<JobSummary>, format: ExportFormat) -> Vec<u8> {
+        let now = "2026-10-19T08:00:00Z".parse().unwrap();
+        encode(stream::iter(jobs).map(Ok), format, now)
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn csv_flattens_tags_and_locations() {
+        let csv =
+            export(vec![job(1, Some(deadline()))], ExportFormat::Csv).await;
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        assert_eq!(reader.headers().unwrap(), COLUMNS.as_slice());
+        let records: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(&record[2], "Acme, Inc.");
+        assert_eq!(&record[4], "Vesterbrogade 1, København; Aarhus");
+        assert_eq!(&record[5], "Rust; PostgreSQL");
+        assert_eq!(&record[6], "senior");
+        assert_eq!(&record[7], "");
+        assert_eq!(&record[9], "50000");
+        assert_eq!(&record[11], "true");
+        assert_eq!(&record[13], "2026-12-01");
+    }
+
+    #[tokio::test]
+    async fn ndjson_has_a_job_on_each_line() {
+        let ndjson =
+            export(vec![job(1, None), job(2, None)], ExportFormat::Ndjson)
+                .await;
+        let lines: Vec<serde_json::Value> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["id"], 2);
+        assert_eq!(lines[0]["tags"][1], "PostgreSQL");
+    }
+
+    #[tokio::test]
+    async fn ics_has_an_event_per_deadline() {
+        let ics = export(
+            vec![job(1, Some(deadline())), job(2, None)],
+            ExportFormat::Ics,
+        )
+        .await;
+        let ics = String::from_utf8(ics).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("UID:job-1@jobhunter\r\n"));
+        assert!(ics.contains("DTSTAMP:20261019T080000Z\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261201\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20261202\r\n"));
+        assert!(
+            ics.contains(
+                "SUMMARY:Deadline: Rust developer at Acme\\, Inc.\r\n"
+            )
+        );
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "{line}");
+        }
+    }
+
+    #[test]
+    fn ics_lines_fold_between_characters() {
+        let line = format!("DESCRIPTION:{}", "æ".repeat(60));
+        let folded = ics_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+        assert_eq!(ics_text("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+    }
+
+    #[tokio::test]
+    async fn xlsx_is_a_workbook() {
+        let xlsx = export(
+            vec![job(1, Some(deadline())), job(2, None)],
+            ExportFormat::Xlsx,
+        )
+        .await;
+        // a zip archive
+        assert!(xlsx.starts_with(b"PK\x03\x04"));
+    }
+}
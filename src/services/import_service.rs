@@ -0,0 +1,549 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::job_fetchers::classifier::classify_job;
+use crate::job_fetchers::job_index::fetcher::JobIndex;
+use crate::job_fetchers::preview::{DateFormat, JobPreview, parse_date_at};
+use crate::job_fetchers::salary::parse_salary;
+use crate::job_fetchers::tag_matcher::TAG_MATCHER;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    CompanyInfo, Description, ImportFormat, ImportIssue, ImportReport, Job,
+    JobInfo, JobUrl, Location, Title,
+};
+
+/// The largest file imported over HTTP.
+pub const MAX_IMPORT_BYTES: usize = 64 * 1024 * 1024;
+
+/// Dates in the generic schema, like the days of the CSV export.
+struct ImportDate;
+
+impl DateFormat for ImportDate {
+    const DATE_FORMATS: &'static [&'static str] = &[
+        "%Y-%m-%d",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%d-%m-%Y",
+        "%d.%m.%Y",
+    ];
+    const TIME_ZONE: Tz = chrono_tz::Europe::Copenhagen;
+}
+
+/// A job in the generic schema, for files that do not come from a fetcher.
+/// The names of the CSV and NDJSON exports are taken too, so an export can
+/// be imported again.
+#[derive(Debug, Deserialize)]
+struct GenericJob {
+    title: String,
+    #[serde(alias = "job_url")]
+    url: String,
+    company: String,
+    company_logo_url: Option<String>,
+    #[serde(default)]
+    description: String,
+    /// Now when left out.
+    #[serde(alias = "created_at")]
+    posted: Option<String>,
+    #[serde(alias = "last_date")]
+    deadline: Option<String>,
+    #[serde(default)]
+    locations: Vec<GenericLocation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenericLocation {
+    address: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// A line of CSV in the generic schema, which has room for one location.
+#[derive(Debug, Deserialize)]
+struct CsvJob {
+    title: String,
+    #[serde(alias = "job_url")]
+    url: String,
+    company: String,
+    company_logo_url: Option<String>,
+    description: Option<String>,
+    #[serde(alias = "created_at")]
+    posted: Option<String>,
+    #[serde(alias = "last_date")]
+    deadline: Option<String>,
+    address: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+impl TryFrom<CsvJob> for GenericJob {
+    type Error = String;
+    fn try_from(job: CsvJob) -> Result<Self, Self::Error> {
+        let locations = match (job.address, job.latitude, job.longitude) {
+            (None, None, None) => Vec::new(),
+            (Some(address), Some(latitude), Some(longitude)) => {
+                vec![GenericLocation {
+                    address,
+                    latitude,
+                    longitude,
+                }]
+            }
+            _ => {
+                return Err("an address needs a latitude and longitude, and \
+                            the other way around"
+                    .to_owned());
+            }
+        };
+        Ok(GenericJob {
+            title: job.title,
+            url: job.url,
+            company: job.company,
+            company_logo_url: job.company_logo_url,
+            description: job.description.unwrap_or_default(),
+            posted: job.posted,
+            deadline: job.deadline,
+            locations,
+        })
+    }
+}
+
+impl ImportFormat {
+    /// The format of a file named `path`, by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(ImportFormat::Json),
+            "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+            "csv" => Some(ImportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// The jobs of a file, each with the record it is from, and why the other
+/// records are not jobs.
+#[derive(Debug, Default)]
+struct ReadJobs {
+    records: usize,
+    jobs: Vec<(usize, Job)>,
+    issues: Vec<ImportIssue>,
+}
+
+impl ReadJobs {
+    fn push(&mut self, record: usize, job: Result<Job, String>) {
+        self.records += 1;
+        match job.and_then(|job| validate(&job).map(|()| job)) {
+            Ok(job) => self.jobs.push((record, job)),
+            Err(message) => self.issues.push(ImportIssue { record, message }),
+        }
+    }
+}
+
+/// Stores the jobs of `content`, adding the new ones and updating the ones
+/// stored before by their url, like a crawl does. A dry run stores nothing
+/// and reports what would be done.
+///
+/// JobIndex results, as in the dumps of `jobindex_files`, go through the
+/// same `JobPreview<JobIndex>` conversion as fetched jobs; other jobs are in
+/// the generic schema of `GenericJob`. The saved searches are not notified
+/// of imported jobs, which are often old.
+pub async fn import_jobs(
+    database: &DataBase,
+    format: ImportFormat,
+    content: &[u8],
+    dry_run: bool,
+) -> Result<ImportReport, sqlx::Error> {
+    let ReadJobs {
+        records,
+        jobs,
+        mut issues,
+    } = read_jobs(format, content, Utc::now());
+    let job_urls: Vec<String> = jobs
+        .iter()
+        .map(|(_, job)| job.job_info.job_url.0.clone())
+        .collect();
+    let stored: HashSet<String> = database
+        .get_stored_job_urls(&job_urls)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut report = ImportReport {
+        dry_run,
+        records,
+        ..Default::default()
+    };
+    for (record, job) in jobs {
+        if !dry_run && let Err(error) = database.insert_job(&job).await {
+            let message = format!("could not be stored: {error}");
+            issues.push(ImportIssue { record, message });
+            continue;
+        }
+        if stored.contains(&job.job_info.job_url.0) {
+            report.existing += 1;
+        } else {
+            report.new += 1;
+        }
+    }
+    issues.sort_by_key(|issue| issue.record);
+    report.issues = issues;
+    Ok(report)
+}
+
+/// The jobs of `content`, with the ones posted at no given time posted
+/// `now`. A job with the url of one before it is left out.
+fn read_jobs(
+    format: ImportFormat,
+    content: &[u8],
+    now: DateTime<Utc>,
+) -> ReadJobs {
+    let mut read = ReadJobs::default();
+    match format {
+        ImportFormat::Json => match json_records(content) {
+            Ok(records) => {
+                for (index, record) in records.into_iter().enumerate() {
+                    read.push(index + 1, json_job(record, now));
+                }
+            }
+            // the file as a whole
+            Err(message) => {
+                read.issues.push(ImportIssue { record: 0, message })
+            }
+        },
+        ImportFormat::Ndjson => {
+            let content = String::from_utf8_lossy(content);
+            for (index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let job = serde_json::from_str(line)
+                    .map_err(|error| format!("not JSON: {error}"))
+                    .and_then(|record| json_job(record, now));
+                read.push(index + 1, job);
+            }
+        }
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(content);
+            let headers = match reader.headers() {
+                Ok(headers) => headers.clone(),
+                Err(error) => {
+                    let message = format!("not CSV: {error}");
+                    read.issues.push(ImportIssue { record: 0, message });
+                    return read;
+                }
+            };
+            for (index, record) in reader.records().enumerate() {
+                // the header is the first line
+                let line = match &record {
+                    Ok(record) => {
+                        record.position().map(|position| position.line())
+                    }
+                    Err(error) => {
+                        error.position().map(|position| position.line())
+                    }
+                }
+                .map_or(index + 2, |line| line as usize);
+                let job = record
+                    .and_then(|record| {
+                        record.deserialize::<CsvJob>(Some(&headers))
+                    })
+                    .map_err(|error| error.to_string())
+                    .and_then(GenericJob::try_from)
+                    .and_then(|job| generic_job(job, now));
+                read.push(line, job);
+            }
+        }
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut jobs = Vec::with_capacity(read.jobs.len());
+    for (record, job) in read.jobs {
+        let job_url = job.job_info.job_url.0.clone();
+        if let Some(first) = seen.insert(job_url, record) {
+            let message = format!("the same job as record {first}");
+            read.issues.push(ImportIssue { record, message });
+            seen.insert(job.job_info.job_url.0.clone(), first);
+        } else {
+            jobs.push((record, job));
+        }
+    }
+    read.jobs = jobs;
+    read
+}
+
+/// The records of a JSON file: a list of them, JobIndex results like
+/// `{"results": [...]}`, or a whole JobIndex result page.
+fn json_records(content: &[u8]) -> Result<Vec<Value>, String> {
+    let not_jobs = || {
+        "expected a list of jobs or an object with a list of `results`"
+            .to_owned()
+    };
+    let value: Value = serde_json::from_slice(content)
+        .map_err(|error| format!("not JSON: {error}"))?;
+    let results = match value {
+        Value::Array(records) => return Ok(records),
+        Value::Object(mut page) => match page.remove("results") {
+            Some(results) => results,
+            None => page
+                .get_mut("jobsearch/result_app")
+                .and_then(|app| app.get_mut("storeData"))
+                .and_then(|store| store.get_mut("searchResponse"))
+                .and_then(|response| response.get_mut("results"))
+                .map(Value::take)
+                .ok_or_else(not_jobs)?,
+        },
+        _ => return Err(not_jobs()),
+    };
+    match results {
+        Value::Array(records) => Ok(records),
+        _ => Err(not_jobs()),
+    }
+}
+
+/// The job of a JSON record, a JobIndex result if it has the html of one
+/// and a job in the generic schema otherwise.
+fn json_job(record: Value, now: DateTime<Utc>) -> Result<Job, String> {
+    if record.get("html").is_some() {
+        let post =
+            serde_json::to_vec(&record).expect("a JSON value should serialise");
+        return job_index_job(&post);
+    }
+    let job: GenericJob =
+        serde_json::from_value(record).map_err(|error| error.to_string())?;
+    generic_job(job, now)
+}
+
+fn job_index_job(post: &[u8]) -> Result<Job, String> {
+    let not_job_index =
+        |error: serde_json::Error| format!("not a JobIndex result: {error}");
+    let preview =
+        JobPreview::<JobIndex>::try_from(post).map_err(not_job_index)?;
+    Job::try_from(preview).map_err(not_job_index)
+}
+
+/// `job` with its tags, classification and salary found like for a fetched
+/// job.
+fn generic_job(job: GenericJob, now: DateTime<Utc>) -> Result<Job, String> {
+    let date = |date: Option<String>| {
+        date.map(|date| parse_date_at::<ImportDate>(&date, now))
+            .transpose()
+            .map_err(|error| error.to_string())
+    };
+    let title = Title(job.title);
+    let description = Description(job.description);
+    Ok(Job {
+        job_info: JobInfo {
+            job_url: JobUrl(job.url),
+            job_tags: TAG_MATCHER.job_tags(&title.0, &description.0),
+            classification: classify_job(&title, &description),
+            salary: parse_salary(&description.0),
+            title,
+            description,
+        },
+        created_at: date(job.posted)?.unwrap_or(now),
+        last_date: date(job.deadline)?,
+        company_info: CompanyInfo {
+            name: job.company,
+            logo_url: job.company_logo_url.unwrap_or_default(),
+            profile_url: None,
+            rating: None,
+        },
+        locations: job
+            .locations
+            .into_iter()
+            .map(|location| Location {
+                address: location.address,
+                geo_location: (location.latitude, location.longitude),
+            })
+            .collect(),
+        geoarea_ids: Vec::new(),
+        contact_info: None,
+    })
+}
+
+/// Whether `job` can be stored and found again.
+fn validate(job: &Job) -> Result<(), String> {
+    if job.job_info.title.0.trim().is_empty() {
+        return Err("the title is empty".to_owned());
+    }
+    if job.company_info.name.trim().is_empty() {
+        return Err("the company is empty".to_owned());
+    }
+    let job_url = &job.job_info.job_url.0;
+    match Url::parse(job_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => return Err(format!("{job_url:?} is not a web address")),
+    }
+    for location in &job.locations {
+        let (latitude, longitude) = location.geo_location;
+        if !(-90.0..=90.0).contains(&latitude)
+            || !(-180.0..=180.0).contains(&longitude)
+        {
+            return Err(format!(
+                "{:?} is at no place on earth",
+                location.address
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-19T08:00:00Z".parse().unwrap()
+    }
+
+    fn read(format: ImportFormat, content: &str) -> ReadJobs {
+        read_jobs(format, content.as_bytes(), now())
+    }
+
+    fn issue(record: usize, message: &str) -> ImportIssue {
+        ImportIssue {
+            record,
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn job_index_dumps() {
+        for file in ["jobindex_files/job.json", "linkedin_files/results.json"] {
+            let content = std::fs::read(file).unwrap();
+            let read = read_jobs(ImportFormat::Json, &content, now());
+            assert_eq!(read.records, 1, "{file}");
+            assert_eq!(read.issues, [], "{file}");
+        }
+
+        let content = std::fs::read("jobindex_files/job.json").unwrap();
+        let (_, job) = &read_jobs(ImportFormat::Json, &content, now()).jobs[0];
+        assert_eq!(job.job_info.title.0, "Tele Sales Agent");
+        assert_eq!(
+            job.job_info.job_url.0,
+            "https://www.jobindex.dk/vis-job/h1577664"
+        );
+        assert_eq!(job.company_info.name, "Saphe A/S");
+        assert!(job.job_info.description.0.starts_with("Vi søger"));
+        assert_eq!(job.locations[0].address, "Gasværksvej 46, 9000 Aalborg");
+        assert!(job.created_at < now());
+        assert!(job.last_date.is_some());
+    }
+
+    #[test]
+    fn a_whole_result_page() {
+        let content = std::fs::read("jobindex_files/jobs.json").unwrap();
+        let page = read_jobs(ImportFormat::Json, &content, now());
+        assert_eq!(page.records, 20);
+        assert_eq!(page.issues, []);
+        assert_eq!(page.jobs.len(), 20);
+        assert!(page.jobs.iter().all(|(_, job)| {
+            job.job_info
+                .job_url
+                .0
+                .starts_with("https://www.jobindex.dk/")
+        }));
+        // a company without a JobIndex page only has a name
+        let (_, job) = &page.jobs[16];
+        assert_eq!(job.company_info.name, "Calibras");
+        assert_eq!(job.company_info.logo_url, "");
+
+        let read = read(ImportFormat::Json, r#"{"storeData": {}}"#);
+        assert_eq!(read.records, 0);
+        assert_eq!(
+            read.issues,
+            [issue(
+                0,
+                "expected a list of jobs or an object with a list of `results`"
+            )]
+        );
+    }
+
+    #[test]
+    fn generic_ndjson() {
+        let read = read(
+            ImportFormat::Ndjson,
+            r#"{"title": "Rust developer", "url": "https://example.com/1", "company": "Acme", "posted": "2026-10-01", "locations": [{"address": "Aarhus", "latitude": 56.16, "longitude": 10.2}]}
+
+{"title": "", "url": "https://example.com/2", "company": "Acme"}
+{"title": "Go developer", "url": "not a url", "company": "Acme"}
+not json
+{"title": "Rust developer", "url": "https://example.com/1", "company": "Acme"}
+"#,
+        );
+        assert_eq!(read.records, 5);
+        assert_eq!(read.jobs.len(), 1);
+        let (record, job) = &read.jobs[0];
+        assert_eq!(*record, 1);
+        assert_eq!(
+            job.created_at,
+            "2026-09-30T22:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(job.last_date, None);
+        assert_eq!(job.locations[0].geo_location, (56.16, 10.2));
+        assert!(job.job_info.job_tags.iter().any(|tag| tag.name == "Rust"));
+
+        let records: Vec<usize> =
+            read.issues.iter().map(|issue| issue.record).collect();
+        assert_eq!(records, [3, 4, 5, 6]);
+        assert_eq!(read.issues[0].message, "the title is empty");
+        assert_eq!(read.issues[3].message, "the same job as record 1");
+    }
+
+    #[test]
+    fn generic_csv() {
+        let read = read(
+            ImportFormat::Csv,
+            "title,url,company,description,deadline,address,latitude,longitude\n\
+             Rust developer,https://example.com/1,\"Acme, Inc.\",We use Rust,2026-11-30,Aarhus,56.16,10.2\n\
+             Go developer,https://example.com/2,Acme,,,,,\n\
+             Elixir developer,https://example.com/3,Acme,,,Aarhus,,\n\
+             C developer,https://example.com/4,Acme,,next week,,,\n",
+        );
+        assert_eq!(read.records, 4);
+        assert_eq!(read.jobs.len(), 2);
+        let (_, job) = &read.jobs[0];
+        assert_eq!(job.company_info.name, "Acme, Inc.");
+        assert_eq!(job.job_info.description.0, "We use Rust");
+        assert_eq!(job.created_at, now());
+        assert!(job.last_date.is_some());
+        assert_eq!(job.locations.len(), 1);
+        assert!(read.jobs[1].1.locations.is_empty());
+
+        let records: Vec<usize> =
+            read.issues.iter().map(|issue| issue.record).collect();
+        assert_eq!(records, [4, 5]);
+    }
+
+    #[test]
+    fn the_csv_export_imports_again() {
+        let read = read(
+            ImportFormat::Csv,
+            "id,title,company,url,locations,tags,seniority,employment_type,language,salary_monthly_min,salary_monthly_max,remote,posted,deadline\n\
+             36,Export dev 1,\"Export Co, ApS\",https://x.dk/1,Aarhus,Rust; Go,senior,,,40001,,false,2026-10-18,2026-10-29\n",
+        );
+        assert_eq!(read.issues, []);
+        let (_, job) = &read.jobs[0];
+        assert_eq!(job.job_info.title.0, "Export dev 1");
+        assert_eq!(
+            job.created_at,
+            "2026-10-17T22:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn formats_by_extension() {
+        let format = |path: &str| ImportFormat::from_path(Path::new(path));
+        assert_eq!(format("dump/jobs.JSON"), Some(ImportFormat::Json));
+        assert_eq!(format("jobs.jsonl"), Some(ImportFormat::Ndjson));
+        assert_eq!(format("jobs.csv"), Some(ImportFormat::Csv));
+        assert_eq!(format("jobs.xlsx"), None);
+        assert_eq!(format("jobs"), None);
+    }
+}
@@ -1,9 +1,12 @@
 use std::{marker::PhantomData, mem::MaybeUninit};
 
+use crate::job_fetchers::dedup::cluster_jobs;
 use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{Job, NotifyReport};
+use crate::services::notification_service::notify_new_matches;
 use crate::{
     job_fetchers::JobFetcher,
-    services::database_service::types::Job,
+    services::database_service::types::JobCluster,
     util::{
         equality::{IsEqualityOp, RecEqChecker, TupleLength},
         options::FetchOptions,
@@ -96,13 +99,33 @@ macro_rules! fetchers {
 }
 
 impl<const N: usize, T: JobFetcher> Jobs<DataBase, [T; N]> {
-    #[allow(unused)]
-    async fn fetch_jobs(&self, options: FetchOptions) -> Option<Vec<Job>> {
-        
-        self
-            .job_fetchers
-            .fetch_all_jobs_with_options_and_db(&options, Some(&self.database))
+    /// The jobs of every fetcher, with reposts and the same job from several
+    /// sources grouped together.
+    pub async fn fetch_jobs(
+        &self,
+        options: FetchOptions,
+    ) -> Option<Vec<JobCluster>> {
+        self.job_fetchers
+            .fetch_all_jobs_with_options_and_db(&options, Some(&self.database))
             .await
+            .map(cluster_jobs)
+    }
+
+    /// Fetches and stores the jobs of every fetcher, then sends the saved
+    /// searches the jobs they have not matched before. Returns the jobs
+    /// fetched with what was sent. Every job of `options` is fetched, as
+    /// the ones stored before are updated by their url.
+    pub async fn crawl(
+        &self,
+        options: FetchOptions,
+    ) -> Option<(Vec<Job>, NotifyReport)> {
+        let jobs = self
+            .job_fetchers
+            .fetch_all_jobs_with_options_and_db(&options, None)
+            .await?;
+        self.database.insert_jobs::<()>(&jobs).await.ok()?;
+        let report = notify_new_matches(&self.database).await.ok()?;
+        Some((jobs, report))
     }
 }
 
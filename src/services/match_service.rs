@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use crate::job_fetchers::classifier::classify_job;
+use crate::job_fetchers::tag_matcher::TAG_MATCHER;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    Description, JobMatch, JobPage, MatchCandidate, MatchProfile, Seniority,
+    Title,
+};
+use crate::util::geo::GeoPoint;
+use crate::util::options::JobFilter;
+
+/// How much each fit counts towards the score, when it is known.
+const TAG_WEIGHT: f64 = 0.6;
+const SENIORITY_WEIGHT: f64 = 0.2;
+const DISTANCE_WEIGHT: f64 = 0.2;
+
+/// Jobs this close to home fit fully, and from there the fit falls off
+/// linearly until `FAR_KM`.
+const NEAR_KM: f64 = 10.0;
+const FAR_KM: f64 = 100.0;
+
+/// The most jobs scored for a ranking, the ones whose tags fit best.
+pub const MAX_MATCH_CANDIDATES: usize = 1000;
+
+/// The skills found in a CV, strongest first, and the seniority it reads as.
+/// The skills are the tags `TAG_MATCHER` finds in jobs, so the two compare.
+pub fn extract_profile(text: &str) -> (Vec<String>, Option<Seniority>) {
+    let mut tags = TAG_MATCHER.job_tags("", text);
+    tags.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    let skills = tags.iter().map(|tag| tag.name.to_owned()).collect();
+    let seniority =
+        classify_job(&Title(String::new()), &Description(text.to_owned()))
+            .seniority;
+    (skills, seniority)
+}
+
+/// The text of an uploaded CV, which is plain text, Markdown or a PDF.
+pub async fn cv_text(
+    content_type: &str,
+    content: Vec<u8>,
+) -> Result<String, &'static str> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    match mime {
+        "text/plain" | "text/markdown" => {
+            String::from_utf8(content).map_err(|_| "the CV is not UTF-8")
+        }
+        "application/pdf" => {
+            // the parser panics on some malformed files
+            tokio::task::spawn_blocking(move || {
+                pdf_extract::extract_text_from_mem(&content)
+            })
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .ok_or("the PDF could not be read")
+        }
+        _ => Err("the CV should be text/plain, text/markdown or a PDF"),
+    }
+}
+
+/// The tag a skill is written as, either its name or one of its needles,
+/// so "golang" is "Go".
+pub fn canonical_skill(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    TAG_MATCHER.tag_name(name).or_else(|| {
+        let matches = TAG_MATCHER.find_matches(name);
+        match matches.as_slice() {
+            [only] if only.range == (0..name.len()) => Some(only.tag),
+            _ => None,
+        }
+    })
+}
+
+/// How far apart `a` and `b` are, as a fit from 0 to 1.
+fn seniority_fit(a: Seniority, b: Seniority) -> f64 {
+    let step = |seniority| {
+        Seniority::ALL
+            .iter()
+            .position(|&other| other == seniority)
+            .expect("every seniority should be in ALL")
+    };
+    match step(a).abs_diff(step(b)) {
+        0 => 1.0,
+        1 => 0.5,
+        _ => 0.0,
+    }
+}
+
+fn distance_fit(distance_km: f64) -> f64 {
+    ((FAR_KM - distance_km) / (FAR_KM - NEAR_KM)).clamp(0.0, 1.0)
+}
+
+/// How well `job` fits `profile`, with `home` the location of its home.
+pub fn score(
+    profile: &MatchProfile,
+    home: Option<GeoPoint>,
+    job: &MatchCandidate,
+) -> JobMatch {
+    let has_skill = |tag: &str| {
+        profile
+            .skills
+            .iter()
+            .any(|skill| skill.eq_ignore_ascii_case(tag))
+    };
+    let (matched, missing): (Vec<_>, Vec<_>) =
+        job.tags.iter().partition(|(tag, _)| has_skill(tag));
+    let total_weight: f64 = job.tags.iter().map(|(_, weight)| weight).sum();
+    let tag_fit =
+        (!profile.skills.is_empty() && total_weight > 0.0).then(|| {
+            matched.iter().map(|(_, weight)| weight).sum::<f64>() / total_weight
+        });
+
+    let seniority_fit = profile
+        .seniority
+        .zip(job.seniority)
+        .map(|(profile, job)| seniority_fit(profile, job));
+
+    let distance_km = home.and_then(|home| {
+        job.locations
+            .iter()
+            .map(|&location| home.distance_km(location))
+            .min_by(f64::total_cmp)
+    });
+    let distance_fit = if job.remote && home.is_some() {
+        Some(1.0)
+    } else {
+        distance_km.map(distance_fit)
+    };
+
+    let fits = [
+        (TAG_WEIGHT, tag_fit),
+        (SENIORITY_WEIGHT, seniority_fit),
+        (DISTANCE_WEIGHT, distance_fit),
+    ];
+    let (weighted, weights) = fits
+        .iter()
+        .filter_map(|&(weight, fit)| Some((weight * fit?, weight)))
+        .fold((0.0, 0.0), |(sum, weights), (value, weight)| {
+            (sum + value, weights + weight)
+        });
+    let score = if weights > 0.0 {
+        (weighted / weights * 1000.0).round() / 10.0
+    } else {
+        0.0
+    };
+
+    let names = |tags: Vec<&(String, f64)>| {
+        tags.into_iter().map(|(tag, _)| tag.clone()).collect()
+    };
+    JobMatch {
+        job_id: job.id,
+        score,
+        matched_tags: names(matched),
+        missing_tags: names(missing),
+        tag_fit,
+        seniority: job.seniority,
+        seniority_fit,
+        distance_km,
+        distance_fit,
+    }
+}
+
+/// Where the distances of `profile` are measured from.
+async fn profile_home(
+    database: &DataBase,
+    profile: &MatchProfile,
+) -> Result<Option<GeoPoint>, sqlx::Error> {
+    let Some(name) = &profile.home else {
+        return Ok(None);
+    };
+    Ok(database
+        .get_home_locations(profile.user_id, Some(name))
+        .await?
+        .pop()
+        .map(|home| home.location))
+}
+
+/// How well the job with the id `job_id` fits `profile`, if there is one.
+pub async fn job_match(
+    database: &DataBase,
+    profile: &MatchProfile,
+    job_id: i64,
+) -> Result<Option<JobMatch>, sqlx::Error> {
+    let home = profile_home(database, profile).await?;
+    Ok(database
+        .get_match_candidates(&[job_id])
+        .await?
+        .pop()
+        .map(|job| score(profile, home, &job)))
+}
+
+/// The ids of the jobs matching `filter` on every page, the best fit for
+/// `profile` first. Only the `MAX_MATCH_CANDIDATES` jobs whose tags fit
+/// best are scored, and the jobs after those are left out. Among equals,
+/// the better tag fit and then the newest comes first.
+pub async fn ranked_job_ids(
+    database: &DataBase,
+    filter: &JobFilter,
+    profile: &MatchProfile,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let home = profile_home(database, profile).await?;
+    let job_ids = database
+        .match_candidate_ids(
+            filter,
+            &profile.skills,
+            MAX_MATCH_CANDIDATES as i64,
+        )
+        .await?;
+    let rank: HashMap<i64, usize> = job_ids
+        .iter()
+        .enumerate()
+        .map(|(rank, &id)| (id, rank))
+        .collect();
+
+    let mut matches: Vec<JobMatch> = database
+        .get_match_candidates(&job_ids)
+        .await?
+        .iter()
+        .map(|job| score(profile, home, job))
+        .collect();
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(rank[&a.job_id].cmp(&rank[&b.job_id]))
+    });
+    Ok(matches.iter().map(|job| job.job_id).collect())
+}
+
+/// The jobs matching `filter` on the page it asks for, ranked like
+/// `ranked_job_ids`.
+pub async fn rank_jobs(
+    database: &DataBase,
+    filter: &JobFilter,
+    profile: &MatchProfile,
+) -> Result<JobPage, sqlx::Error> {
+    let job_ids = ranked_job_ids(database, filter, profile).await?;
+    let total = job_ids.len() as i64;
+    let page = filter.size_options.page(job_ids);
+    Ok(JobPage {
+        total,
+        jobs: database.get_job_summaries(&page).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+
+    use super::*;
+    use crate::services::database_service::types::{Cv, Experience, Skill};
+    use crate::services::doc_service::DOC_SERVICE;
+
+    fn profile(skills: &[&str], seniority: Option<Seniority>) -> MatchProfile {
+        MatchProfile {
+            user_id: 1,
+            skills: skills.iter().map(|&skill| skill.to_owned()).collect(),
+            seniority,
+            home: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn job(tags: &[(&str, f64)]) -> MatchCandidate {
+        MatchCandidate {
+            id: 1,
+            tags: tags
+                .iter()
+                .map(|&(tag, weight)| (tag.to_owned(), weight))
+                .collect(),
+            seniority: None,
+            remote: false,
+            locations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn extracts_tags_and_seniority() {
+        let (skills, seniority) = extract_profile(
+            "Senior developer\n\nRust, Rust and more Rust. Some Python.",
+        );
+        assert_eq!(skills, ["Rust", "Python"]);
+        assert_eq!(seniority, Some(Seniority::Senior));
+    }
+
+    #[test]
+    fn canonical_skill_accepts_needles() {
+        assert_eq!(canonical_skill("Rust"), Some("Rust"));
+        assert_eq!(canonical_skill(" golang "), Some("Go"));
+        assert_eq!(canonical_skill("rust and python"), None);
+        assert_eq!(canonical_skill("knitting"), None);
+    }
+
+    #[test]
+    fn scores_by_tag_weight() {
+        let job = job(&[("Rust", 3.0), ("Docker", 1.0)]);
+        let result = score(&profile(&["rust"], None), None, &job);
+        assert_eq!(result.matched_tags, ["Rust"]);
+        assert_eq!(result.missing_tags, ["Docker"]);
+        assert_eq!(result.tag_fit, Some(0.75));
+        // the only fit there is decides the score
+        assert_eq!(result.score, 75.0);
+    }
+
+    #[test]
+    fn weighs_the_fits_there_are() {
+        let mut job = job(&[("Rust", 1.0)]);
+        job.seniority = Some(Seniority::Lead);
+        let result =
+            score(&profile(&["Go"], Some(Seniority::Junior)), None, &job);
+        assert_eq!(result.tag_fit, Some(0.0));
+        assert_eq!(result.seniority_fit, Some(0.0));
+        assert_eq!(result.score, 0.0);
+
+        let result =
+            score(&profile(&["Rust"], Some(Seniority::Senior)), None, &job);
+        assert_eq!(result.seniority_fit, Some(0.5));
+        // (0.6 * 1 + 0.2 * 0.5) / 0.8
+        assert_eq!(result.score, 87.5);
+    }
+
+    #[test]
+    fn distance_falls_off_from_home() {
+        let home = GeoPoint {
+            lat: 56.1629,
+            lon: 10.2039,
+        };
+        let mut job = job(&[]);
+        job.locations = vec![
+            GeoPoint {
+                lat: 55.6761,
+                lon: 12.5683,
+            },
+            home,
+        ];
+        let result = score(&profile(&[], None), Some(home), &job);
+        assert_eq!(result.distance_km, Some(0.0));
+        assert_eq!(result.distance_fit, Some(1.0));
+
+        job.locations.pop();
+        let result = score(&profile(&[], None), Some(home), &job);
+        assert!(result.distance_km.is_some_and(|km| km > 150.0));
+        assert_eq!(result.distance_fit, Some(0.0));
+
+        job.remote = true;
+        let result = score(&profile(&[], None), Some(home), &job);
+        assert_eq!(result.distance_fit, Some(1.0));
+        assert_eq!(result.score, 100.0);
+
+        let result = score(&profile(&[], None), None, &job);
+        assert_eq!(result.distance_fit, None);
+        assert_eq!(result.score, 0.0);
+    }
+
+    #[tokio::test]
+    async fn reads_the_text_of_a_pdf() {
+        let cv = Cv {
+            name: "Anna Hansen".to_owned(),
+            headline: "Senior backend developer".to_owned(),
+            email: None,
+            phone: None,
+            summary: "Builds services in Rust.".to_owned(),
+            experience: vec![Experience {
+                title: "Developer".to_owned(),
+                employer: "Jensen".to_owned(),
+                location: None,
+                start: NaiveDate::from_ymd_opt(2021, 3, 1).unwrap(),
+                end: None,
+                description: "Ran Docker in production.".to_owned(),
+            }],
+            skills: vec![Skill {
+                name: "Python".to_owned(),
+                level: None,
+            }],
+            education: Vec::new(),
+        };
+        let pdf = DOC_SERVICE.cv_pdf(&cv, None).unwrap();
+
+        let text = cv_text("application/pdf", pdf).await.unwrap();
+        let (mut skills, seniority) = extract_profile(&text);
+        skills.sort();
+        assert_eq!(skills, ["Backend", "Docker", "Python", "Rust"]);
+        assert_eq!(seniority, Some(Seniority::Senior));
+
+        assert!(cv_text("application/pdf", b"%PDF-".to_vec()).await.is_err());
+        assert!(cv_text("image/png", Vec::new()).await.is_err());
+        assert_eq!(
+            cv_text("text/markdown; charset=utf-8", b"# Anna".to_vec()).await,
+            Ok("# Anna".to_owned())
+        );
+    }
+}
@@ -1,3 +1,13 @@
+pub mod application_service;
+pub mod auth_service;
+pub mod commute_service;
 pub mod database_service;
 pub mod doc_service;
+pub mod export_service;
+pub mod import_service;
 pub mod jobs_service;
+pub mod match_service;
+pub mod notification_service;
+pub mod page_service;
+pub mod region_service;
+pub mod webhook_service;
@@ -0,0 +1,549 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::LazyLock;
+
+use chrono::Utc;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+use url::Url;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    DeliveryFailure, JobSummary, NotifyReport, NotifyTarget, SavedSearch,
+};
+use crate::services::webhook_service::{MIN_SECRET_LEN, signed_post};
+use crate::util::options::{JobFilter, JobSort, QueryOptions, SizeOptions};
+
+/// The mail relay at `SMTP_HOST` and `SMTP_PORT`, by default a local
+/// stand-in like MailHog on port 1025.
+pub static SMTP: LazyLock<Smtp> = LazyLock::new(Smtp::from_env);
+
+/// What is sent to every target of a saved search with new matches. It is
+/// also the body of webhooks.
+#[derive(Debug, Serialize)]
+pub struct Notification<'a> {
+    pub saved_search_id: i64,
+    pub saved_search: &'a str,
+    pub user_id: i64,
+    /// Newest first.
+    pub jobs: &'a [JobSummary],
+}
+
+impl Notification<'_> {
+    pub fn subject(&self) -> String {
+        match self.jobs.len() {
+            1 => format!("1 new job for \"{}\"", self.saved_search),
+            count => format!("{count} new jobs for \"{}\"", self.saved_search),
+        }
+    }
+
+    /// The jobs as plain text, one paragraph each.
+    pub fn text(&self) -> String {
+        self.jobs
+            .iter()
+            .map(|job| {
+                let title = job.title.as_deref().unwrap_or("Untitled job");
+                let heading = match &job.company {
+                    Some(company) => format!("{title} at {company}"),
+                    None => title.to_owned(),
+                };
+                match &job.job_url {
+                    Some(job_url) => format!("{heading}\n{job_url}\n"),
+                    None => format!("{heading}\n"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug)]
+pub enum NotifyError {
+    Email(String),
+    Webhook(reqwest::Error),
+    Database(sqlx::Error),
+    /// The target cannot be delivered to, like an email without a domain.
+    Target(&'static str),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Email(error) => write!(f, "email failed: {error}"),
+            NotifyError::Webhook(error) => write!(f, "webhook failed: {error}"),
+            NotifyError::Database(error) => write!(f, "feed failed: {error}"),
+            NotifyError::Target(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+impl From<reqwest::Error> for NotifyError {
+    fn from(error: reqwest::Error) -> Self {
+        NotifyError::Webhook(error)
+    }
+}
+
+impl From<sqlx::Error> for NotifyError {
+    fn from(error: sqlx::Error) -> Self {
+        NotifyError::Database(error)
+    }
+}
+
+/// Somewhere new matches can be sent.
+pub trait NotificationSink {
+    fn deliver(
+        &self,
+        notification: &Notification<'_>,
+    ) -> impl Future<Output = Result<(), NotifyError>> + Send;
+}
+
+pub struct Smtp {
+    host: String,
+    port: u16,
+    from: Mailbox,
+}
+
+impl Smtp {
+    pub fn new(host: String, port: u16, from: Mailbox) -> Self {
+        Smtp { host, port, from }
+    }
+
+    pub fn from_env() -> Self {
+        let host =
+            std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".into());
+        let port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(1025);
+        let from = std::env::var("SMTP_FROM")
+            .ok()
+            .and_then(|from| from.parse().ok())
+            .unwrap_or_else(|| {
+                "Jobhunter <jobhunter@localhost>"
+                    .parse()
+                    .expect("should be a valid mailbox")
+            });
+        Smtp::new(host, port, from)
+    }
+}
+
+/// An email to one address, sent through the relay without TLS, as the
+/// relay is expected to run next to the server.
+pub struct Email<'a> {
+    pub smtp: &'a Smtp,
+    pub to: Mailbox,
+}
+
+impl NotificationSink for Email<'_> {
+    async fn deliver(
+        &self,
+        notification: &Notification<'_>,
+    ) -> Result<(), NotifyError> {
+        let message = Message::builder()
+            .from(self.smtp.from.clone())
+            .to(self.to.clone())
+            .subject(notification.subject())
+            .header(ContentType::TEXT_PLAIN)
+            .body(notification.text())
+            .map_err(|error| NotifyError::Email(error.to_string()))?;
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            &self.smtp.host,
+        )
+        .port(self.smtp.port)
+        .build()
+        .send(message)
+        .await
+        .map_err(|error| NotifyError::Email(error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// A JSON `Notification` posted to a URL, signed like the webhooks of job
+/// events are.
+pub struct Webhook {
+    pub client: reqwest::Client,
+    pub url: Url,
+    pub secret: String,
+}
+
+impl NotificationSink for Webhook {
+    async fn deliver(
+        &self,
+        notification: &Notification<'_>,
+    ) -> Result<(), NotifyError> {
+        let body = serde_json::to_string(notification)
+            .expect("a notification should serialize");
+        signed_post(
+            &self.client,
+            self.url.as_str(),
+            &self.secret,
+            body,
+            Utc::now(),
+        )
+        .send()
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The in-app feed of the owner of the saved search.
+pub struct Feed<'a> {
+    pub database: &'a DataBase,
+}
+
+impl NotificationSink for Feed<'_> {
+    async fn deliver(
+        &self,
+        notification: &Notification<'_>,
+    ) -> Result<(), NotifyError> {
+        let job_ids: Vec<i64> =
+            notification.jobs.iter().map(|job| job.id).collect();
+        self.database
+            .insert_feed_items(
+                notification.user_id,
+                notification.saved_search_id,
+                &job_ids,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+impl NotifyTarget {
+    /// Whether the target can be delivered to at all.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            NotifyTarget::Email { to } => to
+                .parse::<Mailbox>()
+                .map(|_| ())
+                .map_err(|_| "to should be an email address"),
+            NotifyTarget::Webhook { url, secret } => {
+                match Url::parse(url) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                    _ => return Err("url should be an http or https URL"),
+                }
+                if secret.chars().count() < MIN_SECRET_LEN {
+                    return Err("secret should be at least 16 characters");
+                }
+                Ok(())
+            }
+            NotifyTarget::Feed => Ok(()),
+        }
+    }
+
+    async fn deliver(
+        &self,
+        database: &DataBase,
+        notification: &Notification<'_>,
+    ) -> Result<(), NotifyError> {
+        match self {
+            NotifyTarget::Email { to } => {
+                let to = to
+                    .parse()
+                    .map_err(|_| NotifyError::Target("not an email address"))?;
+                Email { smtp: &SMTP, to }.deliver(notification).await
+            }
+            NotifyTarget::Webhook { url, secret } => {
+                let url = Url::parse(url)
+                    .map_err(|_| NotifyError::Target("not a URL"))?;
+                Webhook {
+                    client: reqwest::Client::new(),
+                    url,
+                    secret: secret.clone(),
+                }
+                .deliver(notification)
+                .await
+            }
+            NotifyTarget::Feed => Feed { database }.deliver(notification).await,
+        }
+    }
+}
+
+/// The stored jobs `search` matches, like `/jobs` would list them to the
+/// user of the search, so without the jobs and companies the user hid.
+pub fn search_filter(search: &SavedSearch) -> JobFilter {
+    let (job_name, job_regions, job_tags) = match &search.query_options {
+        QueryOptions::All => (None, Vec::new(), Vec::new()),
+        QueryOptions::Query {
+            job_name,
+            job_regions,
+            job_tags,
+        } => (job_name.clone(), job_regions.clone(), job_tags.clone()),
+    };
+    JobFilter {
+        job_name,
+        job_regions,
+        regions: Vec::new(),
+        job_tags,
+        tag_mode: Default::default(),
+        company: None,
+        posted_after: None,
+        deadline_before: None,
+        remote: None,
+        expired: false,
+        home: None,
+        max_commute: None,
+        starred: false,
+        hidden: Default::default(),
+        viewer: Some(search.user_id),
+        sort: JobSort::Newest,
+        size_options: SizeOptions::All,
+        filter_options: search.filter_options.clone(),
+    }
+}
+
+/// Runs every saved search over the stored jobs and sends the jobs each one
+/// has not matched before to its targets. A job is only ever sent once, also
+/// when a target fails.
+pub async fn notify_new_matches(
+    database: &DataBase,
+) -> Result<NotifyReport, sqlx::Error> {
+    let searches = database.get_saved_searches(None).await?;
+    let mut report = NotifyReport {
+        searches: searches.len(),
+        ..Default::default()
+    };
+
+    for search in searches {
+        let jobs = database
+            .record_new_matches(search.id, &search_filter(&search), true)
+            .await?;
+        if jobs.is_empty() {
+            continue;
+        }
+        report.new_matches += jobs.len();
+
+        let notification = Notification {
+            saved_search_id: search.id,
+            saved_search: &search.name,
+            user_id: search.user_id,
+            jobs: &jobs,
+        };
+        for target in &search.notify {
+            if let Err(error) = target.deliver(database, &notification).await {
+                report.failures.push(DeliveryFailure {
+                    saved_search_id: search.id,
+                    target: target.clone(),
+                    error: error.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::services::webhook_service::SIGNATURE_HEADER;
+    use crate::util::options::{FilterOptions, HiddenMode};
+
+    fn job(id: i64, title: &str, company: Option<&str>) -> JobSummary {
+        JobSummary {
+            job_url: Some(format!("https://www.jobindex.dk/vis-job/h{id}")),
+            company: company.map(str::to_owned),
+            ..JobSummary::for_test(id, title)
+        }
+    }
+
+    fn notification(jobs: &[JobSummary]) -> Notification<'_> {
+        Notification {
+            saved_search_id: 7,
+            saved_search: "Rust i Aarhus",
+            user_id: 1,
+            jobs,
+        }
+    }
+
+    /// Answers one SMTP session like a relay would and returns what it was
+    /// sent after `DATA`.
+    async fn fake_relay(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    write.write_all(b"250 queued\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let reply: &[u8] = match &line.to_uppercase()[..4] {
+                "EHLO" => b"250 localhost\r\n",
+                "DATA" => {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                }
+                "QUIT" => {
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn notification_text() {
+        let jobs = [
+            job(2, "Rust Developer", Some("Netcompany")),
+            job(1, "Backend-udvikler", None),
+        ];
+        let notification = notification(&jobs);
+        assert_eq!(notification.subject(), "2 new jobs for \"Rust i Aarhus\"");
+        assert_eq!(
+            notification.text(),
+            "Rust Developer at Netcompany\nhttps://www.jobindex.dk/vis-job/h2\n\
+             \nBackend-udvikler\nhttps://www.jobindex.dk/vis-job/h1\n"
+        );
+        assert_eq!(
+            Notification {
+                jobs: &jobs[..1],
+                ..notification
+            }
+            .subject(),
+            "1 new job for \"Rust i Aarhus\""
+        );
+    }
+
+    #[test]
+    fn targets_are_validated() {
+        let email = |to: &str| NotifyTarget::Email { to: to.to_owned() };
+        let webhook = |url: &str, secret: &str| NotifyTarget::Webhook {
+            url: url.to_owned(),
+            secret: secret.to_owned(),
+        };
+        let secret = "a secret of some length";
+        assert!(email("anna@example.dk").validate().is_ok());
+        assert!(email("anna").validate().is_err());
+        let hook = "https://example.dk/hooks/jobs";
+        assert!(webhook(hook, secret).validate().is_ok());
+        assert!(webhook(hook, "short").validate().is_err());
+        assert!(webhook("ftp://example.dk", secret).validate().is_err());
+        assert!(webhook("not a url", secret).validate().is_err());
+        assert!(NotifyTarget::Feed.validate().is_ok());
+    }
+
+    #[test]
+    fn saved_search_filter() {
+        let search = SavedSearch {
+            id: 1,
+            user_id: 1,
+            name: "Rust".to_owned(),
+            query_options: QueryOptions::Query {
+                job_name: Some("udvikler".to_owned()),
+                job_regions: vec!["Aarhus".to_owned()],
+                job_tags: vec!["Rust".to_owned()],
+            },
+            filter_options: FilterOptions {
+                salary_min: Some(40_000),
+                ..Default::default()
+            },
+            notify: vec![NotifyTarget::Feed],
+            created_at: Default::default(),
+        };
+        let filter = search_filter(&search);
+        assert_eq!(filter.job_name.as_deref(), Some("udvikler"));
+        assert_eq!(filter.job_regions, ["Aarhus"]);
+        assert_eq!(filter.job_tags, ["Rust"]);
+        assert_eq!(filter.filter_options.salary_min, Some(40_000));
+        assert!(matches!(filter.size_options, SizeOptions::All));
+        assert_eq!(filter.viewer, Some(1));
+        assert_eq!(filter.hidden, HiddenMode::Exclude);
+
+        let everything = search_filter(&SavedSearch {
+            query_options: QueryOptions::All,
+            ..search
+        });
+        assert!(everything.job_name.is_none());
+        assert!(everything.job_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn email_goes_through_the_relay() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let relay = tokio::spawn(fake_relay(listener));
+
+        let smtp = Smtp::new(
+            "127.0.0.1".to_owned(),
+            port,
+            "jobhunter@localhost".parse().unwrap(),
+        );
+        let jobs = [job(3, "Rust Developer", Some("Netcompany"))];
+        Email {
+            smtp: &smtp,
+            to: "anna@example.dk".parse().unwrap(),
+        }
+        .deliver(&notification(&jobs))
+        .await
+        .expect("the relay should accept the email");
+
+        let data = relay.await.unwrap();
+        assert!(data.contains("To: anna@example.dk"), "{data}");
+        assert!(data.contains("Subject: 1 new job for"), "{data}");
+        assert!(data.contains("Rust Developer at Netcompany"), "{data}");
+    }
+
+    #[tokio::test]
+    async fn webhook_posts_signed_json() {
+        let mut server = mockito::Server::new_async().await;
+        let hook = server
+            .mock("POST", "/hooks/jobs")
+            .match_header("content-type", "application/json")
+            .match_header(
+                SIGNATURE_HEADER,
+                mockito::Matcher::Regex(
+                    "^t=[0-9]+,v1=[0-9a-f]{64}$".to_owned(),
+                ),
+            )
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"saved_search_id":7,"user_id":1,"jobs":[{"id":3}]}"#
+                    .to_owned(),
+            ))
+            .create_async()
+            .await;
+        let failing = server
+            .mock("POST", "/hooks/gone")
+            .with_status(410)
+            .create_async()
+            .await;
+
+        let jobs = [job(3, "Rust Developer", None)];
+        let webhook = |path: &str| Webhook {
+            client: reqwest::Client::new(),
+            url: Url::parse(&server.url()).unwrap().join(path).unwrap(),
+            secret: "a secret of some length".to_owned(),
+        };
+        webhook("/hooks/jobs")
+            .deliver(&notification(&jobs))
+            .await
+            .expect("the hook should accept the notification");
+        assert!(
+            webhook("/hooks/gone")
+                .deliver(&notification(&jobs))
+                .await
+                .is_err()
+        );
+        hook.assert_async().await;
+        failing.assert_async().await;
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Europe::Copenhagen;
+use minijinja::{Environment, Value};
+use serde::Serialize;
+use url::form_urlencoded;
+
+use crate::job_fetchers::JOB_TAGS;
+use crate::services::database_service::types::{
+    EmploymentType, Job, Language, SavedSearch, Seniority,
+};
+use crate::util::options::{QueryOptions, SizeOptions};
+
+pub static PAGE_SERVICE: LazyLock<PageService> =
+    LazyLock::new(PageService::new);
+
+/// Jobs on a page of search results, unless the search asks for another
+/// number.
+pub const PAGE_SIZE: usize = 20;
+
+/// The most jobs a page of search results shows, whether asked for with
+/// `page_size` or `jobs`.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Renders the pages of the web dashboard.
+pub struct PageService {
+    templates: Environment<'static>,
+}
+
+impl Default for PageService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageService {
+    pub fn new() -> Self {
+        let mut templates = Environment::new();
+        templates.set_trim_blocks(true);
+        templates.set_lstrip_blocks(true);
+        templates.add_filter("date", date);
+        // what the search form offers to pick from
+        let tags: Vec<&str> = JOB_TAGS.iter().map(|(name, _)| *name).collect();
+        templates.add_global("tags", Value::from(tags));
+        templates.add_global(
+            "seniorities",
+            Value::from_iter(Seniority::ALL.map(|value| value.as_str())),
+        );
+        templates.add_global(
+            "employment_types",
+            Value::from_iter(EmploymentType::ALL.map(|value| value.as_str())),
+        );
+        templates.add_global(
+            "languages",
+            Value::from_iter(Language::ALL.map(|value| value.as_str())),
+        );
+        for (name, source) in [
+            ("layout.html", include_str!("templates/pages/layout.html")),
+            ("search.html", include_str!("templates/pages/search.html")),
+            ("login.html", include_str!("templates/pages/login.html")),
+            (
+                "applications.html",
+                include_str!("templates/pages/applications.html"),
+            ),
+            (
+                "saved_searches.html",
+                include_str!("templates/pages/saved_searches.html"),
+            ),
+        ] {
+            templates
+                .add_template(name, source)
+                .expect("the built-in templates should parse");
+        }
+        PageService { templates }
+    }
+
+    /// The page `name` filled in from `context`, which is usually made with
+    /// `minijinja::context!`.
+    pub fn render(
+        &self,
+        name: &str,
+        context: impl Serialize,
+    ) -> Result<String, minijinja::Error> {
+        self.templates.get_template(name)?.render(context)
+    }
+}
+
+/// A day in Copenhagen time, from a timestamp in RFC 3339.
+fn date(value: String) -> String {
+    match DateTime::parse_from_rfc3339(&value) {
+        Ok(time) => time
+            .with_timezone(&Copenhagen)
+            .format("%Y-%m-%d")
+            .to_string(),
+        Err(_) => value,
+    }
+}
+
+/// A job in the search results.
+#[derive(Debug, Serialize)]
+pub struct JobCard {
+    pub title: String,
+    pub url: String,
+    pub company: String,
+    /// `None` when the company has no logo.
+    pub logo_url: Option<String>,
+    pub tags: Vec<&'static str>,
+    pub locations: Vec<String>,
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+impl From<&Job> for JobCard {
+    fn from(job: &Job) -> Self {
+        let logo_url = &job.company_info.logo_url;
+        JobCard {
+            title: job.job_info.title.0.clone(),
+            url: job.job_info.job_url.0.clone(),
+            company: job.company_info.name.clone(),
+            logo_url: (!logo_url.is_empty()).then(|| logo_url.clone()),
+            tags: job.job_info.job_tags.iter().map(|tag| tag.name).collect(),
+            locations: job
+                .locations
+                .iter()
+                .map(|location| location.address.clone())
+                .collect(),
+            deadline: job.last_date,
+        }
+    }
+}
+
+/// A location of a job on the map.
+#[derive(Debug, Serialize)]
+pub struct Marker {
+    pub lat: f64,
+    pub lon: f64,
+    pub address: String,
+    pub title: String,
+    pub url: String,
+}
+
+/// The locations of `jobs` to put on the map, leaving out those without
+/// coordinates, which are stored as `(0, 0)`.
+pub fn markers(jobs: &[Job]) -> Vec<Marker> {
+    jobs.iter()
+        .flat_map(|job| {
+            job.locations
+                .iter()
+                .filter(|location| location.geo_location != (0.0, 0.0))
+                .map(|location| Marker {
+                    lat: location.geo_location.0,
+                    lon: location.geo_location.1,
+                    address: location.address.clone(),
+                    title: job.job_info.title.0.clone(),
+                    url: job.job_info.job_url.0.clone(),
+                })
+        })
+        .collect()
+}
+
+/// The parameters of the search form in `query`, leaving out the fields
+/// left empty. A search that asks for no number of jobs gets the first page
+/// of `PAGE_SIZE`. Nothing is searched for when the form has not been sent.
+pub fn search_params(query: &str) -> Vec<(String, String)> {
+    let mut params: Vec<(String, String)> =
+        form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .collect();
+    let has = |params: &[(String, String)], name: &str| {
+        params.iter().any(|(param, _)| param == name)
+    };
+    if !params.is_empty() && !has(&params, "jobs") {
+        if !has(&params, "page_size") {
+            params.push(("page_size".to_owned(), PAGE_SIZE.to_string()));
+        }
+        if !has(&params, "page") {
+            params.push(("page".to_owned(), "0".to_owned()));
+        }
+    }
+    params
+}
+
+/// The values of each of `params`, to fill the search form in with again.
+pub fn form_values(params: &[(String, String)]) -> BTreeMap<&str, Vec<&str>> {
+    let mut values: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, value) in params {
+        values.entry(name).or_default().push(value);
+    }
+    values
+}
+
+/// Whether `size_options` asks for more jobs than a page shows.
+pub fn too_many_jobs(size_options: &SizeOptions) -> bool {
+    match *size_options {
+        SizeOptions::Page { page_size, .. } => page_size > MAX_PAGE_SIZE,
+        SizeOptions::NotPaged { jobs } => jobs > MAX_PAGE_SIZE,
+        SizeOptions::All => true,
+    }
+}
+
+/// The link to the search of `params`, on page `page`.
+pub fn page_link(params: &[(String, String)], page: usize) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in params {
+        match name.as_str() {
+            "page" => query.append_pair(name, &page.to_string()),
+            _ => query.append_pair(name, value),
+        };
+    }
+    format!("/ui?{}", query.finish())
+}
+
+/// The link to the search page showing the jobs of `search`.
+pub fn search_link(search: &SavedSearch) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    if let QueryOptions::Query {
+        job_name,
+        job_regions,
+        job_tags,
+    } = &search.query_options
+    {
+        if let Some(job_name) = job_name {
+            query.append_pair("job_name", job_name);
+        }
+        for region in job_regions {
+            query.append_pair("job_regions", region);
+        }
+        for tag in job_tags {
+            query.append_pair("job_tags", tag);
+        }
+    }
+    let filter = &search.filter_options;
+    for seniority in &filter.seniority {
+        query.append_pair("seniority", seniority.as_str());
+    }
+    for employment_type in &filter.employment_types {
+        query.append_pair("employment_type", employment_type.as_str());
+    }
+    for language in &filter.languages {
+        query.append_pair("language", language.as_str());
+    }
+    if let Some(salary_min) = filter.salary_min {
+        query.append_pair("salary_min", &salary_min.to_string());
+    }
+    if let Some(salary_max) = filter.salary_max {
+        query.append_pair("salary_max", &salary_max.to_string());
+    }
+    format!("/ui?{}", query.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use minijinja::context;
+
+    use super::*;
+    use crate::services::database_service::types::{
+        CompanyInfo, Description, JobInfo, JobTag, JobUrl, Location, Title,
+    };
+    use crate::util::options::FilterOptions;
+
+    fn job() -> Job {
+        Job {
+            job_info: JobInfo {
+                job_url: JobUrl("https://example.com/jobs/1".to_owned()),
+                title: Title("Rust <developer>".to_owned()),
+                description: Description(String::new()),
+                job_tags: vec![JobTag {
+                    name: "Rust",
+                    match_count: 1,
+                    in_title: true,
+                    weight: 1.0,
+                    hits: Vec::new(),
+                }],
+                classification: Default::default(),
+                salary: None,
+            },
+            created_at: Utc::now(),
+            last_date: None,
+            company_info: CompanyInfo {
+                name: "Acme".to_owned(),
+                logo_url: "https://example.com/acme.png".to_owned(),
+                profile_url: None,
+                rating: None,
+            },
+            locations: vec![
+                Location {
+                    address: "Aarhus".to_owned(),
+                    geo_location: (56.15, 10.2),
+                },
+                Location {
+                    address: "Somewhere".to_owned(),
+                    geo_location: (0.0, 0.0),
+                },
+            ],
+            geoarea_ids: Vec::new(),
+            contact_info: None,
+        }
+    }
+
+    #[test]
+    fn renders_the_search_results() {
+        let jobs = vec![job()];
+        let cards: Vec<JobCard> = jobs.iter().map(JobCard::from).collect();
+        let params = search_params("job_tags=Rust&seniority=senior");
+        let page = PAGE_SERVICE
+            .render(
+                "search.html",
+                context! {
+                    form => form_values(&params),
+                    jobs => cards,
+                    markers => markers(&jobs),
+                },
+            )
+            .unwrap();
+        assert!(page.contains("Rust &lt;developer&gt;"));
+        // the slashes in attributes come out escaped
+        assert!(page.contains(r#"<img src="https:&#x2f;&#x2f;example.com"#));
+        assert!(page.contains(r#"<span class="tag">Rust</span>"#));
+        assert!(page.contains(r#"value="senior" checked"#));
+        assert!(page.contains(r#""address":"Aarhus""#));
+        assert!(!page.contains("Somewhere\""));
+    }
+
+    #[test]
+    fn searches_a_page_at_a_time() {
+        assert!(search_params("").is_empty());
+        assert!(search_params("job_name=&salary_min=").is_empty());
+        let params = search_params("job_name=rust&job_regions=");
+        assert_eq!(
+            params,
+            [("job_name", "rust"), ("page_size", "20"), ("page", "0")]
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        );
+        assert_eq!(
+            page_link(&params, 1),
+            "/ui?job_name=rust&page_size=20&page=1"
+        );
+        assert_eq!(search_params("jobs=5").len(), 1);
+    }
+
+    #[test]
+    fn shows_a_bounded_number_of_jobs() {
+        let page = |page_size| SizeOptions::Page { page_size, page: 3 };
+        assert!(!too_many_jobs(&page(PAGE_SIZE)));
+        assert!(!too_many_jobs(&page(MAX_PAGE_SIZE)));
+        assert!(too_many_jobs(&page(MAX_PAGE_SIZE + 1)));
+        assert!(!too_many_jobs(&SizeOptions::NotPaged { jobs: 5 }));
+        assert!(too_many_jobs(&SizeOptions::NotPaged { jobs: 100_000 }));
+        assert!(too_many_jobs(&SizeOptions::All));
+    }
+
+    #[test]
+    fn links_saved_searches_to_the_search_page() {
+        let search = SavedSearch {
+            id: 1,
+            user_id: 1,
+            name: "rust".to_owned(),
+            query_options: QueryOptions::Query {
+                job_name: Some("rust developer".to_owned()),
+                job_regions: Vec::new(),
+                job_tags: vec!["C++".to_owned()],
+            },
+            filter_options: FilterOptions {
+                languages: vec![Language::English],
+                salary_min: Some(40000),
+                ..Default::default()
+            },
+            notify: Vec::new(),
+            created_at: Utc::now(),
+        };
+        assert_eq!(
+            search_link(&search),
+            "/ui?job_name=rust+developer&job_tags=C%2B%2B&language=en&salary_min=40000"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use futures::{StreamExt, stream};
+
+use crate::job_fetchers::job_index::fetcher::JobIndex;
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{
+    Region, RegionMatch, RegionSync,
+};
+use crate::util::geo::town_names;
+
+/// Where the catalogue of regions is synced from.
+pub static JOB_INDEX: LazyLock<JobIndex> = LazyLock::new(JobIndex::default);
+
+/// Looked up by `sync_regions` on top of the towns, so the catalogue has the
+/// larger areas even before any job in them is stored.
+const SYNC_QUERIES: &[&str] = &[
+    "Danmark",
+    "Region Hovedstaden",
+    "Region Sjælland",
+    "Region Syddanmark",
+    "Region Midtjylland",
+    "Region Nordjylland",
+    "Storkøbenhavn",
+    "Nordsjælland",
+    "Fyn",
+    "Sydjylland",
+    "Østjylland",
+    "Vestjylland",
+    "Bornholm",
+];
+
+/// `name` folded so "Region Midtjylland" and "regionmidtjylland" are the
+/// same, as JobIndex writes names both ways.
+pub fn region_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Resolves `name` to the one candidate it names exactly, or to the only
+/// candidate there is. Anything else is ambiguous and left to the caller.
+pub fn resolve_region(name: &str, candidates: Vec<Region>) -> RegionMatch {
+    let key = region_key(name);
+    let mut exact = candidates
+        .iter()
+        .filter(|candidate| region_key(&candidate.name) == key);
+    let resolved = match (exact.next(), exact.next()) {
+        (Some(region), None) => Some(region.clone()),
+        (None, _) if candidates.len() == 1 => candidates.first().cloned(),
+        _ => None,
+    };
+    RegionMatch {
+        resolved,
+        candidates,
+    }
+}
+
+/// The region ids to search JobIndex with for `name`: the one it resolves
+/// to, or every region of that exact name when it is ambiguous, so no area
+/// is silently picked over another. Where nothing matches exactly, the best
+/// completion of JobIndex is searched.
+pub fn region_query_ids(name: &str, candidates: Vec<Region>) -> Vec<i64> {
+    let key = region_key(name);
+    let region_match = resolve_region(name, candidates);
+    if let Some(region) = region_match.resolved {
+        return vec![region.id];
+    }
+    let exact: Vec<i64> = region_match
+        .candidates
+        .iter()
+        .filter(|candidate| region_key(&candidate.name) == key)
+        .map(|candidate| candidate.id)
+        .collect();
+    if exact.is_empty() {
+        region_match
+            .candidates
+            .first()
+            .map(|region| region.id)
+            .into_iter()
+            .collect()
+    } else {
+        exact
+    }
+}
+
+/// The parent of each region, from the areas of the jobs in `job_regions`.
+///
+/// JobIndex lists every area a job lies in, so a region lies within each
+/// larger region that is listed on all of its jobs. The parent is the
+/// smallest of those, by kind and then by how many jobs it has. Regions
+/// without jobs get no parent.
+pub fn infer_parents(
+    regions: &[Region],
+    job_regions: &[Vec<i64>],
+) -> HashMap<i64, i64> {
+    let kinds: HashMap<i64, _> = regions
+        .iter()
+        .map(|region| (region.id, region.kind))
+        .collect();
+    let mut jobs_in: HashMap<i64, HashSet<usize>> = HashMap::new();
+    for (job, ids) in job_regions.iter().enumerate() {
+        for id in ids.iter().filter(|id| kinds.contains_key(id)) {
+            jobs_in.entry(*id).or_default().insert(job);
+        }
+    }
+
+    jobs_in
+        .iter()
+        .filter_map(|(&id, jobs)| {
+            let kind = kinds[&id];
+            // a parent is listed on every job, so also on any one of them
+            let job = *jobs.iter().min()?;
+            let parent = job_regions[job]
+                .iter()
+                .filter(|&&other| other != id)
+                .filter(|other| kinds.get(other).is_some_and(|&k| k < kind))
+                .filter(|other| jobs_in[other].is_superset(jobs))
+                .max_by_key(|&&other| {
+                    (
+                        kinds[&other],
+                        std::cmp::Reverse(jobs_in[&other].len()),
+                        std::cmp::Reverse(other),
+                    )
+                })?;
+            Some((id, *parent))
+        })
+        .collect()
+}
+
+/// How many region lookups `sync_regions` has JobIndex answer at a time.
+const SYNC_CONCURRENCY: usize = 8;
+
+/// The regions `name` could mean, from JobIndex where it answers and from
+/// the catalogue where it does not. The parents are the ones the catalogue
+/// knows, which only `sync_regions` writes to.
+pub async fn find_regions(
+    database: &DataBase,
+    name: &str,
+) -> Result<RegionMatch, sqlx::Error> {
+    let candidates = match JOB_INDEX.region_candidates(name).await {
+        Some(mut candidates) => {
+            let ids: Vec<i64> =
+                candidates.iter().map(|region| region.id).collect();
+            let parents = database.region_parents(&ids).await?;
+            for region in &mut candidates {
+                region.parent_id = parents.get(&region.id).copied();
+            }
+            candidates
+        }
+        None => database.search_regions(name).await?,
+    };
+    Ok(resolve_region(name, candidates))
+}
+
+/// Adds the larger areas and towns JobIndex knows to the catalogue, works
+/// out the parent of every region from the stored jobs and gives the stored
+/// jobs without regions those of their postcode.
+///
+/// Only the jobs fetched since regions were kept list every region they lie
+/// in. The others are found by `region=` through their postcode alone, so
+/// not at all when their address has none the catalogue knows.
+pub async fn sync_regions(
+    database: &DataBase,
+) -> Result<RegionSync, sqlx::Error> {
+    let queries = SYNC_QUERIES.iter().copied().chain(town_names());
+    // a query JobIndex does not answer just adds nothing
+    let lookups: Vec<_> = queries
+        .map(|query| JOB_INDEX.region_candidates(query))
+        .collect();
+    let answers: Vec<Option<Vec<Region>>> = stream::iter(lookups)
+        .buffer_unordered(SYNC_CONCURRENCY)
+        .collect()
+        .await;
+    let candidates: Vec<Region> =
+        answers.into_iter().flatten().flatten().collect();
+    database.upsert_regions(&candidates).await?;
+
+    let regions = database.get_regions().await?;
+    let parents = infer_parents(&regions, &database.job_geoareas().await?);
+    database.set_region_parents(&parents).await?;
+    // after the parents, so the backfilled jobs lie in the regions above
+    // their postcode like the fetched ones do
+    let backfilled_jobs = database.backfill_job_geoareas().await?;
+    Ok(RegionSync {
+        regions: regions.len(),
+        with_parent: parents.len(),
+        backfilled_jobs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::database_service::types::RegionKind;
+
+    const DENMARK: i64 = 1221;
+    const MIDTJYLLAND: i64 = 1035;
+    const HOVEDSTADEN: i64 = 1036;
+    const OESTJYLLAND: i64 = 15195;
+    const AARHUS: i64 = 616;
+    const AARHUS_N: i64 = 16154;
+    const BALLERUP: i64 = 620;
+
+    fn region(id: i64, name: &str, kind: RegionKind) -> Region {
+        Region {
+            id,
+            name: name.to_owned(),
+            code: None,
+            kind,
+            parent_id: None,
+        }
+    }
+
+    fn catalogue() -> Vec<Region> {
+        vec![
+            region(DENMARK, "Danmark", RegionKind::Country),
+            region(MIDTJYLLAND, "Region Midtjylland", RegionKind::Region),
+            region(HOVEDSTADEN, "Region Hovedstaden", RegionKind::Region),
+            region(OESTJYLLAND, "Østjylland", RegionKind::Area),
+            region(AARHUS, "Aarhus", RegionKind::Municipality),
+            region(AARHUS_N, "8200 Aarhus N", RegionKind::Postcode),
+            region(BALLERUP, "Ballerup", RegionKind::Municipality),
+        ]
+    }
+
+    #[test]
+    fn exact_names_resolve() {
+        let region_match = resolve_region("aarhus", catalogue());
+        assert_eq!(region_match.resolved.map(|region| region.id), Some(AARHUS));
+        assert_eq!(region_match.candidates.len(), 7);
+
+        let region_match = resolve_region("RegionMidtjylland", catalogue());
+        assert_eq!(
+            region_match.resolved.map(|region| region.id),
+            Some(MIDTJYLLAND)
+        );
+
+        // only one thing it could mean
+        let only =
+            vec![region(AARHUS_N, "8200 Aarhus N", RegionKind::Postcode)];
+        assert!(resolve_region("Aarhus N", only).resolved.is_some());
+    }
+
+    #[test]
+    fn ambiguous_names_are_not_picked() {
+        let mut candidates = catalogue();
+        candidates.push(region(17000, "Aarhus", RegionKind::Area));
+        let region_match = resolve_region("Aarhus", candidates.clone());
+        assert_eq!(region_match.resolved, None);
+        assert_eq!(region_match.candidates, candidates);
+        assert_eq!(region_query_ids("Aarhus", candidates), [AARHUS, 17000]);
+
+        assert_eq!(resolve_region("Region", catalogue()).resolved, None);
+        // nothing exact, so the best completion is searched like before
+        assert_eq!(region_query_ids("Region", catalogue()), [DENMARK]);
+        assert!(region_query_ids("Region", Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn parents_from_the_areas_of_jobs() {
+        let job_regions = vec![
+            vec![DENMARK, MIDTJYLLAND, OESTJYLLAND, AARHUS, AARHUS_N],
+            vec![DENMARK, MIDTJYLLAND, OESTJYLLAND, AARHUS],
+            // in Ballerup and Aarhus N both
+            vec![
+                DENMARK,
+                HOVEDSTADEN,
+                BALLERUP,
+                MIDTJYLLAND,
+                OESTJYLLAND,
+                AARHUS,
+                AARHUS_N,
+            ],
+            vec![DENMARK, HOVEDSTADEN, BALLERUP],
+            // an area not in the catalogue is passed over
+            vec![DENMARK, MIDTJYLLAND, 99999],
+        ];
+        let parents = infer_parents(&catalogue(), &job_regions);
+
+        assert_eq!(parents.get(&AARHUS_N), Some(&AARHUS));
+        assert_eq!(parents.get(&AARHUS), Some(&OESTJYLLAND));
+        assert_eq!(parents.get(&OESTJYLLAND), Some(&MIDTJYLLAND));
+        assert_eq!(parents.get(&BALLERUP), Some(&HOVEDSTADEN));
+        assert_eq!(parents.get(&MIDTJYLLAND), Some(&DENMARK));
+        assert_eq!(parents.get(&DENMARK), None);
+        assert_eq!(parents.get(&99999), None);
+    }
+}
@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::services::database_service::database::DataBase;
+use crate::services::database_service::types::{DeliveryStatus, DueDelivery};
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`, keyed with the
+/// secret of the webhook.
+pub const SIGNATURE_HEADER: &str = "X-Jobhunter-Signature";
+/// The `JobEvent` of the body.
+pub const EVENT_HEADER: &str = "X-Jobhunter-Event";
+/// The id of the delivery, the same on every attempt, so a receiver can drop
+/// the ones it already has.
+pub const DELIVERY_HEADER: &str = "X-Jobhunter-Delivery";
+
+/// Secrets shorter than this are too easy to guess.
+pub const MIN_SECRET_LEN: usize = 16;
+/// Attempts at a delivery before it goes to the dead-letter queue.
+pub const MAX_ATTEMPTS: i32 = 12;
+const FIRST_RETRY_SECS: i64 = 30;
+const MAX_RETRY_SECS: i64 = 6 * 60 * 60;
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a claimed delivery is left to its sender, well over `TIMEOUT`.
+const LEASE_SECS: f64 = 120.0;
+/// Deliveries claimed and sent at once.
+const BATCH: i64 = 32;
+const WORKER_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The hex HMAC-SHA256 of `message`.
+fn hmac_sha256_hex(secret: &str, message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC should take keys of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The `SIGNATURE_HEADER` of `body` sent at `timestamp`. The timestamp is
+/// signed too, so a receiver can turn away old requests that are replayed.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let signature =
+        hmac_sha256_hex(secret, format!("{timestamp}.{body}").as_bytes());
+    format!("t={timestamp},v1={signature}")
+}
+
+/// A JSON `body` posted to `url`, signed with `secret` at `now`.
+pub fn signed_post(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    body: String,
+    now: DateTime<Utc>,
+) -> reqwest::RequestBuilder {
+    client
+        .post(url)
+        .timeout(TIMEOUT)
+        .header(CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign(secret, now.timestamp(), &body))
+        .body(body)
+}
+
+/// How long to wait after `attempts` failed attempts before the next one,
+/// doubling from 30 seconds up to 6 hours. `None` when it is out of
+/// attempts.
+pub fn retry_delay(attempts: i32) -> Option<TimeDelta> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let doublings = (attempts - 1).clamp(0, 30) as u32;
+    let secs = FIRST_RETRY_SECS
+        .saturating_mul(1 << doublings)
+        .min(MAX_RETRY_SECS);
+    Some(TimeDelta::seconds(secs))
+}
+
+/// What came of one attempt at a delivery.
+#[derive(Debug, PartialEq)]
+pub enum Attempt {
+    Delivered {
+        status: u16,
+    },
+    /// With the status, where the webhook answered.
+    Failed {
+        status: Option<u16>,
+        error: String,
+    },
+}
+
+/// Sends `delivery` once, signed at `now`. Anything but a 2xx answer is a
+/// failure.
+pub async fn send(
+    client: &reqwest::Client,
+    delivery: &DueDelivery,
+    now: DateTime<Utc>,
+) -> Attempt {
+    let body = delivery.payload.clone();
+    let response =
+        signed_post(client, &delivery.url, &delivery.secret, body, now)
+            .header(EVENT_HEADER, delivery.event.as_str())
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .send()
+            .await;
+    match response {
+        Ok(response) if response.status().is_success() => Attempt::Delivered {
+            status: response.status().as_u16(),
+        },
+        Ok(response) => Attempt::Failed {
+            status: Some(response.status().as_u16()),
+            error: format!("answered {}", response.status()),
+        },
+        Err(error) => Attempt::Failed {
+            status: None,
+            error: error.to_string(),
+        },
+    }
+}
+
+/// What a round of `deliver_due` did.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct DeliveryRun {
+    pub delivered: usize,
+    /// Failed, and to be tried again later.
+    pub retried: usize,
+    /// Failed for the last time, and now in the dead-letter queue.
+    pub dead: usize,
+}
+
+/// Sends every delivery that is due, a batch at a time, and records how
+/// each attempt went.
+pub async fn deliver_due(
+    database: &DataBase,
+    client: &reqwest::Client,
+) -> Result<DeliveryRun, sqlx::Error> {
+    let mut run = DeliveryRun::default();
+    loop {
+        let due = database.claim_due_deliveries(BATCH, LEASE_SECS).await?;
+        let now = Utc::now();
+        let attempts =
+            join_all(due.iter().map(|delivery| send(client, delivery, now)))
+                .await;
+
+        for (delivery, attempt) in due.iter().zip(attempts) {
+            match attempt {
+                Attempt::Delivered { status } => {
+                    run.delivered += 1;
+                    database
+                        .record_delivery_attempt(
+                            delivery.id,
+                            DeliveryStatus::Delivered,
+                            None,
+                            Some(status.into()),
+                            None,
+                        )
+                        .await?;
+                }
+                Attempt::Failed { status, error } => {
+                    let (status_after, next_attempt_at) =
+                        match retry_delay(delivery.attempts + 1) {
+                            Some(delay) => {
+                                run.retried += 1;
+                                (DeliveryStatus::Pending, Some(now + delay))
+                            }
+                            None => {
+                                run.dead += 1;
+                                (DeliveryStatus::Dead, None)
+                            }
+                        };
+                    database
+                        .record_delivery_attempt(
+                            delivery.id,
+                            status_after,
+                            next_attempt_at,
+                            status.map(i32::from),
+                            Some(&error),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        // a failed delivery is not due again before the next round
+        if (due.len() as i64) < BATCH {
+            return Ok(run);
+        }
+    }
+}
+
+/// Marks the jobs whose deadline passed as expired and sends what is due,
+/// every few seconds for as long as the server runs.
+pub async fn run_worker(database: DataBase) {
+    let client = reqwest::Client::new();
+    let mut interval = tokio::time::interval(WORKER_INTERVAL);
+    loop {
+        interval.tick().await;
+        // whatever fails is still there to try again on the next tick
+        let _ = database.expire_jobs().await;
+        let _ = deliver_due(&database, &client).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::database_service::types::JobEvent;
+
+    fn due(url: String) -> DueDelivery {
+        DueDelivery {
+            id: 42,
+            url,
+            secret: "a secret of some length".to_owned(),
+            event: JobEvent::Created,
+            payload: r#"{"event":"created","job_id":7}"#.to_owned(),
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // test case 2 of RFC 4231
+        assert_eq!(
+            hmac_sha256_hex("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            sign("Jefe", 1700000000, "{}"),
+            format!(
+                "t=1700000000,v1={}",
+                hmac_sha256_hex("Jefe", b"1700000000.{}")
+            )
+        );
+        assert_ne!(
+            sign("Jefe", 1700000000, "{}"),
+            sign("Jefe", 1700000001, "{}")
+        );
+    }
+
+    #[test]
+    fn retries_back_off_until_dead() {
+        assert_eq!(retry_delay(0), Some(TimeDelta::seconds(30)));
+        assert_eq!(retry_delay(1), Some(TimeDelta::seconds(30)));
+        assert_eq!(retry_delay(2), Some(TimeDelta::seconds(60)));
+        assert_eq!(retry_delay(5), Some(TimeDelta::minutes(8)));
+        assert_eq!(retry_delay(MAX_ATTEMPTS - 1), Some(TimeDelta::hours(6)));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed() {
+        let mut server = mockito::Server::new_async().await;
+        let delivery = due(format!("{}/hooks/jobs", server.url()));
+        let now = DateTime::from_timestamp(1700000000, 0).unwrap();
+        let hook = server
+            .mock("POST", "/hooks/jobs")
+            .match_header("content-type", "application/json")
+            .match_header(
+                SIGNATURE_HEADER,
+                sign(&delivery.secret, 1700000000, &delivery.payload).as_str(),
+            )
+            .match_header(EVENT_HEADER, "created")
+            .match_header(DELIVERY_HEADER, "42")
+            .match_body(delivery.payload.as_str())
+            .with_status(202)
+            .create_async()
+            .await;
+        let failing = server
+            .mock("POST", "/hooks/gone")
+            .with_status(410)
+            .create_async()
+            .await;
+
+        let client = reqwest::Client::new();
+        assert_eq!(
+            send(&client, &delivery, now).await,
+            Attempt::Delivered { status: 202 }
+        );
+        hook.assert_async().await;
+
+        let gone = due(format!("{}/hooks/gone", server.url()));
+        assert!(matches!(
+            send(&client, &gone, now).await,
+            Attempt::Failed {
+                status: Some(410),
+                ..
+            }
+        ));
+        failing.assert_async().await;
+
+        let unreachable = due("http://127.0.0.1:9/hooks".to_owned());
+        assert!(matches!(
+            send(&client, &unreachable, now).await,
+            Attempt::Failed { status: None, .. }
+        ));
+    }
+}
@@ -1,5 +1,10 @@
 use futures::StreamExt;
 
+#[allow(
+    async_fn_in_trait,
+    reason = "the queries are made on the thread of the fetcher, whose \
+              futures are not Send"
+)]
 pub trait CreateQuery<From>
 where
     Self: Sized,
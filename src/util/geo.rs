@@ -0,0 +1,382 @@
+use serde::Serialize;
+
+/// Radius searches further out than this are better done as a bounding box.
+pub const MAX_RADIUS_KM: f64 = 500.0;
+/// The mean radius of the earth.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Where the larger Danish towns are, for geocoding addresses that are not
+/// known from any job. Names are lowercase.
+const DANISH_TOWNS: &[(&str, f64, f64)] = &[
+    ("københavn", 55.6761, 12.5683),
+    ("kbh", 55.6761, 12.5683),
+    ("copenhagen", 55.6761, 12.5683),
+    ("frederiksberg", 55.6786, 12.5314),
+    ("aarhus", 56.1629, 10.2039),
+    ("århus", 56.1629, 10.2039),
+    ("odense", 55.4038, 10.3883),
+    ("aalborg", 57.0488, 9.9217),
+    ("ålborg", 57.0488, 9.9217),
+    ("esbjerg", 55.4765, 8.4594),
+    ("randers", 56.4607, 10.0364),
+    ("kolding", 55.4904, 9.4722),
+    ("horsens", 55.8607, 9.8503),
+    ("vejle", 55.7113, 9.5364),
+    ("roskilde", 55.6415, 12.0803),
+    ("herning", 56.1393, 8.9738),
+    ("hørsholm", 55.8810, 12.5008),
+    ("helsingør", 56.0361, 12.6136),
+    ("silkeborg", 56.1697, 9.5451),
+    ("næstved", 55.2299, 11.7609),
+    ("fredericia", 55.5657, 9.7526),
+    ("viborg", 56.4532, 9.4020),
+    ("køge", 55.4580, 12.1821),
+    ("holstebro", 56.3601, 8.6161),
+    ("taastrup", 55.6517, 12.2925),
+    ("slagelse", 55.4028, 11.3546),
+    ("hillerød", 55.9267, 12.3109),
+    ("sønderborg", 54.9138, 9.7922),
+    ("svendborg", 55.0598, 10.6068),
+    ("hjørring", 57.4642, 9.9823),
+    ("holbæk", 55.7175, 11.7128),
+    ("frederikshavn", 57.4407, 10.5366),
+    ("ballerup", 55.7317, 12.3633),
+    ("lyngby", 55.7704, 12.5038),
+    ("billund", 55.7308, 9.1153),
+    ("skive", 56.5667, 9.0333),
+];
+
+/// A point in degrees, like the `x` (latitude) and `y` (longitude) of a
+/// `job_location`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lon: f64) -> Result<Self, &'static str> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err("lat should be between -90 and 90");
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err("lon should be between -180 and 180");
+        }
+        Ok(GeoPoint { lat, lon })
+    }
+
+    /// The great-circle distance to `other`.
+    pub fn distance_km(&self, other: GeoPoint) -> f64 {
+        let (lat, other_lat) = (self.lat.to_radians(), other.lat.to_radians());
+        let half_lat = (other_lat - lat) / 2.0;
+        let half_lon = (other.lon - self.lon).to_radians() / 2.0;
+        let haversine = half_lat.sin().powi(2)
+            + lat.cos() * other_lat.cos() * half_lon.sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * haversine.sqrt().asin()
+    }
+}
+
+/// The names of the towns `town_location` knows.
+pub fn town_names() -> impl Iterator<Item = &'static str> {
+    DANISH_TOWNS.iter().map(|&(town, ..)| town)
+}
+
+/// The center of the Danish town named in `address`, e.g. "8000 Aarhus C".
+/// Where several towns are named, the last one wins, as addresses end with
+/// the town.
+pub fn town_location(address: &str) -> Option<GeoPoint> {
+    address
+        .split(|c: char| !c.is_alphanumeric())
+        .rev()
+        .map(str::to_lowercase)
+        .find_map(|word| {
+            DANISH_TOWNS
+                .iter()
+                .find(|(town, ..)| *town == word)
+                .map(|&(_, lat, lon)| GeoPoint { lat, lon })
+        })
+}
+
+/// The area shown by a map view. Boxes across the antimeridian are not
+/// supported, which is no loss for Danish jobs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub south_west: GeoPoint,
+    pub north_east: GeoPoint,
+}
+
+impl BoundingBox {
+    pub fn new(
+        south: f64,
+        west: f64,
+        north: f64,
+        east: f64,
+    ) -> Result<Self, &'static str> {
+        let south_west = GeoPoint::new(south, west)?;
+        let north_east = GeoPoint::new(north, east)?;
+        if south > north {
+            return Err("south should be below north");
+        }
+        if west > east {
+            return Err("west should be below east");
+        }
+        Ok(BoundingBox {
+            south_west,
+            north_east,
+        })
+    }
+
+    pub fn center(&self) -> GeoPoint {
+        GeoPoint {
+            lat: (self.south_west.lat + self.north_east.lat) / 2.0,
+            lon: (self.south_west.lon + self.north_east.lon) / 2.0,
+        }
+    }
+
+    /// The distance from the center to the furthest corner, so the circle of
+    /// this radius around the center holds the whole box.
+    pub fn radius_km(&self) -> f64 {
+        let center = self.center();
+        [
+            self.south_west,
+            self.north_east,
+            GeoPoint {
+                lat: self.south_west.lat,
+                lon: self.north_east.lon,
+            },
+            GeoPoint {
+                lat: self.north_east.lat,
+                lon: self.south_west.lon,
+            },
+        ]
+        .into_iter()
+        .map(|corner| center.distance_km(corner))
+        .fold(0.0, f64::max)
+    }
+}
+
+/// Where to look for jobs, either around a point or within a map view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoArea {
+    Radius { center: GeoPoint, radius_km: f64 },
+    Box(BoundingBox),
+}
+
+impl GeoArea {
+    /// The area of the `lat`, `lon` and `radius_km` or the `south`, `west`,
+    /// `north` and `east` query parameters.
+    pub fn from_params(
+        lat: Option<f64>,
+        lon: Option<f64>,
+        radius_km: Option<f64>,
+        south: Option<f64>,
+        west: Option<f64>,
+        north: Option<f64>,
+        east: Option<f64>,
+    ) -> Result<Self, &'static str> {
+        match (lat, lon, radius_km, south, west, north, east) {
+            (Some(lat), Some(lon), Some(radius_km), None, None, None, None) => {
+                if !(radius_km > 0.0 && radius_km <= MAX_RADIUS_KM) {
+                    return Err("radius_km should be above 0 and at most 500");
+                }
+                Ok(GeoArea::Radius {
+                    center: GeoPoint::new(lat, lon)?,
+                    radius_km,
+                })
+            }
+            (
+                None,
+                None,
+                None,
+                Some(south),
+                Some(west),
+                Some(north),
+                Some(east),
+            ) => Ok(GeoArea::Box(BoundingBox::new(south, west, north, east)?)),
+            _ => Err(
+                "please provide either lat, lon and radius_km or south, west, north and east",
+            ),
+        }
+    }
+
+    /// The point distances are measured from.
+    pub fn center(&self) -> GeoPoint {
+        match self {
+            GeoArea::Radius { center, .. } => *center,
+            GeoArea::Box(bounding_box) => bounding_box.center(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COPENHAGEN: (f64, f64) = (55.6761, 12.5683);
+    const AARHUS: (f64, f64) = (56.1629, 10.2039);
+    const AALBORG: (f64, f64) = (57.0488, 9.9217);
+    const ODENSE: (f64, f64) = (55.4038, 10.3883);
+
+    fn point((lat, lon): (f64, f64)) -> GeoPoint {
+        GeoPoint { lat, lon }
+    }
+
+    #[test]
+    fn distances_between_cities() {
+        let distance = |from, to| point(from).distance_km(point(to));
+        assert!((distance(COPENHAGEN, AARHUS) - 156.9).abs() < 0.1);
+        assert!((distance(COPENHAGEN, ODENSE) - 140.5).abs() < 0.1);
+        assert!((distance(AARHUS, AALBORG) - 100.0).abs() < 0.1);
+        assert_eq!(distance(ODENSE, AALBORG), distance(AALBORG, ODENSE));
+        assert_eq!(distance(ODENSE, ODENSE), 0.0);
+    }
+
+    #[test]
+    fn towns_in_addresses() {
+        assert_eq!(
+            town_location("Tangen 4, 8200 Aarhus N"),
+            Some(point(AARHUS))
+        );
+        assert_eq!(town_location("9000 aalborg"), Some(point(AALBORG)));
+        assert_eq!(
+            town_location("Odensevej 2, København K"),
+            Some(point(COPENHAGEN))
+        );
+        assert_eq!(town_location("Industrivej 8, 9510 Arden"), None);
+    }
+
+    #[test]
+    fn radius_around_a_city() {
+        let (lat, lon) = COPENHAGEN;
+        let area = GeoArea::from_params(
+            Some(lat),
+            Some(lon),
+            Some(25.0),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            area,
+            Ok(GeoArea::Radius {
+                center: GeoPoint { lat, lon },
+                radius_km: 25.0
+            })
+        );
+        assert_eq!(area.unwrap().center(), GeoPoint { lat, lon });
+    }
+
+    #[test]
+    fn box_between_cities() {
+        // Funen and the east of Jutland, from Odense up to Aalborg
+        let area = GeoArea::from_params(
+            None,
+            None,
+            None,
+            Some(ODENSE.0),
+            Some(AALBORG.1),
+            Some(AALBORG.0),
+            Some(ODENSE.1),
+        )
+        .expect("should be a box");
+        let center = area.center();
+        assert!((center.lat - 56.2263).abs() < 1e-4);
+        assert!((center.lon - 10.155).abs() < 1e-4);
+        let GeoArea::Box(bounding_box) = area else {
+            unreachable!()
+        };
+        // the corners further south are further apart
+        let radius = bounding_box.radius_km();
+        assert!((radius - 92.6).abs() < 0.1);
+        for city in [ODENSE, AARHUS, AALBORG] {
+            assert!(center.distance_km(point(city)) <= radius);
+        }
+
+        // the corners the wrong way around
+        assert!(
+            BoundingBox::new(AALBORG.0, AARHUS.1, ODENSE.0, ODENSE.1).is_err()
+        );
+        assert!(
+            BoundingBox::new(ODENSE.0, COPENHAGEN.1, AARHUS.0, AARHUS.1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn bad_areas_are_rejected() {
+        let (lat, lon) = AARHUS;
+        assert!(
+            GeoArea::from_params(
+                Some(lat),
+                Some(lon),
+                None,
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            GeoArea::from_params(
+                Some(lat),
+                Some(lon),
+                Some(0.0),
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            GeoArea::from_params(
+                Some(lat),
+                Some(lon),
+                Some(f64::NAN),
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            GeoArea::from_params(
+                Some(lon),
+                Some(lat),
+                Some(501.0),
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            GeoArea::from_params(
+                Some(91.0),
+                Some(lon),
+                Some(10.0),
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            GeoArea::from_params(
+                Some(lat),
+                Some(lon),
+                Some(10.0),
+                Some(lat),
+                Some(lon),
+                Some(lat),
+                Some(lon),
+            )
+            .is_err()
+        );
+    }
+}
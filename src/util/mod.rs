@@ -1,4 +1,5 @@
 pub mod equality;
 pub mod from_query;
+pub mod geo;
 pub mod options;
 pub mod streamer;
@@ -3,17 +3,25 @@ use std::ops::Range;
 use axum::response::IntoResponse;
 use axum_extra::extract::{Query, QueryRejection};
 use axum_macros::FromRequestParts;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use reqwest::StatusCode;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::json;
 
+use crate::job_fetchers::preview::{DateFormat, parse_date};
+use crate::services::database_service::types::{
+    EmploymentType, Job, Language, Seniority,
+};
+
 #[derive(FromRequestParts, Debug, Serialize)]
 #[from_request(via(Query), rejection(ApiError))]
 
 pub struct FetchOptions {
     pub query_options: QueryOptions,
     pub size_options: SizeOptions,
+    pub filter_options: FilterOptions,
 }
 
 // We implement `IntoResponse` for our extractor so it can be used as a response
@@ -41,6 +49,45 @@ impl From<QueryRejection> for ApiError {
     }
 }
 
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => Self {
+                status: StatusCode::NOT_FOUND,
+                message: "not found".to_owned(),
+            },
+            sqlx::Error::Database(error) if error.is_unique_violation() => {
+                Self {
+                    status: StatusCode::CONFLICT,
+                    message: "already exists".to_owned(),
+                }
+            }
+            // the details of a database error are not for the client
+            _ => Self {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: "database error".to_owned(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.status)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
 // We implement `IntoResponse` so `ApiError` can be used as a response
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
@@ -58,34 +105,14 @@ impl<'de> Deserialize<'de> for FetchOptions {
     where
         D: Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        #[serde(deny_unknown_fields)]
-        struct Params {
-            job_name: Option<String>,
-
-            #[serde(default)]
-            job_regions: Vec<String>,
-            #[serde(default)]
-            job_tags: Vec<String>,
-            page_size: Option<usize>,
-            page: Option<usize>,
-            jobs: Option<usize>,
+        let params = JobParams::deserialize(deserializer)?;
+        if let Some(name) = params.stored_only() {
+            return Err(D::Error::custom(format!(
+                "`{name}` only filters the stored jobs"
+            )));
         }
-
-        let params: Params = Params::deserialize(deserializer)?;
-        let size_options = match (params.page_size, params.page, params.jobs) {
-            (Some(page_size), Some(page), None) => {
-                SizeOptions::Page { page_size, page }
-            }
-
-            (None, None, Some(jobs)) => SizeOptions::NotPaged { jobs },
-            (None, None, None) => SizeOptions::All,
-            _ => {
-                return Err(D::Error::custom(
-                    "please provied either page_size and page or jobs",
-                ));
-            }
-        };
+        let size_options = params.size_options().map_err(D::Error::custom)?;
+        let filter_options = params.filter_options();
         let query_options = if params.job_name.is_some()
             || params.job_regions.is_empty()
             || !params.job_tags.is_empty()
@@ -102,6 +129,7 @@ impl<'de> Deserialize<'de> for FetchOptions {
         Ok(FetchOptions {
             size_options,
             query_options,
+            filter_options,
         })
     }
 }
@@ -111,11 +139,313 @@ impl FetchOptions {
         FetchOptions {
             query_options: QueryOptions::All,
             size_options: SizeOptions::All,
+            filter_options: FilterOptions::default(),
         }
     }
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+/// Filters over the jobs stored in the database, see `DataBase::filter_jobs`.
+#[derive(FromRequestParts, Debug)]
+#[from_request(via(Query), rejection(ApiError))]
+pub struct JobFilter {
+    /// Part of the job title.
+    pub job_name: Option<String>,
+    /// Parts of the addresses of the job locations, any of which matches.
+    pub job_regions: Vec<String>,
+    /// Ids of regions from the catalogue, any of which or of whose
+    /// sub-regions the job lies in.
+    pub regions: Vec<i64>,
+    pub job_tags: Vec<String>,
+    pub tag_mode: TagMode,
+    /// Any name the company posts under.
+    pub company: Option<String>,
+    pub posted_after: Option<DateTime<Utc>>,
+    pub deadline_before: Option<DateTime<Utc>>,
+    pub remote: Option<bool>,
+    /// Also the jobs whose deadline passed, which `DataBase::expire_jobs`
+    /// marks expired.
+    pub expired: bool,
+    /// The name of the home location `max_commute` and `JobSort::Commute`
+    /// are measured from.
+    pub home: Option<String>,
+    /// The longest estimated commute from `home` in minutes, see
+    /// `commute_service::commute_job_ids`.
+    pub max_commute: Option<f64>,
+    /// Only the jobs the `viewer` has starred.
+    pub starred: bool,
+    pub hidden: HiddenMode,
+    /// The user `starred` and `hidden` are of, who is signed in rather than
+    /// named in the query.
+    pub viewer: Option<i64>,
+    pub sort: JobSort,
+    pub size_options: SizeOptions,
+    pub filter_options: FilterOptions,
+}
+
+/// Whether a job needs all of the `job_tags` or just one of them.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMode {
+    #[default]
+    All,
+    Any,
+}
+
+/// Whether the jobs a user has hidden, or whose company the user has
+/// hidden, are listed.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenMode {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+impl HiddenMode {
+    /// Whether a job should be hidden to be listed, or `None` if it does
+    /// not matter.
+    pub fn wanted(&self) -> Option<bool> {
+        match self {
+            HiddenMode::Exclude => Some(false),
+            HiddenMode::Include => None,
+            HiddenMode::Only => Some(true),
+        }
+    }
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSort {
+    #[default]
+    Newest,
+    Oldest,
+    /// The closest deadline first.
+    Deadline,
+    /// The highest monthly salary first.
+    Salary,
+    /// The best fit for the profile of the signed in user first, see
+    /// `match_service::score`.
+    Match,
+    /// The shortest estimated commute from the `home` of the filter first.
+    Commute,
+    /// The jobs on which the `job_tags` of the filter weigh the most first,
+    /// see `JobTag::weight`.
+    TagStrength,
+}
+
+/// Dates in query parameters, either a day, which starts at midnight in
+/// Denmark, a timestamp with an offset or a relative date like "2 weeks ago".
+pub struct QueryDate;
+
+impl DateFormat for QueryDate {
+    const DATE_FORMATS: &'static [&'static str] = &["%Y-%m-%d"];
+    const TIME_ZONE: Tz = chrono_tz::Europe::Copenhagen;
+    const RELATIVE_DATES: bool = true;
+}
+
+/// The query parameters of a job search. `JobFilter` takes all of them, and
+/// `FetchOptions` those JobIndex can be searched with.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobParams {
+    job_name: Option<String>,
+    #[serde(default)]
+    job_regions: Vec<String>,
+    #[serde(default)]
+    region: Vec<i64>,
+    #[serde(default)]
+    job_tags: Vec<String>,
+    #[serde(default)]
+    tag_mode: TagMode,
+    company: Option<String>,
+    posted_after: Option<String>,
+    deadline_before: Option<String>,
+    remote: Option<bool>,
+    #[serde(default)]
+    expired: bool,
+    home: Option<String>,
+    max_commute: Option<f64>,
+    #[serde(default)]
+    starred: bool,
+    #[serde(default)]
+    hidden: HiddenMode,
+    #[serde(default)]
+    sort: JobSort,
+    page_size: Option<usize>,
+    page: Option<usize>,
+    jobs: Option<usize>,
+
+    #[serde(default)]
+    seniority: Vec<Seniority>,
+    #[serde(default)]
+    employment_type: Vec<EmploymentType>,
+    #[serde(default)]
+    language: Vec<Language>,
+    salary_min: Option<i32>,
+    salary_max: Option<i32>,
+}
+
+impl JobParams {
+    fn size_options(&self) -> Result<SizeOptions, &'static str> {
+        SizeOptions::from_params(self.page_size, self.page, self.jobs)
+    }
+
+    fn filter_options(&self) -> FilterOptions {
+        FilterOptions {
+            seniority: self.seniority.clone(),
+            employment_types: self.employment_type.clone(),
+            languages: self.language.clone(),
+            salary_min: self.salary_min,
+            salary_max: self.salary_max,
+        }
+    }
+
+    /// The first of the parameters that is set and that only the stored jobs
+    /// can be filtered on. A JobIndex search needs all of the `job_tags`.
+    fn stored_only(&self) -> Option<&'static str> {
+        [
+            ("region", !self.region.is_empty()),
+            ("tag_mode", self.tag_mode != TagMode::All),
+            ("company", self.company.is_some()),
+            ("posted_after", self.posted_after.is_some()),
+            ("deadline_before", self.deadline_before.is_some()),
+            ("remote", self.remote.is_some()),
+            ("expired", self.expired),
+            ("home", self.home.is_some()),
+            ("max_commute", self.max_commute.is_some()),
+            ("starred", self.starred),
+            ("hidden", self.hidden != HiddenMode::default()),
+            ("sort", self.sort != JobSort::default()),
+        ]
+        .into_iter()
+        .find_map(|(name, set)| set.then_some(name))
+    }
+}
+
+impl<'de> Deserialize<'de> for JobFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let params = JobParams::deserialize(deserializer)?;
+        let size_options = params.size_options().map_err(D::Error::custom)?;
+        let filter_options = params.filter_options();
+        if params
+            .max_commute
+            .is_some_and(|minutes| minutes.is_nan() || minutes <= 0.0)
+        {
+            return Err(D::Error::custom("max_commute should be above 0"));
+        }
+        let by_commute =
+            params.max_commute.is_some() || params.sort == JobSort::Commute;
+        if by_commute && params.home.is_none() {
+            return Err(D::Error::custom(
+                "max_commute and sort=commute need a home",
+            ));
+        }
+        if params.sort == JobSort::TagStrength && params.job_tags.is_empty() {
+            return Err(D::Error::custom("sort=tag_strength needs job_tags"));
+        }
+        if params.home.is_some() && params.sort == JobSort::Match {
+            return Err(D::Error::custom("sort=match does not go with home"));
+        }
+        let parse = |date: Option<String>| {
+            date.map(|date| parse_date::<QueryDate>(&date))
+                .transpose()
+                .map_err(D::Error::custom)
+        };
+
+        Ok(JobFilter {
+            job_name: params.job_name,
+            job_regions: params.job_regions,
+            regions: params.region,
+            job_tags: params.job_tags,
+            tag_mode: params.tag_mode,
+            company: params.company,
+            posted_after: parse(params.posted_after)?,
+            deadline_before: parse(params.deadline_before)?,
+            remote: params.remote,
+            expired: params.expired,
+            home: params.home,
+            max_commute: params.max_commute,
+            starred: params.starred,
+            hidden: params.hidden,
+            viewer: None,
+            sort: params.sort,
+            size_options,
+            filter_options,
+        })
+    }
+}
+
+/// Filters on what the classifier derived from a job, which JobIndex can not
+/// search on. They are applied to the fetched jobs, and a filtered fetch reads
+/// on until it has as many jobs as it asked for. An empty list lets
+/// everything through.
+///
+/// The salary bounds are in DKK per month and keep the jobs whose salary range
+/// overlaps them, so a job without a comparable salary is left out once
+/// either bound is set.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FilterOptions {
+    pub seniority: Vec<Seniority>,
+    pub employment_types: Vec<EmploymentType>,
+    pub languages: Vec<Language>,
+    pub salary_min: Option<i32>,
+    pub salary_max: Option<i32>,
+}
+
+impl FilterOptions {
+    /// Whether every job passes.
+    pub fn is_empty(&self) -> bool {
+        self.seniority.is_empty()
+            && self.employment_types.is_empty()
+            && self.languages.is_empty()
+            && self.salary_min.is_none()
+            && self.salary_max.is_none()
+    }
+
+    pub fn matches(&self, job: &Job) -> bool {
+        fn allows<T: PartialEq>(allowed: &[T], value: Option<T>) -> bool {
+            allowed.is_empty()
+                || value.is_some_and(|value| allowed.contains(&value))
+        }
+        let classification = &job.job_info.classification;
+
+        allows(&self.seniority, classification.seniority)
+            && allows(&self.employment_types, classification.employment_type)
+            && allows(&self.languages, classification.language)
+            && self.allows_salary(job)
+    }
+
+    fn allows_salary(&self, job: &Job) -> bool {
+        if self.salary_min.is_none() && self.salary_max.is_none() {
+            return true;
+        }
+        job.job_info
+            .salary
+            .and_then(|salary| salary.monthly_dkk())
+            .is_some_and(|(min, max)| {
+                self.salary_min.is_none_or(|bound| max >= bound)
+                    && self.salary_max.is_none_or(|bound| min <= bound)
+            })
+    }
+
+    pub fn filter(&self, mut jobs: Vec<Job>) -> Vec<Job> {
+        jobs.retain(|job| self.matches(job));
+        jobs
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub enum QueryOptions {
     #[default]
     All,
@@ -138,6 +468,56 @@ pub enum SizeOptions {
 }
 
 impl SizeOptions {
+    /// The size options of the `page_size` and `page` or `jobs` query
+    /// parameters, which are shared by every endpoint that pages. Rows past
+    /// what a query can `LIMIT` and `OFFSET` to are refused.
+    pub fn from_params(
+        page_size: Option<usize>,
+        page: Option<usize>,
+        jobs: Option<usize>,
+    ) -> Result<Self, &'static str> {
+        const TOO_LARGE: &str = "page_size, page and jobs are too large";
+        let fits = |rows: usize| i64::try_from(rows).is_ok();
+        match (page_size, page, jobs) {
+            (Some(page_size), Some(page), None) => {
+                let offset = page_size.checked_mul(page);
+                if !fits(page_size) || !offset.is_some_and(fits) {
+                    return Err(TOO_LARGE);
+                }
+                Ok(SizeOptions::Page { page_size, page })
+            }
+
+            (None, None, Some(jobs)) if !fits(jobs) => Err(TOO_LARGE),
+            (None, None, Some(jobs)) => Ok(SizeOptions::NotPaged { jobs }),
+            (None, None, None) => Ok(SizeOptions::All),
+            _ => Err("please provied either page_size and page or jobs"),
+        }
+    }
+
+    /// The `LIMIT` and `OFFSET` of a query for these rows, pages counting from
+    /// zero like in `job_num_to_query`. No limit means every row, and rows
+    /// past `i64::MAX` are out of reach.
+    pub fn limit_offset(&self) -> (Option<i64>, i64) {
+        let rows = |rows: usize| i64::try_from(rows).unwrap_or(i64::MAX);
+        match *self {
+            Self::Page { page_size, page } => {
+                (Some(rows(page_size)), rows(page_size.saturating_mul(page)))
+            }
+            Self::NotPaged { jobs } => (Some(rows(jobs)), 0),
+            Self::All => (None, 0),
+        }
+    }
+
+    /// The `items` on these rows, for rows ordered outside of a query.
+    pub fn page<T>(&self, items: Vec<T>) -> Vec<T> {
+        let (limit, offset) = self.limit_offset();
+        items
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
     // TODO, change page_size to an assoicated constant on a trait implemented on JobFetcher
     pub fn job_num_to_query(
         &self,
@@ -234,6 +614,203 @@ mod tests {
 
     use super::*;
     use crate::job_fetchers::job_index::fetcher::JobIndex;
+
+    #[tokio::test]
+    async fn filter_options_from_query() {
+        use axum::extract::FromRequestParts;
+
+        let (mut parts, _) = axum::http::Request::builder()
+            .uri(
+                "/jobs?job_name=rust&seniority=senior&seniority=lead\
+                 &employment_type=part_time&language=da&salary_min=40000",
+            )
+            .body(())
+            .unwrap()
+            .into_parts();
+        let options = FetchOptions::from_request_parts(&mut parts, &())
+            .await
+            .expect("should parse");
+
+        assert_eq!(
+            options.filter_options.seniority,
+            [Seniority::Senior, Seniority::Lead]
+        );
+        assert_eq!(
+            options.filter_options.employment_types,
+            [EmploymentType::PartTime]
+        );
+        assert_eq!(options.filter_options.languages, [Language::Danish]);
+        assert_eq!(options.filter_options.salary_min, Some(40_000));
+        assert_eq!(options.filter_options.salary_max, None);
+
+        let error = serde_html_form::from_str::<FetchOptions>(
+            "job_name=rust&company=Acme",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("`company`"));
+    }
+    #[test]
+    fn commutes_need_a_home() {
+        let parse = serde_html_form::from_str::<JobFilter>;
+        let filter = parse("home=work&max_commute=45&sort=commute").unwrap();
+        assert_eq!(filter.home.as_deref(), Some("work"));
+        assert_eq!(filter.max_commute, Some(45.0));
+        assert_eq!(filter.sort, JobSort::Commute);
+
+        assert!(parse("max_commute=45").is_err());
+        assert!(parse("sort=commute").is_err());
+        assert!(parse("home=work&max_commute=0").is_err());
+        assert!(parse("home=work&sort=match").is_err());
+    }
+
+    #[test]
+    fn tag_strength_needs_tags() {
+        let parse = serde_html_form::from_str::<JobFilter>;
+        let filter = parse("job_tags=Rust&sort=tag_strength").unwrap();
+        assert_eq!(filter.sort, JobSort::TagStrength);
+        assert!(parse("sort=tag_strength").is_err());
+    }
+
+    #[tokio::test]
+    async fn job_filter_from_query() {
+        use axum::extract::FromRequestParts;
+
+        let (mut parts, _) = axum::http::Request::builder()
+            .uri(
+                "/jobs?job_tags=rust&job_tags=postgres&tag_mode=any\
+                 &company=Netcompany&posted_after=2025-10-01&remote=true\
+                 &sort=deadline&seniority=senior&page_size=20&page=1\
+                 &region=1035&region=16154&starred=true&hidden=only",
+            )
+            .body(())
+            .unwrap()
+            .into_parts();
+        let filter = JobFilter::from_request_parts(&mut parts, &())
+            .await
+            .expect("should parse");
+
+        assert_eq!(filter.job_tags, ["rust", "postgres"]);
+        assert_eq!(filter.regions, [1035, 16154]);
+        assert_eq!(filter.tag_mode, TagMode::Any);
+        assert_eq!(filter.company.as_deref(), Some("Netcompany"));
+        // midnight in Copenhagen, which is on summer time
+        assert_eq!(
+            filter.posted_after,
+            Some("2025-09-30T22:00:00Z".parse().unwrap())
+        );
+        assert_eq!(filter.deadline_before, None);
+        assert_eq!(filter.remote, Some(true));
+        assert_eq!(filter.sort, JobSort::Deadline);
+        assert!(filter.starred);
+        assert_eq!(filter.hidden, HiddenMode::Only);
+        assert_eq!(filter.viewer, None);
+        assert_eq!(filter.filter_options.seniority, [Seniority::Senior]);
+        assert!(matches!(
+            filter.size_options,
+            SizeOptions::Page {
+                page_size: 20,
+                page: 1
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn job_filter_rejects_bad_dates() {
+        use axum::extract::FromRequestParts;
+
+        let (mut parts, _) = axum::http::Request::builder()
+            .uri("/jobs?deadline_before=soon")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert!(
+            JobFilter::from_request_parts(&mut parts, &())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn job_filter_viewer_is_not_from_query() {
+        use axum::extract::FromRequestParts;
+
+        let (mut parts, _) = axum::http::Request::builder()
+            .uri("/jobs?starred=true&viewer=1")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert!(
+            JobFilter::from_request_parts(&mut parts, &())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn size_options_from_params() {
+        assert!(matches!(
+            SizeOptions::from_params(Some(10), Some(2), None),
+            Ok(SizeOptions::Page {
+                page_size: 10,
+                page: 2
+            })
+        ));
+        assert!(matches!(
+            SizeOptions::from_params(None, None, Some(5)),
+            Ok(SizeOptions::NotPaged { jobs: 5 })
+        ));
+        assert!(matches!(
+            SizeOptions::from_params(None, None, None),
+            Ok(SizeOptions::All)
+        ));
+        assert!(SizeOptions::from_params(Some(10), None, None).is_err());
+        assert!(SizeOptions::from_params(Some(10), Some(1), Some(5)).is_err());
+        assert!(
+            SizeOptions::from_params(Some(usize::MAX), Some(0), None).is_err()
+        );
+        assert!(
+            SizeOptions::from_params(Some(1 << 40), Some(1 << 40), None)
+                .is_err()
+        );
+        assert!(
+            SizeOptions::from_params(None, None, Some(usize::MAX)).is_err()
+        );
+    }
+
+    #[test]
+    fn limit_offset() {
+        let page = SizeOptions::Page {
+            page_size: 20,
+            page: 3,
+        };
+        assert_eq!(page.limit_offset(), (Some(20), 60));
+        assert_eq!(
+            SizeOptions::NotPaged { jobs: 7 }.limit_offset(),
+            (Some(7), 0)
+        );
+        assert_eq!(SizeOptions::All.limit_offset(), (None, 0));
+        let huge = SizeOptions::Page {
+            page_size: usize::MAX,
+            page: 2,
+        };
+        assert_eq!(huge.limit_offset(), (Some(i64::MAX), i64::MAX));
+    }
+
+    #[test]
+    fn page_of_items() {
+        let items: Vec<usize> = (0..10).collect();
+        let page = SizeOptions::Page {
+            page_size: 4,
+            page: 2,
+        };
+        assert_eq!(page.page(items.clone()), [8, 9]);
+        assert_eq!(
+            SizeOptions::NotPaged { jobs: 3 }.page(items.clone()),
+            [0, 1, 2]
+        );
+        assert_eq!(SizeOptions::All.page(items.clone()), items);
+    }
+
     #[test]
     fn first_page() {
         let size_options: SizeOptions = SizeOptions::Page {